@@ -0,0 +1,306 @@
+use crate::static_vars::ALIGNMENT_UPDATE_COUNT;
+use crate::structs::{AlignmentForm, AppSettings, VerseAlignment};
+use crate::utils::alignment::{
+    align_words, alignment_suggestions, find_verse, read_book_usfm, resolve_quote, unalign_words, verse_alignment,
+    write_verse_alignments,
+};
+use crate::utils::bcv_ref::canonical_book_code;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, full_repo_path, os_slash_str};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use crate::utils::tsv::{book_tsv_path, tsv_rows};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{get, post, State};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+// Serializes read-modify-write of aligned USFM
+static ALIGNMENT_LOCK: Mutex<()> = Mutex::new(());
+
+fn unknown_book_response(book: &str) -> status::Custom<(ContentType, String)> {
+    not_ok_json_response(
+        Status::BadRequest,
+        make_bad_json_data_response(format!("Unknown book '{}'", book)),
+    )
+}
+
+fn read_verse_alignment(
+    app_resources_dir: &str,
+    full_path: &str,
+    book: &str,
+    chapter: u16,
+    verse: u16,
+) -> Result<VerseAlignment, String> {
+    let (_, usfm) = read_book_usfm(app_resources_dir, full_path, book).map_err(|e| e.0)?;
+    let usfm_verse = find_verse(&usfm, chapter, verse).map_err(|e| e.0)?;
+    Ok(verse_alignment(&usfm_verse.usfm))
+}
+
+/// *`GET /verse/<repo_path>?book=TIT&chapter=1&verse=1`*
+///
+/// Typically mounted as **`/alignment/verse/<repo_path>?book=TIT&chapter=1&verse=1`**
+///
+/// Returns the target words of a verse, numbered by occurrence, and their alignments to source words, read from `\zaln` milestones in the USFM ingredient.
+///
+/// ```text
+/// {
+///   "words": [{"word": "Paul", "occurrence": 1, "occurrences": 1}, ...],
+///   "alignments": [
+///     {
+///       "sources": [{"content": "Παῦλος", "strong": "G39720", "lemma": "Παῦλος", "morph": "Gr,N,,,,,NMS,", "occurrence": 1, "occurrences": 1}],
+///       "targets": [{"word": "Paul", "occurrence": 1, "occurrences": 1}]
+///     }
+///   ]
+/// }
+/// ```
+#[get("/verse/<repo_path..>?<book>&<chapter>&<verse>")]
+pub fn get_verse_alignment(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    chapter: u16,
+    verse: u16,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let book_code = match canonical_book_code(&state.app_resources_dir, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    match read_verse_alignment(&state.app_resources_dir, &full_path, &book_code, chapter, verse) {
+        Ok(a) => ok_json_response(serde_json::to_string(&a).unwrap()),
+        Err(e) => not_ok_json_response(Status::NotFound, make_bad_json_data_response(e)),
+    }
+}
+
+/// *`POST /align/<repo_path>?book=TIT&chapter=1&verse=1`*
+///
+/// Typically mounted as **`/alignment/align/<repo_path>?book=TIT&chapter=1&verse=1`**
+///
+/// Aligns target words of a verse to source words, from JSON with *sources* and *targets*, and returns the verse alignment.
+/// Target words are removed from any previous alignment. Target occurrences default to 1.
+///
+/// ```text
+/// {
+///   "sources": [{"content": "Παῦλος", "strong": "G39720", "lemma": "Παῦλος", "occurrence": 1, "occurrences": 1}],
+///   "targets": [{"word": "Paul", "occurrence": 1}]
+/// }
+/// ```
+#[post("/align/<repo_path..>?<book>&<chapter>&<verse>", format = "json", data = "<json_form>")]
+pub fn post_align(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    chapter: u16,
+    verse: u16,
+    json_form: Json<AlignmentForm>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let book_code = match canonical_book_code(&state.app_resources_dir, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    let _lock = ALIGNMENT_LOCK.lock().unwrap();
+    let current = match read_verse_alignment(&state.app_resources_dir, &full_path, &book_code, chapter, verse) {
+        Ok(a) => a,
+        Err(e) => return not_ok_json_response(Status::NotFound, make_bad_json_data_response(e)),
+    };
+    let alignments = match align_words(
+        &current,
+        json_form.sources.as_deref().unwrap_or(&[]),
+        json_form.targets.as_deref().unwrap_or(&[]),
+    ) {
+        Ok(a) => a,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    if let Err(e) = write_verse_alignments(&state.app_resources_dir, &full_path, &book_code, chapter, verse, &alignments) {
+        return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0));
+    }
+    ALIGNMENT_UPDATE_COUNT.fetch_add(1, Ordering::Relaxed);
+    match read_verse_alignment(&state.app_resources_dir, &full_path, &book_code, chapter, verse) {
+        Ok(a) => ok_json_response(serde_json::to_string(&a).unwrap()),
+        Err(e) => not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e)),
+    }
+}
+
+/// *`POST /unalign/<repo_path>?book=TIT&chapter=1&verse=1`*
+///
+/// Typically mounted as **`/alignment/unalign/<repo_path>?book=TIT&chapter=1&verse=1`**
+///
+/// Removes alignments from a verse, from JSON with optional *sources* and *targets*, and returns the verse alignment.
+/// Alignments of the source words are removed, and the target words are removed from their alignments.
+/// When neither is provided, every alignment in the verse is removed.
+#[post("/unalign/<repo_path..>?<book>&<chapter>&<verse>", format = "json", data = "<json_form>")]
+pub fn post_unalign(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    chapter: u16,
+    verse: u16,
+    json_form: Json<AlignmentForm>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let book_code = match canonical_book_code(&state.app_resources_dir, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    let _lock = ALIGNMENT_LOCK.lock().unwrap();
+    let current = match read_verse_alignment(&state.app_resources_dir, &full_path, &book_code, chapter, verse) {
+        Ok(a) => a,
+        Err(e) => return not_ok_json_response(Status::NotFound, make_bad_json_data_response(e)),
+    };
+    let alignments = if json_form.sources.is_none() && json_form.targets.is_none() {
+        vec![]
+    } else {
+        unalign_words(&current, &json_form.sources, &json_form.targets)
+    };
+    if let Err(e) = write_verse_alignments(&state.app_resources_dir, &full_path, &book_code, chapter, verse, &alignments) {
+        return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0));
+    }
+    ALIGNMENT_UPDATE_COUNT.fetch_add(1, Ordering::Relaxed);
+    match read_verse_alignment(&state.app_resources_dir, &full_path, &book_code, chapter, verse) {
+        Ok(a) => ok_json_response(serde_json::to_string(&a).unwrap()),
+        Err(e) => not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e)),
+    }
+}
+
+/// *`POST /suggest/<repo_path>?book=TIT&chapter=1&verse=1`*
+///
+/// Typically mounted as **`/alignment/suggest/<repo_path>?book=TIT&chapter=1&verse=1`**
+///
+/// Suggests alignments for the source words of a verse, provided as JSON with *sources*, from alignments already made in the repo.
+/// Only unaligned target words are suggested. *count* is the number of times the same decision was made before.
+///
+/// ```text
+/// [
+///   {
+///     "sources": [{"content": "Θεοῦ", "strong": "G23160", "lemma": "θεός", "occurrence": 1, "occurrences": 1}],
+///     "targets": [{"word": "God", "occurrence": 1, "occurrences": 1}],
+///     "count": 12
+///   }
+/// ]
+/// ```
+#[post("/suggest/<repo_path..>?<book>&<chapter>&<verse>", format = "json", data = "<json_form>")]
+pub fn post_suggest_alignments(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    chapter: u16,
+    verse: u16,
+    json_form: Json<AlignmentForm>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let book_code = match canonical_book_code(&state.app_resources_dir, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    let current = match read_verse_alignment(&state.app_resources_dir, &full_path, &book_code, chapter, verse) {
+        Ok(a) => a,
+        Err(e) => return not_ok_json_response(Status::NotFound, make_bad_json_data_response(e)),
+    };
+    let suggestions = alignment_suggestions(&full_path, &current, json_form.sources.as_deref().unwrap_or(&[]));
+    ok_json_response(serde_json::to_string(&suggestions).unwrap())
+}
+
+/// *`GET /resolve-quote/<repo_path>?book=TIT&chapter=1&verse=1&quote=Θεοῦ&occurrence=1`*
+///
+/// *`GET /resolve-quote/<repo_path>?book=TIT&notes=git.door43.org/unfoldingWord/en_tn&id=rtc9`*
+///
+/// Typically mounted as **`/alignment/resolve-quote/<repo_path>?...`**
+///
+/// Resolves an original-language quote to the aligned target words of a verse in the textTranslation burrito at *repo_path*.
+/// The quote is given with *chapter*, *verse*, *quote* and *occurrence* (default 1), or read from the note with ID *id* in the TSV
+/// for *book* in the translation notes repo *notes*. *text* may be passed to `/app-state/snippet` to highlight the words.
+///
+/// ```text
+/// {
+///   "quote": "Θεοῦ",
+///   "occurrence": 1,
+///   "sources": [{"content": "Θεοῦ", "strong": "G23160", "lemma": "θεός", "morph": "Gr,N,,,,,GMS,", "occurrence": 1, "occurrences": 2}],
+///   "targets": [{"word": "God", "occurrence": 1, "occurrences": 2}],
+///   "text": "God",
+///   "missing": []
+/// }
+/// ```
+#[get("/resolve-quote/<repo_path..>?<book>&<chapter>&<verse>&<quote>&<occurrence>&<notes>&<id>")]
+#[allow(clippy::too_many_arguments)]
+pub fn get_resolved_quote(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    chapter: Option<u16>,
+    verse: Option<u16>,
+    quote: Option<String>,
+    occurrence: Option<i32>,
+    notes: Option<String>,
+    id: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let book_code = match canonical_book_code(&state.app_resources_dir, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    let (chapter, verse, quote, occurrence) = match (notes, id) {
+        (Some(notes_repo_path), Some(note_id)) => {
+            if !check_path_components(&mut PathBuf::from(&notes_repo_path).components()) {
+                return not_ok_bad_repo_json_response();
+            }
+            let notes_path = format!("{}{}{}", state.repo_dir.lock().unwrap().clone(), os_slash_str(), notes_repo_path);
+            let tsv = match book_tsv_path(&notes_path, &book_code).and_then(|p| std::fs::read_to_string(p).ok()) {
+                Some(t) => t,
+                None => {
+                    return not_ok_json_response(
+                        Status::NotFound,
+                        make_bad_json_data_response(format!("No notes TSV for {}", book_code)),
+                    )
+                }
+            };
+            match tsv_rows(&tsv).into_iter().find(|r| r.cell(&["ID"]).as_ref() == Some(&note_id)) {
+                Some(row) => (
+                    row.chapter,
+                    row.verse,
+                    row.cell(&["Quote", "OrigQuote", "OrigWords"]).unwrap_or_default(),
+                    row.cell(&["Occurrence"]).and_then(|o| o.parse().ok()).unwrap_or(1),
+                ),
+                None => {
+                    return not_ok_json_response(
+                        Status::NotFound,
+                        make_bad_json_data_response(format!("No note with ID '{}'", note_id)),
+                    )
+                }
+            }
+        }
+        _ => match (chapter, verse, quote) {
+            (Some(c), Some(v), Some(q)) => (c, v, q, occurrence.unwrap_or(1)),
+            _ => {
+                return not_ok_json_response(
+                    Status::BadRequest,
+                    make_bad_json_data_response(
+                        "Either chapter, verse and quote, or notes and id, must be provided".to_string(),
+                    ),
+                )
+            }
+        },
+    };
+    match read_verse_alignment(&state.app_resources_dir, &full_path, &book_code, chapter, verse) {
+        Ok(a) => ok_json_response(serde_json::to_string(&resolve_quote(&a, &quote, occurrence)).unwrap()),
+        Err(e) => not_ok_json_response(Status::NotFound, make_bad_json_data_response(e)),
+    }
+}
@@ -0,0 +1,293 @@
+use crate::static_vars::BOOKMARKS_UPDATE_COUNT;
+use crate::structs::{AppSettings, Bookmark, BookmarkForm, PankosmiaError};
+use crate::utils::files::write_file_atomically;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::bookmarks_path;
+use crate::utils::response::{not_ok_json_response, ok_json_response, ok_ok_json_response};
+use crate::utils::time::utc_now_timestamp_string;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{get, post, State};
+use serde_json::json;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+// Serializes read-modify-write of the bookmarks file
+static BOOKMARKS_LOCK: Mutex<()> = Mutex::new(());
+
+// A missing bookmarks file means no bookmarks, but an unreadable one is an error, so that it is never overwritten
+fn read_bookmarks(working_dir: &String) -> Result<Vec<Bookmark>, PankosmiaError> {
+    let path_to_bookmarks = bookmarks_path(working_dir);
+    match std::fs::read_to_string(&path_to_bookmarks) {
+        Ok(s) => serde_json::from_str(&s)
+            .map_err(|e| PankosmiaError(format!("Could not parse bookmarks in '{}': {}", path_to_bookmarks, e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(PankosmiaError(format!("Could not read bookmarks from '{}': {}", path_to_bookmarks, e))),
+    }
+}
+
+fn write_bookmarks(working_dir: &String, bookmarks: &[Bookmark]) -> Result<(), std::io::Error> {
+    write_file_atomically(&bookmarks_path(working_dir), &serde_json::to_string_pretty(bookmarks)?)?;
+    BOOKMARKS_UPDATE_COUNT.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+fn not_ok_bookmarks_response(e: PankosmiaError) -> status::Custom<(ContentType, String)> {
+    not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0))
+}
+
+fn clean_tags(tags: &Option<Vec<String>>) -> Vec<String> {
+    let mut cleaned: Vec<String> = match tags {
+        Some(t) => t
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect(),
+        None => vec![],
+    };
+    cleaned.sort();
+    cleaned.dedup();
+    cleaned
+}
+
+/// *`GET /?tag=checking&book=JHN&source=_local_&organization=_local_&project=my_project`*
+///
+/// Typically mounted as **`/bookmarks?tag=checking&book=JHN&source=_local_&organization=_local_&project=my_project`**
+///
+/// Returns bookmarks and notes, optionally filtered by tag, book and project. Bookmarks are stored in the working dir, not in any burrito.
+///
+/// ```text
+/// [
+///   {
+///     "id": "9d6f...",
+///     "bcv": {"book_code": "JHN", "chapter": 3, "verse": 16, "to_verse": 16},
+///     "project": {"source": "_local_", "organization": "_local_", "project": "my_project"},
+///     "note": "Check 'world' against the glossary",
+///     "tags": ["checking"],
+///     "created": "2025-03-01T10:00:00.000Z",
+///     "updated": "2025-03-01T10:00:00.000Z"
+///   }
+/// ]
+/// ```
+#[get("/?<tag>&<book>&<source>&<organization>&<project>")]
+pub fn list_bookmarks(
+    state: &State<AppSettings>,
+    tag: Option<String>,
+    book: Option<String>,
+    source: Option<String>,
+    organization: Option<String>,
+    project: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let _lock = BOOKMARKS_LOCK.lock().unwrap();
+    let bookmarks: Vec<Bookmark> = match read_bookmarks(&state.working_dir) {
+        Ok(b) => b,
+        Err(e) => return not_ok_bookmarks_response(e),
+    };
+    let bookmarks: Vec<Bookmark> = bookmarks
+        .into_iter()
+        .filter(|b| match &tag {
+            Some(t) => b.tags.contains(t),
+            None => true,
+        })
+        .filter(|b| match &book {
+            Some(bk) => b.bcv.book_code == bk.to_uppercase(),
+            None => true,
+        })
+        .filter(|b| {
+            if source.is_none() && organization.is_none() && project.is_none() {
+                return true;
+            }
+            match &b.project {
+                Some(p) => {
+                    source.as_ref().is_none_or(|s| *s == p.source)
+                        && organization.as_ref().is_none_or(|o| *o == p.organization)
+                        && project.as_ref().is_none_or(|pr| *pr == p.project)
+                }
+                None => false,
+            }
+        })
+        .collect();
+    ok_json_response(serde_json::to_string(&bookmarks).unwrap())
+}
+
+/// *`GET /item/<id>`*
+///
+/// Typically mounted as **`/bookmarks/item/<id>`**
+///
+/// Returns one bookmark.
+#[get("/item/<id>")]
+pub fn get_bookmark(state: &State<AppSettings>, id: String) -> status::Custom<(ContentType, String)> {
+    let _lock = BOOKMARKS_LOCK.lock().unwrap();
+    let bookmarks = match read_bookmarks(&state.working_dir) {
+        Ok(b) => b,
+        Err(e) => return not_ok_bookmarks_response(e),
+    };
+    match bookmarks.into_iter().find(|b| b.id == id) {
+        Some(b) => ok_json_response(serde_json::to_string(&b).unwrap()),
+        None => not_ok_json_response(
+            Status::NotFound,
+            make_bad_json_data_response(format!("No bookmark with id '{}'", id)),
+        ),
+    }
+}
+
+/// *`POST /new`*
+///
+/// Typically mounted as **`/bookmarks/new`**
+///
+/// Adds a bookmark from JSON with *bcv* and optional *project*, *note* and *tags*, and returns it.
+///
+/// ```text
+/// {
+///   "bcv": {"book_code": "JHN", "chapter": 3, "verse": 16, "to_verse": 16},
+///   "note": "Check 'world' against the glossary",
+///   "tags": ["checking"]
+/// }
+/// ```
+#[post("/new", format = "json", data = "<json_form>")]
+pub fn post_new_bookmark(
+    state: &State<AppSettings>,
+    json_form: Json<BookmarkForm>,
+) -> status::Custom<(ContentType, String)> {
+    let _lock = BOOKMARKS_LOCK.lock().unwrap();
+    let mut bookmarks = match read_bookmarks(&state.working_dir) {
+        Ok(b) => b,
+        Err(e) => return not_ok_bookmarks_response(e),
+    };
+    let now = utc_now_timestamp_string();
+    let mut bcv = json_form.bcv.clone();
+    bcv.book_code = bcv.book_code.to_uppercase();
+    let bookmark = Bookmark {
+        id: Uuid::new_v4().to_string(),
+        bcv,
+        project: json_form.project.clone(),
+        note: json_form.note.clone(),
+        tags: clean_tags(&json_form.tags),
+        created: now.clone(),
+        updated: now,
+    };
+    bookmarks.push(bookmark.clone());
+    match write_bookmarks(&state.working_dir, &bookmarks) {
+        Ok(_) => ok_json_response(serde_json::to_string(&bookmark).unwrap()),
+        Err(e) => not_ok_json_response(
+            Status::InternalServerError,
+            make_bad_json_data_response(format!("Could not write bookmarks: {}", e)),
+        ),
+    }
+}
+
+/// *`POST /update/<id>`*
+///
+/// Typically mounted as **`/bookmarks/update/<id>`**
+///
+/// Replaces the reference, project, note and tags of a bookmark, using the same JSON as `/bookmarks/new`.
+#[post("/update/<id>", format = "json", data = "<json_form>")]
+pub fn post_update_bookmark(
+    state: &State<AppSettings>,
+    id: String,
+    json_form: Json<BookmarkForm>,
+) -> status::Custom<(ContentType, String)> {
+    let _lock = BOOKMARKS_LOCK.lock().unwrap();
+    let mut bookmarks = match read_bookmarks(&state.working_dir) {
+        Ok(b) => b,
+        Err(e) => return not_ok_bookmarks_response(e),
+    };
+    let bookmark = match bookmarks.iter_mut().find(|b| b.id == id) {
+        Some(b) => b,
+        None => {
+            return not_ok_json_response(
+                Status::NotFound,
+                make_bad_json_data_response(format!("No bookmark with id '{}'", id)),
+            )
+        }
+    };
+    bookmark.bcv = json_form.bcv.clone();
+    bookmark.bcv.book_code = bookmark.bcv.book_code.to_uppercase();
+    bookmark.project = json_form.project.clone();
+    bookmark.note = json_form.note.clone();
+    bookmark.tags = clean_tags(&json_form.tags);
+    bookmark.updated = utc_now_timestamp_string();
+    let updated = bookmark.clone();
+    match write_bookmarks(&state.working_dir, &bookmarks) {
+        Ok(_) => ok_json_response(serde_json::to_string(&updated).unwrap()),
+        Err(e) => not_ok_json_response(
+            Status::InternalServerError,
+            make_bad_json_data_response(format!("Could not write bookmarks: {}", e)),
+        ),
+    }
+}
+
+/// *`POST /delete/<id>`*
+///
+/// Typically mounted as **`/bookmarks/delete/<id>`**
+///
+/// Deletes a bookmark.
+#[post("/delete/<id>")]
+pub fn post_delete_bookmark(state: &State<AppSettings>, id: String) -> status::Custom<(ContentType, String)> {
+    let _lock = BOOKMARKS_LOCK.lock().unwrap();
+    let mut bookmarks = match read_bookmarks(&state.working_dir) {
+        Ok(b) => b,
+        Err(e) => return not_ok_bookmarks_response(e),
+    };
+    let before = bookmarks.len();
+    bookmarks.retain(|b| b.id != id);
+    if bookmarks.len() == before {
+        return not_ok_json_response(
+            Status::NotFound,
+            make_bad_json_data_response(format!("No bookmark with id '{}'", id)),
+        );
+    }
+    match write_bookmarks(&state.working_dir, &bookmarks) {
+        Ok(_) => ok_ok_json_response(),
+        Err(e) => not_ok_json_response(
+            Status::InternalServerError,
+            make_bad_json_data_response(format!("Could not write bookmarks: {}", e)),
+        ),
+    }
+}
+
+/// *`GET /tags`*
+///
+/// Typically mounted as **`/bookmarks/tags`**
+///
+/// Returns the tags in use, with the number of bookmarks for each.
+///
+/// `{"checking": 3, "question": 1}`
+#[get("/tags")]
+pub fn list_bookmark_tags(state: &State<AppSettings>) -> status::Custom<(ContentType, String)> {
+    let _lock = BOOKMARKS_LOCK.lock().unwrap();
+    let mut tags = std::collections::BTreeMap::new();
+    let bookmarks = match read_bookmarks(&state.working_dir) {
+        Ok(b) => b,
+        Err(e) => return not_ok_bookmarks_response(e),
+    };
+    for bookmark in bookmarks {
+        for tag in bookmark.tags {
+            *tags.entry(tag).or_insert(0) += 1;
+        }
+    }
+    ok_json_response(serde_json::to_string(&tags).unwrap())
+}
+
+/// *`GET /export`*
+///
+/// Typically mounted as **`/bookmarks/export`**
+///
+/// Returns all bookmarks as a JSON document for backup or sharing.
+///
+/// `{"exported": "2025-03-01T10:00:00.000Z", "bookmarks": [...]}`
+#[get("/export")]
+pub fn export_bookmarks(state: &State<AppSettings>) -> status::Custom<(ContentType, String)> {
+    let _lock = BOOKMARKS_LOCK.lock().unwrap();
+    let bookmarks = match read_bookmarks(&state.working_dir) {
+        Ok(b) => b,
+        Err(e) => return not_ok_bookmarks_response(e),
+    };
+    let export_json = json!({
+        "exported": utc_now_timestamp_string(),
+        "bookmarks": bookmarks
+    });
+    ok_json_response(serde_json::to_string_pretty(&export_json).unwrap())
+}
@@ -1,6 +1,5 @@
 use crate::structs::AppSettings;
-use crate::utils::burrito_api::checks::basic_shape::check_basic_shape;
-use crate::utils::burrito_api::checks::metadata_validation::check_metadata_validation;
+use crate::utils::burrito_api::checks::audit_report;
 use crate::utils::paths::{check_path_components, os_slash_str};
 use crate::utils::response::{not_ok_bad_repo_json_response, ok_json_response};
 use rocket::http::ContentType;
@@ -23,8 +22,7 @@ pub async fn audit(
         let burrito_path = state.repo_dir.lock().unwrap().clone()
             + os_slash_str()
             + &repo_path.display().to_string();
-        let mut report = check_basic_shape(burrito_path.clone());
-        report.extend(check_metadata_validation(burrito_path).iter().cloned());
+        let report = audit_report(&state.app_resources_dir, burrito_path);
         ok_json_response(serde_json::to_string(&report).unwrap())
     } else {
         not_ok_bad_repo_json_response()
@@ -0,0 +1,108 @@
+use crate::structs::AppSettings;
+use crate::structs::BytesOrError;
+use crate::utils::html_export::{html_single_page, write_html_site};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::zip::make_zip_file;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, State};
+use serde_json::{json, Value};
+use std::path::{Components, PathBuf};
+use uuid::Uuid;
+
+fn bad_html_export_response(status: Status, reason: String) -> status::Custom<(ContentType, BytesOrError)> {
+    status::Custom(
+        status,
+        (
+            ContentType::JSON,
+            BytesOrError::Error(make_bad_json_data_response(reason)),
+        ),
+    )
+}
+
+/// *`GET /export/html/<repo_path>?output=zip`*
+///
+/// Typically mounted as **`/burrito/export/html/<repo_path>?output=zip`**
+///
+/// Renders the USFM books of a repo as HTML, using the current typography settings.
+/// - With *output* `zip` (the default), returns a zip of a static site with an index page, a page per book and customized webfonts.
+/// - With *output* `temp`, writes a single page as a temp file, and returns its id, which can be viewed at `/temp/html/<uuid>`.
+/// ```text
+/// {"uuid": "0d3c7a1e-..."}
+/// ```
+#[get("/export/html/<repo_path..>?<output>")]
+pub async fn get_exported_html(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    output: Option<String>,
+) -> status::Custom<(ContentType, BytesOrError)> {
+    let path_components: Components<'_> = repo_path.components();
+    let path_to_repo = format!(
+        "{}{}{}",
+        state.repo_dir.lock().unwrap().clone(),
+        os_slash_str(),
+        &repo_path.display().to_string()
+    );
+    if !check_path_components(&mut path_components.clone())
+        || !std::path::Path::new(&path_to_repo).is_dir()
+    {
+        return bad_html_export_response(Status::BadRequest, "bad repo path".to_string());
+    }
+    let path_to_repo_metadata = format!("{}{}metadata.json", &path_to_repo, os_slash_str());
+    let metadata: Value = match std::fs::read_to_string(&path_to_repo_metadata)
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(e) => return bad_html_export_response(Status::InternalServerError, format!("Could not read metadata: {}", e)),
+    };
+    let typography = state.typography.lock().unwrap().clone();
+    match output.unwrap_or("zip".to_string()).as_str() {
+        "zip" => {
+            let export_dir = match tempfile::tempdir() {
+                Ok(d) => d,
+                Err(e) => {
+                    return bad_html_export_response(
+                        Status::InternalServerError,
+                        format!("Could not make export directory: {}", e),
+                    )
+                }
+            };
+            let export_path = export_dir.path().display().to_string();
+            if let Err(e) = write_html_site(&state.app_resources_dir, &path_to_repo, &metadata, &typography, &export_path) {
+                return bad_html_export_response(Status::BadRequest, e.0);
+            }
+            let temp_zip_path = make_zip_file(&export_path);
+            match std::fs::read(&temp_zip_path) {
+                Ok(b) => status::Custom(Status::Ok, (ContentType::ZIP, BytesOrError::Bytes(b))),
+                Err(e) => bad_html_export_response(Status::InternalServerError, format!("Could not read zip: {}", e)),
+            }
+        }
+        "temp" => {
+            let page = match html_single_page(&path_to_repo, &metadata, &typography, "/api/webfonts") {
+                Ok(p) => p,
+                Err(e) => return bad_html_export_response(Status::BadRequest, e.0),
+            };
+            let temp_id = Uuid::new_v4().to_string();
+            let destination = format!(
+                "{}{}temp{}{}",
+                state.working_dir.clone(),
+                os_slash_str(),
+                os_slash_str(),
+                &temp_id
+            );
+            match std::fs::write(destination, page) {
+                Ok(_) => status::Custom(
+                    Status::Ok,
+                    (
+                        ContentType::JSON,
+                        BytesOrError::Bytes(json!({"uuid": temp_id}).to_string().into_bytes()),
+                    ),
+                ),
+                Err(e) => bad_html_export_response(Status::InternalServerError, format!("Could not write: {}", e)),
+            }
+        }
+        other => bad_html_export_response(Status::BadRequest, format!("Unknown output '{}'", other)),
+    }
+}
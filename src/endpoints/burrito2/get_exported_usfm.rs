@@ -0,0 +1,78 @@
+use crate::structs::AppSettings;
+use crate::structs::BytesOrError;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::usj::export_usfm_ingredients;
+use crate::utils::zip::make_zip_file;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, State};
+use std::path::{Components, PathBuf};
+
+/// *`GET /export/<format>/<repo_path>?book=TIT`*
+///
+/// Typically mounted as **`/burrito/export/<format>/<repo_path>?book=TIT`**
+///
+/// Returns a zip of the USFM ingredients of a repo converted to *format*, which is `usx` or `usj`. If *book* is provided, only that book is converted.
+#[get("/export/<format>/<repo_path..>?<book>", rank = 2)]
+pub async fn get_exported_usfm(
+    state: &State<AppSettings>,
+    format: String,
+    repo_path: PathBuf,
+    book: Option<String>,
+) -> status::Custom<(ContentType, BytesOrError)> {
+    let path_components: Components<'_> = repo_path.components();
+    let path_to_repo = format!(
+        "{}{}{}",
+        state.repo_dir.lock().unwrap().clone(),
+        os_slash_str(),
+        &repo_path.display().to_string()
+    );
+    if !check_path_components(&mut path_components.clone())
+        || !std::path::Path::new(&path_to_repo).is_dir()
+    {
+        return status::Custom(
+            Status::BadRequest,
+            (
+                ContentType::JSON,
+                BytesOrError::Error(make_bad_json_data_response("bad repo path".to_string())),
+            ),
+        );
+    }
+    let export_dir = match tempfile::tempdir() {
+        Ok(d) => d,
+        Err(e) => {
+            return status::Custom(
+                Status::InternalServerError,
+                (
+                    ContentType::JSON,
+                    BytesOrError::Error(make_bad_json_data_response(format!(
+                        "Could not make export directory: {}",
+                        e
+                    ))),
+                ),
+            )
+        }
+    };
+    let export_path = export_dir.path().display().to_string();
+    if let Err(e) = export_usfm_ingredients(&path_to_repo, format.as_str(), &book, &export_path) {
+        return status::Custom(
+            Status::BadRequest,
+            (
+                ContentType::JSON,
+                BytesOrError::Error(make_bad_json_data_response(e.0)),
+            ),
+        );
+    }
+    let temp_zip_path = make_zip_file(&export_path);
+    match std::fs::read(&temp_zip_path) {
+        Ok(b) => status::Custom(Status::Ok, (ContentType::ZIP, BytesOrError::Bytes(b))),
+        Err(e) => status::Custom(
+            Status::InternalServerError,
+            (
+                ContentType::JSON,
+                BytesOrError::Error(make_bad_json_data_response(format!("Could not read zip: {}", e))),
+            ),
+        ),
+    }
+}
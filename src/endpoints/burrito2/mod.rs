@@ -1,6 +1,7 @@
 pub mod raw_metadata;
 pub mod summary_metadata;
 pub mod summary_metadatas;
+pub mod summary_metadata_index;
 pub mod raw_text_ingredient;
 pub mod raw_text_ingredients;
 pub mod raw_bytes_ingredient;
@@ -14,8 +15,25 @@ pub mod post_revert_ingredient;
 pub mod post_copy_ingredient;
 pub mod audit;
 pub mod post_remake_ingredients_metadata;
+pub mod post_remake_localized_names;
 pub mod post_zipped_ingredient;
 pub mod get_zipped_ingredients;
 pub mod get_zipped_repo;
 pub mod post_zipped_repo;
-pub mod remake_burrito_from_zip;
\ No newline at end of file
+pub mod remake_burrito_from_zip;
+pub mod repair;
+pub mod post_metadata_identification;
+pub mod post_metadata_languages;
+pub mod post_metadata_copyright;
+pub mod post_metadata_localized_names;
+pub mod post_metadata_confidential;
+pub mod verses;
+pub mod post_verses;
+pub mod get_exported_usfm;
+pub mod post_exported_usfm_burrito;
+pub mod get_exported_html;
+pub mod plan_progress;
+pub mod stats;
+pub mod post_transfer_ingredients;
+pub mod post_split_burrito;
+pub mod post_merge_burritos;
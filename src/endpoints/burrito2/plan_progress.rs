@@ -0,0 +1,79 @@
+use crate::structs::AppSettings;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::plan_progress::{plan_progress, plan_translation_repo_path};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use crate::utils::time::utc_now_timestamp_string;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, State};
+use serde_json::Value;
+use std::path::{Components, PathBuf};
+
+/// *`GET /plan-progress/<repo_path>?translation=_local_/_local_/my_bible`*
+///
+/// Typically mounted as **`/burrito/plan-progress/<repo_path>?translation=_local_/_local_/my_bible`**
+///
+/// Compares the x-translationplan repo at *repo_path* with the textTranslation repo it tracks, which is *translation* or,
+/// by default, the `repo_path` or `translation` field of `ingredients/plan.json`. Returns
+/// - verses drafted against verses expected from the translation versification, overall, by book and chapter, and by plan section
+/// - the number of commits whose message names each plan stage
+/// - the audit status of the translation
+/// - overdue sections (with a past `due` date and not fully drafted) and stages (with a past `due` date and no commits)
+///
+/// ```text
+/// {
+///   "translation_repo_path": "_local_/_local_/my_bible",
+///   "expected": 46,
+///   "drafted": 23,
+///   "percent": 50.0,
+///   "books": [{"book_code": "TIT", "expected": 46, "drafted": 23, "percent": 50.0, "chapters": [...]}],
+///   "sections": [{"book_code": "TIT", "start": "1:1", "end": "1:16", "expected": 16, "drafted": 16, "percent": 100.0, "due": "2025-03-01", "overdue": false}],
+///   "stages": [{"name": "Draft", "commits": 12, "last_commit": 1740823200, "due": null, "overdue": false}],
+///   "audit": {"success": true, "failed_checks": []},
+///   "overdue": []
+/// }
+/// ```
+#[get("/plan-progress/<repo_path..>?<translation>")]
+pub async fn plan_progress_report(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    translation: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let path_to_repo = format!("{}{}{}", repo_dir, os_slash_str(), &repo_path.display().to_string());
+    if !check_path_components(&mut path_components.clone()) || !std::path::Path::new(&path_to_repo).is_dir() {
+        return not_ok_bad_repo_json_response();
+    }
+    let path_to_plan = format!("{}{}ingredients{}plan.json", path_to_repo, os_slash_str(), os_slash_str());
+    let plan: Value = match std::fs::read_to_string(&path_to_plan)
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response(format!("Could not read plan: {}", e)),
+            )
+        }
+    };
+    let translation_repo_path = match translation.or(plan_translation_repo_path(&plan)) {
+        Some(t) => t,
+        None => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response("No translation repo in plan or request".to_string()),
+            )
+        }
+    };
+    if !check_path_components(&mut PathBuf::from(&translation_repo_path).components()) {
+        return not_ok_bad_repo_json_response();
+    }
+    let today = utc_now_timestamp_string()[..10].to_string();
+    match plan_progress(&state.app_resources_dir, &plan, &repo_dir, &translation_repo_path, &today) {
+        Ok(progress) => ok_json_response(serde_json::to_string(&progress).unwrap()),
+        Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    }
+}
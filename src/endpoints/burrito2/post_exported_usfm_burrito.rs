@@ -0,0 +1,149 @@
+use crate::structs::{AppSettings, PankosmiaError};
+use crate::utils::burrito::{edit_metadata, refresh_ingredients_in_metadata_value};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::local_repo::{add_and_commit_all, init_local_repo, new_local_repo_path};
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use crate::utils::time::utc_now_timestamp_string;
+use crate::utils::usj::export_usfm_ingredients;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{post, State};
+use serde_json::{json, Value};
+use std::path::{Components, PathBuf};
+use walkdir::WalkDir;
+
+// Copies everything except USFM, backups and metadata from the source repo, then adds converted books and rewrites metadata
+fn make_exported_burrito(
+    app_resources_dir: &str,
+    source_repo_path: &String,
+    new_repo_path: &String,
+    format: &str,
+    abbr: &str,
+    source_metadata: &Value,
+) -> Result<(), PankosmiaError> {
+    let new_repo = init_local_repo(new_repo_path)?;
+    for entry in WalkDir::new(source_repo_path).into_iter().filter_entry(|e| e.file_name() != ".git") {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => return Err(PankosmiaError(format!("Could not read source repo: {}", e))),
+        };
+        let relative_path = entry.path().strip_prefix(source_repo_path).unwrap().to_path_buf();
+        let relative_string = relative_path.display().to_string();
+        if !entry.path().is_file()
+            || relative_string == "metadata.json"
+            || relative_string.ends_with(".usfm")
+            || relative_string.ends_with(".bak")
+        {
+            continue;
+        }
+        let target_path = std::path::Path::new(new_repo_path).join(&relative_path);
+        if let Some(parent) = target_path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(PankosmiaError(format!("Could not create directory in new repo: {}", e)));
+            }
+        }
+        if let Err(e) = std::fs::copy(entry.path(), &target_path) {
+            return Err(PankosmiaError(format!("Could not copy {}: {}", relative_string, e)));
+        }
+    }
+    let path_to_new_ingredients = format!("{}{}ingredients", new_repo_path, os_slash_str());
+    export_usfm_ingredients(source_repo_path, format, &None, &path_to_new_ingredients)?;
+    let path_to_new_metadata = format!("{}{}metadata.json", new_repo_path, os_slash_str());
+    if let Err(e) = std::fs::write(&path_to_new_metadata, source_metadata.to_string()) {
+        return Err(PankosmiaError(format!("Could not write metadata to new repo: {}", e)));
+    }
+    edit_metadata(app_resources_dir, new_repo_path, |metadata| {
+        refresh_ingredients_in_metadata_value(app_resources_dir.to_string(), new_repo_path.clone(), metadata);
+        metadata["meta"]["dateCreated"] = json!(utc_now_timestamp_string());
+        if let Some(abbreviations) = metadata["identification"]["abbreviation"].as_object_mut() {
+            for (_, value) in abbreviations.iter_mut() {
+                *value = json!(abbr);
+            }
+        }
+        if let Some(names) = metadata["identification"]["name"].as_object_mut() {
+            for (_, value) in names.iter_mut() {
+                if let Some(name) = value.as_str() {
+                    *value = json!(format!("{} ({})", name, format.to_uppercase()));
+                }
+            }
+        }
+        Ok(())
+    })?;
+    add_and_commit_all(&new_repo, format!("Export to {}", format.to_uppercase()).as_str())
+}
+
+/// *`POST /export/<format>/<repo_path>?abbr=MYBIBLE_usx`*
+///
+/// Typically mounted as **`/burrito/export/<format>/<repo_path>?abbr=MYBIBLE_usx`**
+///
+/// Makes a new local textTranslation repo in which the USFM ingredients of a repo are converted to *format*, which is `usx` or `usj`.
+/// Other ingredients are copied, and ingredients metadata, including mimeTypes, is rebuilt. The new repo is called *abbr*,
+/// which defaults to the name of the source repo followed by the format. Returns the repo path of the new repo.
+/// ```text
+/// {"repo_path": "_local_/_local_/MYBIBLE_usx"}
+/// ```
+#[post("/export/<format>/<repo_path..>?<abbr>")]
+pub async fn post_exported_usfm_burrito(
+    state: &State<AppSettings>,
+    format: String,
+    repo_path: PathBuf,
+    abbr: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let full_repo_path = format!("{}{}{}", repo_dir, os_slash_str(), &repo_path.display().to_string());
+    if !check_path_components(&mut path_components.clone())
+        || !std::path::Path::new(&full_repo_path).is_dir()
+    {
+        return not_ok_bad_repo_json_response();
+    }
+    if format != "usx" && format != "usj" {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response(format!("Unknown export format '{}'", format)),
+        );
+    }
+    let path_to_repo_metadata = format!("{}{}metadata.json", &full_repo_path, os_slash_str());
+    let source_metadata: Value = match std::fs::read_to_string(&path_to_repo_metadata)
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            return not_ok_json_response(
+                Status::InternalServerError,
+                make_bad_json_data_response(format!("Could not read metadata: {}", e)),
+            )
+        }
+    };
+    if source_metadata["type"]["flavorType"]["flavor"]["name"].as_str() != Some("textTranslation") {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response("Only textTranslation burritos can be exported".to_string()),
+        );
+    }
+    let new_abbr = abbr.unwrap_or(format!(
+        "{}_{}",
+        repo_path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or("export".to_string()),
+        format
+    ));
+    let new_repo_path = match new_local_repo_path(&repo_dir, &new_abbr) {
+        Ok(p) => p,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    match make_exported_burrito(
+        &state.app_resources_dir,
+        &full_repo_path,
+        &new_repo_path,
+        format.as_str(),
+        &new_abbr,
+        &source_metadata,
+    ) {
+        Ok(_) => ok_json_response(json!({"repo_path": format!("_local_/_local_/{}", new_abbr)}).to_string()),
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&new_repo_path);
+            not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0))
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::structs::{AppSettings, BurritoMergeForm};
+use crate::utils::burrito_transfer::merge_burritos;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, check_path_string_components, os_slash_str};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{post, State};
+use std::path::PathBuf;
+
+/// *`POST /merge`*
+///
+/// Typically mounted as **`/burrito/merge`**
+///
+/// Merges burritos with the same flavor and language and no books in common, from JSON with *repo_paths* and *abbr*, into a new
+/// local burrito called *abbr*. The metadata of the first burrito is the starting point, and ingredients, currentScope and
+/// localizedNames are merged. The new repo has one commit per source burrito, recording the source repo and commit, the same is
+/// recorded for each book in `meta.comments`, and the source burritos are unchanged. Returns the new repo path and the source of
+/// each book.
+///
+/// ```text
+/// {"abbr": "my_bible", "repo_paths": ["_local_/_local_/my_tit", "_local_/_local_/my_phm"]}
+/// ```
+///
+/// returns
+///
+/// ```text
+/// {
+///   "repo_path": "_local_/_local_/my_bible",
+///   "books": {"PHM": {"repo_path": "_local_/_local_/my_phm", "commit": "4e1f..."}, "TIT": {...}}
+/// }
+/// ```
+#[post("/merge", format = "json", data = "<json_form>")]
+pub async fn post_merge_burritos(
+    state: &State<AppSettings>,
+    json_form: Json<BurritoMergeForm>,
+) -> status::Custom<(ContentType, String)> {
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    for repo_path in json_form.repo_paths.iter() {
+        let full_repo_path = format!("{}{}{}", repo_dir, os_slash_str(), repo_path);
+        if !check_path_components(&mut PathBuf::from(repo_path).components())
+            || !std::path::Path::new(&full_repo_path).is_dir()
+        {
+            return not_ok_bad_repo_json_response();
+        }
+    }
+    if !check_path_string_components(json_form.abbr.clone()) || json_form.abbr.contains("/") {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response(format!("Bad abbreviation '{}'", json_form.abbr)),
+        );
+    }
+    match merge_burritos(&state.app_resources_dir, &repo_dir, &json_form.repo_paths, &json_form.abbr) {
+        Ok(m) => ok_json_response(serde_json::to_string(&m).unwrap()),
+        Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    }
+}
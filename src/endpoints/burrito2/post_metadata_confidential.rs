@@ -0,0 +1,43 @@
+use crate::structs::AppSettings;
+use crate::utils::burrito::edit_metadata;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_ok_json_response,
+};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{post, State};
+use serde_json::Value;
+use std::path::{Components, PathBuf};
+
+/// *`POST /metadata/confidential/<repo_path>?confidential=true|false`*
+///
+/// Typically mounted as **`/burrito/metadata/confidential/<repo_path>?confidential=true|false`**
+///
+/// Sets the confidentiality flag of the metadata.
+#[post("/metadata/confidential/<repo_path..>?<confidential>")]
+pub async fn post_metadata_confidential(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    confidential: bool,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if check_path_components(&mut path_components.clone()) {
+        let full_repo_path = format!(
+            "{}{}{}",
+            state.repo_dir.lock().unwrap(),
+            os_slash_str(),
+            &repo_path.display().to_string()
+        );
+        match edit_metadata(&state.app_resources_dir, &full_repo_path, |metadata| {
+            metadata["confidential"] = Value::Bool(confidential);
+            Ok(())
+        }) {
+            Ok(_) => ok_ok_json_response(),
+            Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+        }
+    } else {
+        not_ok_bad_repo_json_response()
+    }
+}
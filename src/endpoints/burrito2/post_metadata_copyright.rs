@@ -0,0 +1,59 @@
+use crate::structs::{AppSettings, PankosmiaError};
+use crate::utils::burrito::edit_metadata;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_ok_json_response,
+};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{post, State};
+use serde_json::Value;
+use std::path::{Components, PathBuf};
+
+/// *`POST /metadata/copyright/<repo_path>`*
+///
+/// Typically mounted as **`/burrito/metadata/copyright/<repo_path>`**
+///
+/// Replaces the copyright section of the metadata with the JSON object in the body. The edited metadata must be schema valid. eg
+///
+/// ```text
+/// {
+///   "shortStatements": [
+///     {"statement": "<p>© 2025 My Org, CC BY-SA 4.0</p>", "mimetype": "text/html", "lang": "en"}
+///   ]
+/// }
+/// ```
+#[post(
+    "/metadata/copyright/<repo_path..>",
+    format = "json",
+    data = "<json_form>"
+)]
+pub async fn post_metadata_copyright(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    json_form: Json<Value>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if check_path_components(&mut path_components.clone()) {
+        let full_repo_path = format!(
+            "{}{}{}",
+            state.repo_dir.lock().unwrap(),
+            os_slash_str(),
+            &repo_path.display().to_string()
+        );
+        match edit_metadata(&state.app_resources_dir, &full_repo_path, |metadata| {
+            if !json_form.is_object() {
+                return Err(PankosmiaError("Copyright must be a JSON object".to_string()));
+            }
+            metadata["copyright"] = json_form.clone().into_inner();
+            Ok(())
+        }) {
+            Ok(_) => ok_ok_json_response(),
+            Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+        }
+    } else {
+        not_ok_bad_repo_json_response()
+    }
+}
@@ -0,0 +1,102 @@
+use crate::structs::{AppSettings, PankosmiaError};
+use crate::utils::burrito::edit_metadata;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_ok_json_response,
+};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::serde::Deserialize;
+use rocket::{post, State};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Components, PathBuf};
+
+#[derive(Deserialize)]
+pub struct MetadataIdentificationForm {
+    pub name: Option<BTreeMap<String, Option<String>>>,
+    pub abbreviation: Option<BTreeMap<String, Option<String>>>,
+    pub description: Option<BTreeMap<String, Option<String>>>,
+}
+
+fn edit_localized_string(
+    identification: &mut Value,
+    field: &str,
+    edits: &Option<BTreeMap<String, Option<String>>>,
+) -> Result<(), PankosmiaError> {
+    let edits = match edits {
+        Some(e) => e,
+        None => return Ok(()),
+    };
+    if !identification[field].is_object() {
+        identification[field] = Value::Object(Map::new());
+    }
+    let field_ob = identification[field].as_object_mut().unwrap();
+    for (language, value) in edits {
+        match value {
+            Some(v) => {
+                if v.trim().is_empty() {
+                    return Err(PankosmiaError(format!(
+                        "Empty {} for language '{}'",
+                        field, language
+                    )));
+                }
+                field_ob.insert(language.clone(), Value::String(v.clone()));
+            }
+            None => {
+                field_ob.remove(language);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// *`POST /metadata/identification/<repo_path>`*
+///
+/// Typically mounted as **`/burrito/metadata/identification/<repo_path>`**
+///
+/// Edits the localized name, abbreviation and description in the identification section of the metadata. Each field is optional and maps language codes to strings. A null value removes that language. The edited metadata must be schema valid. eg
+///
+/// ```text
+/// {
+///   "name": {"fr": "Pain Sur Les Eaux"},
+///   "abbreviation": {"fr": "PSLE", "de": null}
+/// }
+/// ```
+#[post(
+    "/metadata/identification/<repo_path..>",
+    format = "json",
+    data = "<json_form>"
+)]
+pub async fn post_metadata_identification(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    json_form: Json<MetadataIdentificationForm>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if check_path_components(&mut path_components.clone()) {
+        let full_repo_path = format!(
+            "{}{}{}",
+            state.repo_dir.lock().unwrap(),
+            os_slash_str(),
+            &repo_path.display().to_string()
+        );
+        match edit_metadata(&state.app_resources_dir, &full_repo_path, |metadata| {
+            if !metadata["identification"].is_object() {
+                metadata["identification"] = Value::Object(Map::new());
+            }
+            let identification = &mut metadata["identification"];
+            edit_localized_string(identification, "name", &json_form.name)?;
+            edit_localized_string(identification, "abbreviation", &json_form.abbreviation)?;
+            edit_localized_string(identification, "description", &json_form.description)?;
+            Ok(())
+        }) {
+            Ok(_) => ok_ok_json_response(),
+            Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+        }
+    } else {
+        not_ok_bad_repo_json_response()
+    }
+}
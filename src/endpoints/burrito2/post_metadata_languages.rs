@@ -0,0 +1,64 @@
+use crate::structs::{AppSettings, BurritoMetadataLanguage, PankosmiaError};
+use crate::utils::burrito::edit_metadata;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_ok_json_response,
+};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{post, State};
+use std::path::{Components, PathBuf};
+
+/// *`POST /metadata/languages/<repo_path>`*
+///
+/// Typically mounted as **`/burrito/metadata/languages/<repo_path>`**
+///
+/// Replaces the languages section of the metadata. At least one language is required, and the first language is treated as the main language by other endpoints. The edited metadata must be schema valid. eg
+///
+/// ```text
+/// [
+///   {"tag": "fr", "name": {"en": "French", "fr": "Français"}, "scriptDirection": "ltr"}
+/// ]
+/// ```
+#[post(
+    "/metadata/languages/<repo_path..>",
+    format = "json",
+    data = "<json_form>"
+)]
+pub async fn post_metadata_languages(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    json_form: Json<Vec<BurritoMetadataLanguage>>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if check_path_components(&mut path_components.clone()) {
+        let full_repo_path = format!(
+            "{}{}{}",
+            state.repo_dir.lock().unwrap(),
+            os_slash_str(),
+            &repo_path.display().to_string()
+        );
+        match edit_metadata(&state.app_resources_dir, &full_repo_path, |metadata| {
+            if json_form.is_empty() {
+                return Err(PankosmiaError("At least one language is required".to_string()));
+            }
+            for language in json_form.iter() {
+                if language.name.lock().unwrap().is_empty() {
+                    return Err(PankosmiaError(format!(
+                        "No name provided for language '{}'",
+                        language.tag
+                    )));
+                }
+            }
+            metadata["languages"] = serde_json::to_value(&*json_form).unwrap();
+            Ok(())
+        }) {
+            Ok(_) => ok_ok_json_response(),
+            Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+        }
+    } else {
+        not_ok_bad_repo_json_response()
+    }
+}
@@ -0,0 +1,96 @@
+use crate::structs::{AppSettings, PankosmiaError};
+use crate::utils::burrito::edit_metadata;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_ok_json_response,
+};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{post, State};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Components, PathBuf};
+
+// localizedNames key -> name kind -> language -> name, where null removes a book or language
+type LocalizedNamesEdits = BTreeMap<String, Option<BTreeMap<String, BTreeMap<String, Option<String>>>>>;
+
+/// *`POST /metadata/localized-names/<repo_path>`*
+///
+/// Typically mounted as **`/burrito/metadata/localized-names/<repo_path>`**
+///
+/// Edits localizedNames in the metadata. The body maps localizedNames keys to `short`, `abbr` and/or `long` entries, each of which maps language codes to strings. A null language value removes that language, and a null book value removes the whole book entry. The edited metadata must be schema valid. eg
+///
+/// ```text
+/// {
+///   "book-tit": {"short": {"fr": "Tite"}, "abbr": {"fr": "Ti"}},
+///   "book-phm": null
+/// }
+/// ```
+#[post(
+    "/metadata/localized-names/<repo_path..>",
+    format = "json",
+    data = "<json_form>"
+)]
+pub async fn post_metadata_localized_names(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    json_form: Json<LocalizedNamesEdits>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if check_path_components(&mut path_components.clone()) {
+        let full_repo_path = format!(
+            "{}{}{}",
+            state.repo_dir.lock().unwrap(),
+            os_slash_str(),
+            &repo_path.display().to_string()
+        );
+        match edit_metadata(&state.app_resources_dir, &full_repo_path, |metadata| {
+            if !metadata["localizedNames"].is_object() {
+                metadata["localizedNames"] = Value::Object(Map::new());
+            }
+            let localized_names = metadata["localizedNames"].as_object_mut().unwrap();
+            for (book_key, book_edits) in json_form.iter() {
+                let book_edits = match book_edits {
+                    Some(e) => e,
+                    None => {
+                        localized_names.remove(book_key);
+                        continue;
+                    }
+                };
+                let book_entry = localized_names
+                    .entry(book_key.clone())
+                    .or_insert(Value::Object(Map::new()));
+                for (name_kind, languages) in book_edits {
+                    if !["short", "abbr", "long"].contains(&name_kind.as_str()) {
+                        return Err(PankosmiaError(format!(
+                            "Unknown localizedNames field '{}' for {}",
+                            name_kind, book_key
+                        )));
+                    }
+                    if !book_entry[name_kind].is_object() {
+                        book_entry[name_kind] = Value::Object(Map::new());
+                    }
+                    let kind_ob = book_entry[name_kind].as_object_mut().unwrap();
+                    for (language, value) in languages {
+                        match value {
+                            Some(v) => {
+                                kind_ob.insert(language.clone(), Value::String(v.clone()));
+                            }
+                            None => {
+                                kind_ob.remove(language);
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }) {
+            Ok(_) => ok_ok_json_response(),
+            Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+        }
+    } else {
+        not_ok_bad_repo_json_response()
+    }
+}
@@ -1,5 +1,6 @@
 use crate::structs::{AppSettings, BurritoMetadata};
 use crate::utils::json_responses::{make_bad_json_data_response};
+use crate::utils::search_index::update_search_index_for_ingredient;
 use crate::utils::paths::{check_path_components, check_path_string_components, os_slash_str};
 use crate::utils::response::{not_ok_json_response, ok_ok_json_response, not_ok_bad_repo_json_response};
 use rocket::http::{ContentType, Status};
@@ -8,18 +9,19 @@ use rocket::serde::json::Json;
 use rocket::{post, State};
 use serde_json::Value;
 use std::path::{Components, PathBuf};
-use crate::utils::burrito::{destination_parent, ingredients_metadata_from_files, ingredients_scopes_from_files};
+use crate::utils::burrito::{destination_parent, ingredients_metadata_from_files, ingredients_scopes_from_files, localized_names_from_usfm};
 
-/// *`POST /ingredient/raw/<repo_path>?ipath=my_burrito_path&update_ingredients&no_bak`*
+/// *`POST /ingredient/raw/<repo_path>?ipath=my_burrito_path&update_ingredients&update_localized_names&no_bak`*
 ///
-/// Typically mounted as **`/burrito/ingredient/raw/<repo_path>?ipath=my_burrito_path&update_ingredients&no_bak`**
+/// Typically mounted as **`/burrito/ingredient/raw/<repo_path>?ipath=my_burrito_path&update_ingredients&update_localized_names&no_bak`**
 ///
-/// Writes a document, where the document is provided as JSON with a 'payload' key. The ipath parameter is required. There are two optional parameters:
+/// Writes a document, where the document is provided as JSON with a 'payload' key. The ipath parameter is required. There are three optional parameters:
 /// - update_ingredients to rewrite the metadata (default is false)
+/// - update_localized_names to also remake localizedNames from USFM headers, in the first metadata language (only with update_ingredients, default is false)
 /// - no_bak to write bak files (default is true)
 
 #[post(
-    "/ingredient/raw/<repo_path..>?<ipath>&<update_ingredients>&<no_bak>",
+    "/ingredient/raw/<repo_path..>?<ipath>&<update_ingredients>&<update_localized_names>&<no_bak>",
     format = "json",
     data = "<json_form>"
 )]
@@ -29,6 +31,7 @@ pub async fn post_raw_ingredient(
     repo_path: PathBuf,
     ipath: String,
     update_ingredients: Option<String>,
+    update_localized_names: Option<String>,
     no_bak: Option<String>,
     json_form: Json<Value>,
 ) -> status::Custom<(ContentType, String)> {
@@ -127,6 +130,18 @@ pub async fn post_raw_ingredient(
                 flavor_type_ob["currentScope"] = serde_json::from_str(serde_json::to_string(&new_current_scope).unwrap().as_str()).unwrap();
                 metadata_struct.r#type = serde_json::from_str(serde_json::to_string(&type_ob).unwrap().as_str()).unwrap();
             }
+            // Maybe remake localizedNames from USFM headers
+            if update_localized_names.is_some() {
+                let language_tag = match metadata_struct.languages.first() {
+                    Some(l) => l.tag.clone(),
+                    None => "en".to_string(),
+                };
+                metadata_struct.localizedNames = localized_names_from_usfm(
+                    full_repo_path.clone(),
+                    language_tag,
+                    &metadata_struct.localizedNames,
+                );
+            }
 
             // Write metadata
             let metadata_output_string = match serde_json::to_string(&metadata_struct) {
@@ -148,6 +163,15 @@ pub async fn post_raw_ingredient(
                 }
             }
         }
+        // Changed ingredients are reindexed by the next search anyway, so this does not fail the save
+        if let Err(e) = update_search_index_for_ingredient(
+            &state.working_dir,
+            &repo_dir,
+            &repo_path.display().to_string(),
+            &ipath,
+        ) {
+            println!("Could not update search index for {}: {}", ipath, e.0);
+        }
         ok_ok_json_response()
     } else {
         not_ok_bad_repo_json_response()
@@ -0,0 +1,57 @@
+use crate::structs::AppSettings;
+use crate::utils::burrito::{edit_metadata, localized_names_from_usfm, metadata_language_tag};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_ok_json_response,
+};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{post, State};
+use std::path::{Components, PathBuf};
+
+/// *`POST /metadata/remake-localized-names/<repo_path>?language=fr`*
+///
+/// Typically mounted as **`/burrito/metadata/remake-localized-names/<repo_path>?language=fr`**
+///
+/// Remakes the localizedNames section of the metadata from the `\h`, `\toc1`, `\toc2` and `\toc3` markers of the USFM ingredients:
+/// - short from `\toc2`, falling back to `\h`
+/// - abbr from `\toc3`
+/// - long from `\toc1`, falling back to `\h`
+///
+/// Names are written for *language*, which defaults to the first language of the metadata. Names in other languages are kept.
+#[post("/metadata/remake-localized-names/<repo_path..>?<language>")]
+pub async fn remake_localized_names(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    language: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    let full_repo_path = format!(
+        "{}{}{}",
+        state.repo_dir.lock().unwrap(),
+        os_slash_str(),
+        &repo_path.display().to_string()
+    );
+    if check_path_components(&mut path_components.clone())
+        && std::fs::metadata(&full_repo_path).is_ok()
+    {
+        match edit_metadata(&state.app_resources_dir, &full_repo_path, |metadata| {
+            let language_tag = match &language {
+                Some(l) => l.clone(),
+                None => metadata_language_tag(metadata),
+            };
+            metadata["localizedNames"] = localized_names_from_usfm(
+                full_repo_path.clone(),
+                language_tag,
+                &metadata["localizedNames"],
+            );
+            Ok(())
+        }) {
+            Ok(_) => ok_ok_json_response(),
+            Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+        }
+    } else {
+        not_ok_bad_repo_json_response()
+    }
+}
@@ -0,0 +1,52 @@
+use crate::structs::AppSettings;
+use crate::utils::burrito_transfer::split_burrito;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{post, State};
+use serde_json::json;
+use std::path::{Components, PathBuf};
+
+/// *`POST /split/<repo_path>?books=TIT,PHM`*
+///
+/// Typically mounted as **`/burrito/split/<repo_path>?books=TIT,PHM`**
+///
+/// Splits a burrito into new local burritos, one per book in currentScope or per book in the comma-separated *books*.
+/// Each new burrito is a clone of the source repo, and so keeps its history, with the ingredients and localizedNames of other books
+/// removed in a new commit. New burritos are named after the source repo and the book, and the book name is added to their
+/// names. The source repo, which must have no uncommitted changes, is unchanged. Returns the repo paths of the new burritos. If any
+/// new burrito cannot be made, none are kept.
+///
+/// ```text
+/// {"repo_paths": ["_local_/_local_/my_bible_PHM", "_local_/_local_/my_bible_TIT"]}
+/// ```
+#[post("/split/<repo_path..>?<books>")]
+pub async fn post_split_burrito(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    books: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let full_repo_path = format!("{}{}{}", repo_dir, os_slash_str(), &repo_path.display().to_string());
+    if !check_path_components(&mut path_components.clone()) || !std::path::Path::new(&full_repo_path).is_dir() {
+        return not_ok_bad_repo_json_response();
+    }
+    let split_books = books.map(|b| {
+        b.split(",")
+            .map(|c| c.trim().to_uppercase())
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<String>>()
+    });
+    match split_burrito(
+        &state.app_resources_dir,
+        &repo_dir,
+        &repo_path.display().to_string(),
+        &split_books,
+    ) {
+        Ok(repo_paths) => ok_json_response(json!({"repo_paths": repo_paths}).to_string()),
+        Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    }
+}
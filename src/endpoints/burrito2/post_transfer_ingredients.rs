@@ -0,0 +1,74 @@
+use crate::structs::{AppSettings, IngredientTransferForm};
+use crate::utils::burrito_transfer::transfer_ingredients;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, check_path_string_components, os_slash_str};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{post, State};
+use std::path::{Components, PathBuf};
+
+/// *`POST /ingredients/transfer/<repo_path>`*
+///
+/// Typically mounted as **`/burrito/ingredients/transfer/<repo_path>`**
+///
+/// Copies ingredients from the burrito at *repo_path* to the burrito at *target*, from JSON with *target* and at least one of
+/// *books* (book codes, whose ingredients are all copied) and *ipaths* (ingredient files or directories, relative to `ingredients`).
+/// The burritos must have the same flavor. With *delete_src* the ingredients are moved. Existing target ingredients are only
+/// replaced, with a backup, with *overwrite*. Ingredients, currentScope and localizedNames are updated in both metadata files,
+/// after any source ingredients have been deleted. If a step fails after the copy, the error says which steps completed.
+///
+/// ```text
+/// {"target": "_local_/_local_/my_translation", "books": ["TIT", "PHM"], "ipaths": [], "delete_src": false, "overwrite": false}
+/// ```
+///
+/// returns
+///
+/// ```text
+/// {"books": ["PHM", "TIT"], "ingredients": ["PHM.usfm", "TIT.usfm"]}
+/// ```
+#[post("/ingredients/transfer/<repo_path..>", format = "json", data = "<json_form>")]
+pub async fn post_transfer_ingredients(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    json_form: Json<IngredientTransferForm>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let full_src_path = format!("{}{}{}", repo_dir, os_slash_str(), &repo_path.display().to_string());
+    let full_target_path = format!("{}{}{}", repo_dir, os_slash_str(), &json_form.target);
+    if !check_path_components(&mut path_components.clone())
+        || !check_path_components(&mut PathBuf::from(&json_form.target).components())
+        || !std::path::Path::new(&full_src_path).is_dir()
+        || !std::path::Path::new(&full_target_path).is_dir()
+    {
+        return not_ok_bad_repo_json_response();
+    }
+    let books: Vec<String> = json_form
+        .books
+        .clone()
+        .unwrap_or_default()
+        .iter()
+        .map(|b| b.trim().to_uppercase())
+        .collect();
+    let ipaths = json_form.ipaths.clone().unwrap_or_default();
+    if ipaths.iter().any(|p| !check_path_string_components(p.clone())) {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response("Bad ingredient path".to_string()),
+        );
+    }
+    match transfer_ingredients(
+        &state.app_resources_dir,
+        &full_src_path,
+        &full_target_path,
+        &books,
+        &ipaths,
+        json_form.delete_src.unwrap_or(false),
+        json_form.overwrite.unwrap_or(false),
+    ) {
+        Ok(t) => ok_json_response(serde_json::to_string(&t).unwrap()),
+        Err(e) => not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    }
+}
@@ -0,0 +1,142 @@
+use crate::structs::AppSettings;
+use crate::utils::bcv_ref::canonical_book_code;
+use crate::utils::burrito::{rewrite_ingredients_metadata, usfm_ingredient_for_book};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_ok_json_response,
+};
+use crate::utils::search_index::update_search_index_for_ingredient;
+use crate::utils::usfm::replace_usfm_verses;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{post, State};
+use serde_json::Value;
+use std::path::{Components, PathBuf};
+
+/// *`POST /verses/<repo_path>?book=JHN&chapter=3&verse=16&to_verse=17&update_ingredients&no_bak`*
+///
+/// Typically mounted as **`/burrito/verses/<repo_path>?book=JHN&chapter=3&verse=16&to_verse=17&update_ingredients&no_bak`**
+///
+/// Replaces the content of one verse or verse range in the USFM ingredient for a book, which must be a canonical book code, where the new USFM content is provided as JSON with a 'payload' key. *to_verse* defaults to *verse*.
+///
+/// The first verse marker is kept, as are paragraph, heading and chunk markers after the last verse, and everything outside the range. New content for a range must include exactly the verse markers of the range after the first verse. When the new content has no alignment markup, existing `\zaln-s` alignments are kept for the words that are still in each verse. There are two optional parameters, as for `/burrito/ingredient/raw`:
+/// - update_ingredients to rewrite the metadata (default is false)
+/// - no_bak to write bak files (default is true)
+#[post(
+    "/verses/<repo_path..>?<book>&<chapter>&<verse>&<to_verse>&<update_ingredients>&<no_bak>",
+    format = "json",
+    data = "<json_form>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn post_verses(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    chapter: u16,
+    verse: u16,
+    to_verse: Option<u16>,
+    update_ingredients: Option<String>,
+    no_bak: Option<String>,
+    json_form: Json<Value>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if !check_path_components(&mut path_components.clone()) {
+        return not_ok_bad_repo_json_response();
+    }
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let full_repo_path = format!("{}{}{}", &repo_dir, os_slash_str(), &repo_path.display().to_string());
+    let new_content = match json_form["payload"].as_str() {
+        Some(p) => p.to_string(),
+        None => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response(format!("Could not find payload in {:?}", json_form)),
+            )
+        }
+    };
+    let book_code = match canonical_book_code(&state.app_resources_dir, &book) {
+        Some(b) => b,
+        None => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response(format!("Unknown book '{}'", book)),
+            )
+        }
+    };
+    let ipath = match usfm_ingredient_for_book(&state.app_resources_dir, &full_repo_path, &book_code) {
+        Some(p) => p,
+        None => {
+            return not_ok_json_response(
+                Status::NotFound,
+                make_bad_json_data_response(format!("No USFM for book {}", book_code)),
+            )
+        }
+    };
+    let destination = format!("{}{}ingredients{}{}", &full_repo_path, os_slash_str(), os_slash_str(), &ipath);
+    let usfm_string = match std::fs::read_to_string(&destination) {
+        Ok(s) => s,
+        Err(e) => {
+            return not_ok_json_response(
+                Status::InternalServerError,
+                make_bad_json_data_response(format!("Could not read {}: {}", ipath, e)),
+            )
+        }
+    };
+    let new_usfm = match replace_usfm_verses(
+        &usfm_string,
+        chapter,
+        verse,
+        to_verse.unwrap_or(verse),
+        &new_content,
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0))
+        }
+    };
+    // Maybe make backup file
+    if no_bak.is_none() {
+        let destination_backup_path = format!("{}.bak", &destination);
+        match std::fs::write(&destination_backup_path, &usfm_string) {
+            Ok(_) => (),
+            Err(e) => {
+                return not_ok_json_response(
+                    Status::InternalServerError,
+                    make_bad_json_data_response(format!("Could not write backup file: {}", e)),
+                )
+            }
+        }
+    }
+    match std::fs::write(&destination, &new_usfm) {
+        Ok(_) => (),
+        Err(e) => {
+            return not_ok_json_response(
+                Status::InternalServerError,
+                make_bad_json_data_response(format!("Could not write to {}: {}", ipath, e)),
+            )
+        }
+    }
+    if update_ingredients.is_some() {
+        match rewrite_ingredients_metadata(state.app_resources_dir.clone(), full_repo_path) {
+            Ok(_) => (),
+            Err(e) => {
+                return not_ok_json_response(
+                    Status::InternalServerError,
+                    make_bad_json_data_response(e.0),
+                )
+            }
+        }
+    }
+    // Changed ingredients are reindexed by the next search anyway, so this does not fail the save
+    if let Err(e) = update_search_index_for_ingredient(
+        &state.working_dir,
+        &repo_dir,
+        &repo_path.display().to_string(),
+        &ipath,
+    ) {
+        println!("Could not update search index for {}: {}", ipath, e.0);
+    }
+    ok_ok_json_response()
+}
@@ -0,0 +1,123 @@
+use crate::structs::AppSettings;
+use crate::utils::burrito_api::checks::audit_report;
+use crate::utils::burrito_api::checks::report_helpers::CheckReport;
+use crate::utils::burrito_api::repairs::{
+    repair_ingredients_dir, repair_ingredients_metadata, repair_metadata_fields,
+    repair_unexpected_content,
+};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{post, State};
+use serde_json::{json, Value};
+use std::path::{Components, PathBuf};
+
+/// *`POST /repair/<repo_path>`*
+///
+/// Typically mounted as **`/burrito/repair/<repo_path>`**
+///
+/// Fixes the problems found by `/burrito/audit` that can be fixed mechanically, where *repo_path* is *`<server>/<org>/<repo>`* and refers to a local repo:
+/// - creates a missing ingredients directory
+/// - moves unexpected top-level content into ingredients
+/// - fills missing metadata fields that the schema requires from the content template for the flavor
+/// - regenerates ingredient checksums, sizes and currentScope
+///
+/// Returns the audit report before and after the repair, plus the actions taken.
+///
+/// ```text
+/// {
+///   "before": [...],
+///   "actions": [{"name": "Repair:Content:Moved", "path": "...", "success": true, "comment": "Moved into ingredients", "data": ["GEN.usfm"]}],
+///   "after": [...]
+/// }
+/// ```
+#[post("/repair/<repo_path..>")]
+pub async fn repair(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if !check_path_components(&mut path_components.clone()) {
+        return not_ok_bad_repo_json_response();
+    }
+    let burrito_path = state.repo_dir.lock().unwrap().clone()
+        + os_slash_str()
+        + &repo_path.display().to_string();
+    if !std::path::Path::new(&burrito_path).is_dir() {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response("Repo not found".to_string()),
+        );
+    }
+    let app_resources_dir = state.app_resources_dir.clone();
+    let before = audit_report(&app_resources_dir, burrito_path.clone());
+    let mut actions: Vec<CheckReport> = vec![];
+    // Shape
+    actions.extend(repair_ingredients_dir(burrito_path.clone()));
+    for report in before.iter() {
+        if report.name == "BurritoShape:Content:Unexpected" {
+            if let Some(unexpected) = &report.data {
+                actions.extend(repair_unexpected_content(burrito_path.clone(), unexpected));
+            }
+        }
+    }
+    // Metadata
+    let path_to_repo_metadata = format!("{}{}metadata.json", &burrito_path, os_slash_str());
+    let metadata_json = match std::fs::read_to_string(&path_to_repo_metadata) {
+        Ok(s) => match serde_json::from_str::<Value>(&s) {
+            Ok(Value::Object(o)) => Some(Value::Object(o)),
+            _ => None,
+        },
+        Err(_) => None,
+    };
+    match metadata_json {
+        Some(mut metadata) => {
+            actions.extend(repair_metadata_fields(
+                app_resources_dir.clone(),
+                burrito_path.clone(),
+                &mut metadata,
+            ));
+            actions.extend(repair_ingredients_metadata(
+                app_resources_dir,
+                burrito_path.clone(),
+                &mut metadata,
+            ));
+            let metadata_output_string = match serde_json::to_string(&metadata) {
+                Ok(s) => s,
+                Err(e) => {
+                    return not_ok_json_response(
+                        Status::InternalServerError,
+                        make_bad_json_data_response(format!("Could not make metadata as JSON: {}", e)),
+                    )
+                }
+            };
+            match std::fs::write(&path_to_repo_metadata, &metadata_output_string) {
+                Ok(_) => (),
+                Err(e) => {
+                    return not_ok_json_response(
+                        Status::InternalServerError,
+                        make_bad_json_data_response(format!("Could not write metadata to repo: {}", e)),
+                    )
+                }
+            }
+        }
+        None => actions.push(CheckReport {
+            name: "Repair:Metadata:Unrepairable".to_string(),
+            path: burrito_path.clone(),
+            success: false,
+            comment: Some("Metadata is missing or is not a JSON object".to_string()),
+            data: None,
+        }),
+    }
+    let after = audit_report(&state.app_resources_dir, burrito_path);
+    ok_json_response(
+        serde_json::to_string(&json!({
+            "before": before,
+            "actions": actions,
+            "after": after
+        }))
+        .unwrap(),
+    )
+}
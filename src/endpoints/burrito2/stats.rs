@@ -0,0 +1,51 @@
+use crate::structs::AppSettings;
+use crate::utils::burrito_stats::burrito_stats;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{not_ok_bad_repo_json_response, ok_json_response};
+use rocket::http::ContentType;
+use rocket::response::status;
+use rocket::{get, State};
+use std::path::{Components, PathBuf};
+
+/// *`GET /stats/<repo_path>`*
+///
+/// Typically mounted as **`/burrito/stats/<repo_path>`**
+///
+/// Returns drafting statistics for every book of a USFM or audio burrito, by chapter: verses present and expected from the versification,
+/// empty verses, word counts and, for audio translations, the number of recorded paragraphs and their total duration in seconds.
+///
+/// ```text
+/// [
+///   {
+///     "book_code": "TIT",
+///     "expected_verses": 46,
+///     "present_verses": 44,
+///     "empty_verses": 2,
+///     "words": 812,
+///     "recorded_paragraphs": 0,
+///     "audio_duration": 0.0,
+///     "chapters": [
+///       {"chapter": 1, "expected_verses": 16, "present_verses": 14, "empty_verses": [15, 16], "words": 301, "recorded_paragraphs": 0, "audio_duration": 0.0},
+///       ...
+///     ]
+///   }
+/// ]
+/// ```
+#[get("/stats/<repo_path..>")]
+pub async fn stats(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    let path_to_repo = format!(
+        "{}{}{}",
+        state.repo_dir.lock().unwrap().clone(),
+        os_slash_str(),
+        &repo_path.display().to_string()
+    );
+    if !check_path_components(&mut path_components.clone()) || !std::path::Path::new(&path_to_repo).is_dir() {
+        return not_ok_bad_repo_json_response();
+    }
+    let book_stats = burrito_stats(&state.app_resources_dir, &path_to_repo);
+    ok_json_response(serde_json::to_string(&book_stats).unwrap())
+}
@@ -2,19 +2,21 @@ use rocket::http::{ContentType, Status};
 use rocket::response::status;
 use rocket::{get, State};
 use std::path::{Components, PathBuf};
-use crate::structs::{AppSettings, MetadataSummary};
+use crate::structs::AppSettings;
 use crate::utils::json_responses::make_bad_json_data_response;
 use crate::utils::paths::{check_path_components, os_slash_str};
 use crate::utils::response::{
     not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response,
 };
-use crate::utils::burrito::summary_metadata_from_file;
+use crate::utils::burrito::{bad_metadata_summary, preferred_languages, summary_metadata_from_file};
 
-/// *`GET /metadata/summary/<repo_path>`*
+/// *`GET /metadata/summary/<repo_path>?lang=fr,en`*
 ///
-/// Typically mounted as **`/burrito/metadata/summary/<repo_path>`**
+/// Typically mounted as **`/burrito/metadata/summary/<repo_path>?lang=fr,en`**
 ///
-/// Returns a flat summary of information from the raw metadata.json file for the specified burrito, where *repo_path* is *`<server>/<org>/<repo>`* and refers to a local repo. eg, the response to `/burrito/metadata/summary/git.door43.org/BurritoTruck/fr_psle` might be
+/// Returns a flat summary of information from the raw metadata.json file for the specified burrito, where *repo_path* is *`<server>/<org>/<repo>`* and refers to a local repo.
+///
+/// The name, description, abbreviation and language name are given in the first available language of *lang*, a comma-separated list which defaults to the user's language preferences. The full localized maps are also returned. eg, the response to `/burrito/metadata/summary/git.door43.org/BurritoTruck/fr_psle` might be
 ///
 /// ```text
 /// {
@@ -26,13 +28,16 @@ use crate::utils::burrito::summary_metadata_from_file;
 ///   "flavor": "textTranslation",
 ///   "language_code": "fr",
 ///   "language_name": "French",
-///   "script_direction": "ltr"
+///   "script_direction": "ltr",
+///   "names": {"en": "Bread Upon The Waters", "fr": "Pain Sur Les Eaux"},
+///   ...
 /// }
 /// ```
-#[get("/metadata/summary/<repo_path..>")]
+#[get("/metadata/summary/<repo_path..>?<lang>")]
 pub async fn summary_metadata(
     state: &State<AppSettings>,
     repo_path: PathBuf,
+    lang: Option<String>,
 ) -> status::Custom<(ContentType, String)> {
     let path_components: Components<'_> = repo_path.components();
     if check_path_components(&mut path_components.clone()) {
@@ -43,19 +48,12 @@ pub async fn summary_metadata(
             &repo_path.display().to_string(),
             os_slash_str()
         );
-        let summary = summary_metadata_from_file(path_to_serve).unwrap_or_else(|_| MetadataSummary {
-            name: "? Bad Metadata JSON ?".to_string(),
-            description: "?".to_string(),
-            abbreviation: "?".to_string(),
-            generated_date: "?".to_string(),
-            flavor_type: "?".to_string(),
-            flavor: "?".to_string(),
-            language_code: "?".to_string(),
-            language_name: "?".to_string(),
-            script_direction: "?".to_string(),
-            book_codes: vec![],
-            timestamp: 0
-        });
+        let languages = match lang {
+            Some(l) => preferred_languages(l),
+            None => state.languages.lock().unwrap().clone(),
+        };
+        let summary = summary_metadata_from_file(path_to_serve, &languages)
+            .unwrap_or_else(|_| bad_metadata_summary());
         match serde_json::to_string(&summary) {
             Ok(v) => ok_json_response(v),
             Err(e) => not_ok_json_response(
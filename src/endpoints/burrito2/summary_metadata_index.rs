@@ -0,0 +1,73 @@
+use crate::structs::{AppSettings, MetadataIndexQuery};
+use crate::utils::burrito::{negotiate_metadata_summary, preferred_languages};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::metadata_index::{metadata_index, summary_matches};
+use crate::utils::response::{not_ok_json_response, ok_json_response};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, State};
+use serde_json::Value;
+
+/// *`GET /metadata/index?<org>&<flavor>&<language>&<book>&<updated_since>&<sort>&<lang>`*
+///
+/// Typically mounted as **`/burrito/metadata/index?<org>&<flavor>&<language>&<book>&<updated_since>&<sort>&<lang>`**
+///
+/// Returns a sorted JSON array of local repo metadata summaries from the metadata index, each with a `path` field. Filters are as for `/burrito/metadata/summaries`, plus *updated_since*, a Unix timestamp in seconds.
+///
+/// *sort* may be
+/// - `path` (the default)
+/// - `name`, using the negotiated name
+/// - `updated`, most recently changed first
+///
+/// ```text
+/// [
+///   {"path": "git.door43.org/BurritoTruck/fr_psle", "name": "Pain Sur Les Eaux", "flavor": "textTranslation", "timestamp": 1731668522, ...}
+/// ]
+/// ```
+#[get("/metadata/index?<query..>")]
+pub fn summary_metadata_index(
+    state: &State<AppSettings>,
+    query: MetadataIndexQuery,
+) -> status::Custom<(ContentType, String)> {
+    let languages = match query.lang {
+        Some(l) => preferred_languages(l),
+        None => state.languages.lock().unwrap().clone(),
+    };
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let mut summaries = vec![];
+    let index = match metadata_index(&state.working_dir, &repo_dir, &query.org) {
+        Ok(i) => i,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    for (repo_path, entry) in index {
+        if !summary_matches(&entry.summary, &query.flavor, &query.language, &query.book) {
+            continue;
+        }
+        if let Some(since) = query.updated_since {
+            if entry.summary.timestamp < since {
+                continue;
+            }
+        }
+        let mut summary = entry.summary;
+        negotiate_metadata_summary(&mut summary, &languages);
+        summaries.push((repo_path, summary));
+    }
+    match query.sort.unwrap_or("path".to_string()).as_str() {
+        "path" => {}
+        "name" => summaries.sort_by_key(|s| s.1.name.to_lowercase()),
+        "updated" => summaries.sort_by_key(|s| std::cmp::Reverse(s.1.timestamp)),
+        s => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response(format!("Unknown sort '{}'", s)),
+            )
+        }
+    }
+    let mut summary_values = vec![];
+    for (repo_path, summary) in summaries {
+        let mut summary_value = serde_json::to_value(&summary).unwrap();
+        summary_value["path"] = Value::String(repo_path);
+        summary_values.push(summary_value);
+    }
+    ok_json_response(serde_json::to_string(&summary_values).unwrap())
+}
@@ -0,0 +1,168 @@
+use crate::structs::{AppSettings, Bcv, VersesQuery};
+use crate::utils::bcv_ref::canonical_book_code;
+use crate::utils::burrito::usfm_ingredient_for_book;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::response::{
+    not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response,
+};
+use crate::utils::versification::{map_bcv_between, named_versification, repo_versification};
+use crate::utils::usfm::{usfm_plain_text, usfm_verses_in_range};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, State};
+use serde_json::json;
+use std::path::{Components, PathBuf};
+
+/// *`GET /verses/<repo_path>?book=JHN&chapter=3&verse=16&to_verse=18&format=plain|usfm&versification=eng`*
+///
+/// Typically mounted as **`/burrito/verses/<repo_path>?book=JHN&chapter=3&verse=16&to_verse=18&format=plain|usfm&versification=eng`**
+///
+/// Returns verses from the USFM ingredient for a book, where *repo_path* is *`<server>/<org>/<repo>`* and refers to a local repo.
+///
+/// *book* must be a canonical book code. When *book* is missing the current navigation position is used, and *chapter*, *verse* and *to_verse* must not be given. Otherwise *chapter* is required. When *verse* is missing the whole chapter is returned, and *to_verse* defaults to *verse*. Bridged verses such as `\v 3-5` are returned whole if they overlap the range. *format* is `plain` (the default), for text without markup, or `usfm`.
+///
+/// *versification* names the scheme of the requested reference, and defaults to `eng` for the navigation position. When it is set and the repo has an `ingredients/vrs.json`, the reference is mapped into the versification of the repo, and the original reference is returned as `requested_bcv`.
+///
+/// ```text
+/// {
+///   "ipath": "JHN.usfm",
+///   "bcv": {"book_code": "JHN", "chapter": 3, "verse": 16, "to_verse": 17},
+///   "format": "plain",
+///   "verses": [
+///     {"chapter": 3, "verse": 16, "to_verse": 16, "text": "For God so loved the world..."},
+///     {"chapter": 3, "verse": 17, "to_verse": 17, "text": "For God did not send..."}
+///   ]
+/// }
+/// ```
+#[get("/verses/<repo_path..>?<query..>")]
+pub async fn verses(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    query: VersesQuery,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    if !check_path_components(&mut path_components.clone()) {
+        return not_ok_bad_repo_json_response();
+    }
+    let full_repo_path = format!(
+        "{}{}{}",
+        state.repo_dir.lock().unwrap(),
+        os_slash_str(),
+        &repo_path.display().to_string()
+    );
+    let format = query.format.unwrap_or("plain".to_string());
+    if format != "plain" && format != "usfm" {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response(format!("Unknown format '{}'", format)),
+        );
+    }
+    let requested_versification = match (&query.book, query.versification) {
+        (_, Some(v)) => Some(v),
+        (None, None) => Some("eng".to_string()),
+        (Some(_), None) => None,
+    };
+    let mut requested_bcv = match (query.book, query.chapter) {
+        (Some(b), Some(chapter)) => Bcv {
+            book_code: match canonical_book_code(&state.app_resources_dir, &b) {
+                Some(book_code) => book_code,
+                None => {
+                    return not_ok_json_response(
+                        Status::BadRequest,
+                        make_bad_json_data_response(format!("Unknown book '{}'", b)),
+                    )
+                }
+            },
+            chapter,
+            verse: query.verse.unwrap_or(0),
+            to_verse: query.to_verse.or(query.verse).unwrap_or(u16::MAX),
+        },
+        (Some(_), None) => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response("chapter is required with book".to_string()),
+            )
+        }
+        (None, _) => {
+            if query.chapter.is_some() || query.verse.is_some() || query.to_verse.is_some() {
+                return not_ok_json_response(
+                    Status::BadRequest,
+                    make_bad_json_data_response("chapter, verse and to_verse require book".to_string()),
+                );
+            }
+            state.bcv.lock().unwrap().clone()
+        }
+    };
+    let mut bcv = match (requested_versification, repo_versification(&full_repo_path)) {
+        (Some(v), Some(to_versification)) => {
+            let from_versification = match named_versification(&state.app_resources_dir, &v) {
+                Ok(fv) => fv,
+                Err(e) => {
+                    return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0))
+                }
+            };
+            let mut mapped_bcv = requested_bcv.clone();
+            // Whole chapters are not mapped verse by verse
+            if requested_bcv.to_verse != u16::MAX {
+                mapped_bcv = map_bcv_between(&requested_bcv, &from_versification, &to_versification);
+            }
+            mapped_bcv
+        }
+        _ => requested_bcv.clone(),
+    };
+    let ipath = match usfm_ingredient_for_book(&state.app_resources_dir, &full_repo_path, &bcv.book_code) {
+        Some(p) => p,
+        None => {
+            return not_ok_json_response(
+                Status::NotFound,
+                make_bad_json_data_response(format!("No USFM for book {}", bcv.book_code)),
+            )
+        }
+    };
+    let usfm_string = match std::fs::read_to_string(format!(
+        "{}{}ingredients{}{}",
+        full_repo_path,
+        os_slash_str(),
+        os_slash_str(),
+        ipath
+    )) {
+        Ok(s) => s,
+        Err(e) => {
+            return not_ok_json_response(
+                Status::InternalServerError,
+                make_bad_json_data_response(format!("Could not read {}: {}", ipath, e)),
+            )
+        }
+    };
+    let selected_verses = usfm_verses_in_range(&usfm_string, bcv.chapter, bcv.verse, bcv.to_verse);
+    // Whole chapter
+    if bcv.to_verse == u16::MAX {
+        bcv.to_verse = selected_verses.iter().map(|v| v.to_verse).max().unwrap_or(0);
+        requested_bcv.to_verse = bcv.to_verse;
+    }
+    let verse_records: Vec<_> = selected_verses
+        .iter()
+        .map(|v| {
+            json!({
+                "chapter": v.chapter,
+                "verse": v.verse,
+                "to_verse": v.to_verse,
+                "text": match format.as_str() {
+                    "usfm" => v.usfm.trim().to_string(),
+                    _ => usfm_plain_text(&v.usfm),
+                }
+            })
+        })
+        .collect();
+    ok_json_response(
+        serde_json::to_string(&json!({
+            "ipath": ipath,
+            "requested_bcv": requested_bcv,
+            "bcv": bcv,
+            "format": format,
+            "verses": verse_records
+        }))
+        .unwrap(),
+    )
+}
@@ -0,0 +1,42 @@
+use crate::structs::{AppSettings, Bcv};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::response::{not_ok_json_response, ok_json_response};
+use crate::utils::versification::{map_bcv_between, named_versification};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, State};
+
+/// *`GET /map-reference?book=PSA&chapter=3&verse=1&to_verse=2&from=eng&to=org`*
+///
+/// Typically mounted as **`/content-utils/map-reference?book=PSA&chapter=3&verse=1&to_verse=2&from=eng&to=org`**
+///
+/// Converts a reference between two versification schemes, using the `mappedVerses` of the vrs files. *to_verse* defaults to *verse*.
+///
+/// `{"book_code":"PSA","chapter":3,"verse":2,"to_verse":3}`
+#[get("/map-reference?<book>&<chapter>&<verse>&<to_verse>&<from>&<to>")]
+pub fn map_reference(
+    state: &State<AppSettings>,
+    book: String,
+    chapter: u16,
+    verse: u16,
+    to_verse: Option<u16>,
+    from: String,
+    to: String,
+) -> status::Custom<(ContentType, String)> {
+    let from_versification = match named_versification(&state.app_resources_dir, &from) {
+        Ok(v) => v,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    let to_versification = match named_versification(&state.app_resources_dir, &to) {
+        Ok(v) => v,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    let bcv = Bcv {
+        book_code: book.to_uppercase(),
+        chapter,
+        verse,
+        to_verse: to_verse.unwrap_or(verse),
+    };
+    let mapped_bcv = map_bcv_between(&bcv, &from_versification, &to_versification);
+    ok_json_response(serde_json::to_string(&mapped_bcv).unwrap())
+}
@@ -4,4 +4,5 @@ pub mod list_content_template_filenames;
 pub mod content_template;
 pub mod list_versifications;
 pub mod versification;
-pub mod product_content;
\ No newline at end of file
+pub mod product_content;
+pub mod map_reference;
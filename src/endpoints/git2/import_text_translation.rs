@@ -0,0 +1,376 @@
+use crate::structs::{AppSettings, PankosmiaError};
+use crate::utils::burrito::{edit_metadata, localized_names_from_usfm, refresh_ingredients_in_metadata_value};
+use crate::utils::files::load_json;
+use crate::utils::json::replace_in_json_strings;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::local_repo::{add_and_commit_all, init_local_repo, new_local_repo_path};
+use crate::utils::paths::os_slash_str;
+use crate::utils::response::{not_ok_json_response, ok_json_response};
+use crate::utils::time::utc_now_timestamp_string;
+use crate::utils::usfm::usfm_book_headers;
+use crate::utils::usj::usx_to_usfm;
+use crate::utils::versification::versification_template_path;
+use crate::utils::zip::unpack_zip_file;
+use regex::Regex;
+use rocket::form::{Form, FromForm};
+use rocket::fs::TempFile;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{post, State};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::LazyLock;
+use tempfile::NamedTempFile;
+use walkdir::WalkDir;
+
+#[derive(FromForm)]
+pub struct ImportTextTranslationForm<'f> {
+    file: Option<TempFile<'f>>,
+    folder: Option<String>,
+    content_abbr: Option<String>,
+    content_name: Option<String>,
+    content_language_code: Option<String>,
+    content_language_name: Option<String>,
+    versification: Option<String>,
+}
+
+// What can be learnt about the project from Paratext Settings.xml or DBL metadata.xml
+#[derive(Default)]
+struct ImportedProjectDetails {
+    source: String,
+    abbr: Option<String>,
+    name: Option<String>,
+    language_code: Option<String>,
+    language_name: Option<String>,
+    versification: Option<String>,
+}
+
+static START_TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<([A-Za-z][\w:.-]*)(?:\s[^>]*)?>").unwrap());
+
+// The content of the first element with a tag, up to the next end tag for it
+fn xml_element_content<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = START_TAG_RE.captures_iter(xml).find(|c| &c[1] == tag)?.get(0)?.end();
+    let length = xml[start..].find(&format!("</{}>", tag))?;
+    Some(&xml[start..start + length])
+}
+
+fn xml_element_text(xml: &str, tag: &str) -> Option<String> {
+    xml_element_content(xml, tag)
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty() && !t.contains("<"))
+}
+
+// Paratext stores versification as a number
+fn paratext_versification_name(number: &str) -> Option<String> {
+    match number {
+        "1" => Some("org"),
+        "2" => Some("lxx"),
+        "3" => Some("vul"),
+        "4" => Some("eng"),
+        "5" => Some("rsc"),
+        "6" => Some("rso"),
+        _ => None,
+    }
+    .map(|v| v.to_string())
+}
+
+fn imported_project_details(source_dir: &String) -> ImportedProjectDetails {
+    let mut details = ImportedProjectDetails {
+        source: "USFM".to_string(),
+        ..Default::default()
+    };
+    for entry in WalkDir::new(source_dir).into_iter().filter_map(|e| e.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        if !entry.path().is_file() || (file_name != "settings.xml" && file_name != "metadata.xml") {
+            continue;
+        }
+        let xml = match std::fs::read_to_string(entry.path()) {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        if file_name == "settings.xml" && xml.contains("<ScriptureText>") {
+            details.source = "Paratext".to_string();
+            details.abbr = xml_element_text(&xml, "Name");
+            details.name = xml_element_text(&xml, "FullName");
+            details.language_code = xml_element_text(&xml, "LanguageIsoCode")
+                .and_then(|c| c.split(":").next().map(|s| s.to_string()))
+                .filter(|c| !c.is_empty());
+            details.language_name = xml_element_text(&xml, "Language");
+            details.versification = xml_element_text(&xml, "Versification")
+                .and_then(|v| paratext_versification_name(v.as_str()));
+            break;
+        }
+        if file_name == "metadata.xml" && xml.contains("<DBLMetadata") {
+            details.source = "USX".to_string();
+            let identification_xml = xml_element_content(&xml, "identification").unwrap_or("");
+            details.abbr = xml_element_text(identification_xml, "abbreviation");
+            details.name = xml_element_text(identification_xml, "name");
+            let language_xml = xml_element_content(&xml, "language").unwrap_or("");
+            details.language_code = xml_element_text(language_xml, "iso");
+            details.language_name = xml_element_text(language_xml, "name");
+        }
+    }
+    details
+}
+
+// Finds USFM books, in files starting with \id, and USX books, converting them to USFM. The first file for each book wins.
+fn imported_books(source_dir: &String) -> BTreeMap<String, String> {
+    let mut books = BTreeMap::new();
+    for entry in WalkDir::new(source_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.path().is_file() {
+            continue;
+        }
+        let file_text = match std::fs::read_to_string(entry.path()) {
+            Ok(s) => s.trim_start_matches('\u{feff}').to_string(),
+            Err(_) => continue,
+        };
+        let is_usx = entry.path().extension().map(|e| e.to_string_lossy().to_lowercase()) == Some("usx".to_string());
+        let usfm = if is_usx {
+            usx_to_usfm(&file_text)
+        } else if file_text.trim_start().starts_with("\\id ") {
+            file_text
+        } else {
+            continue;
+        };
+        if let Some(book_code) = usfm_book_headers(&usfm).book_code {
+            if book_code.len() == 3 && !books.contains_key(&book_code) {
+                books.insert(book_code, usfm);
+            }
+        }
+    }
+    books
+}
+
+// Project details once form values and defaults have been applied
+struct ImportedTextTranslation {
+    source: String,
+    abbr: String,
+    name: String,
+    language_code: String,
+    language_name: String,
+    versification: String,
+}
+
+fn make_imported_burrito(
+    app_resources_dir: &String,
+    new_repo_path: &String,
+    project: &ImportedTextTranslation,
+    books: &BTreeMap<String, String>,
+) -> Result<(), PankosmiaError> {
+    let template_path = |parts: &[&str]| {
+        format!(
+            "{}{}templates{}content_templates{}{}",
+            app_resources_dir,
+            os_slash_str(),
+            os_slash_str(),
+            os_slash_str(),
+            parts.join(os_slash_str())
+        )
+    };
+    let new_repo = init_local_repo(new_repo_path)?;
+    let path_to_ingredients = format!("{}{}ingredients", new_repo_path, os_slash_str());
+    if let Err(e) = std::fs::create_dir(&path_to_ingredients) {
+        return Err(PankosmiaError(format!("Could not create ingredients directory for repo: {}", e)));
+    }
+    if let Ok(gitignore_string) = std::fs::read_to_string(template_path(&["gitignore.txt"])) {
+        if let Err(e) = std::fs::write(format!("{}{}.gitignore", new_repo_path, os_slash_str()), gitignore_string) {
+            return Err(PankosmiaError(format!("Could not write gitignore to repo: {}", e)));
+        }
+    }
+    let versification_schema = match load_json(&versification_template_path(app_resources_dir, &project.versification)?) {
+        Ok(j) => j,
+        Err(e) => return Err(PankosmiaError(format!("Could not load versification JSON: {}", e))),
+    };
+    if let Err(e) = std::fs::write(
+        format!("{}{}vrs.json", path_to_ingredients, os_slash_str()),
+        serde_json::to_string(&versification_schema).unwrap(),
+    ) {
+        return Err(PankosmiaError(format!("Could not write versification to repo: {}", e)));
+    }
+    for (book_code, usfm) in books.iter() {
+        if let Err(e) = std::fs::write(format!("{}{}{}.usfm", path_to_ingredients, os_slash_str(), book_code), usfm) {
+            return Err(PankosmiaError(format!("Could not write usfm to repo: {}", e)));
+        }
+    }
+    let metadata_string = match std::fs::read_to_string(template_path(&["text_translation", "metadata.json"])) {
+        Ok(v) => v,
+        Err(e) => return Err(PankosmiaError(format!("Could not load metadata template as string: {}", e))),
+    };
+    let language_json = json!({"tag": project.language_code, "name": {"en": project.language_name}});
+    let metadata_string = metadata_string
+        .replace("%%CREATED_TIMESTAMP%%", utc_now_timestamp_string().as_str())
+        .replace("%%LANGUAGE%%", language_json.to_string().as_str())
+        .replace("%%SCOPE%%", "");
+    // Names from project settings may contain characters that would need escaping in JSON text
+    let mut metadata: Value = match serde_json::from_str(&metadata_string) {
+        Ok(v) => v,
+        Err(e) => return Err(PankosmiaError(format!("Could not parse metadata template: {}", e))),
+    };
+    replace_in_json_strings(&mut metadata, "%%ABBR%%", project.abbr.as_str());
+    replace_in_json_strings(&mut metadata, "%%CONTENT_NAME%%", project.name.as_str());
+    if let Err(e) = std::fs::write(format!("{}{}metadata.json", new_repo_path, os_slash_str()), metadata.to_string()) {
+        return Err(PankosmiaError(format!("Could not write metadata template to repo: {}", e)));
+    }
+    edit_metadata(app_resources_dir, new_repo_path, |metadata| {
+        refresh_ingredients_in_metadata_value(app_resources_dir.clone(), new_repo_path.clone(), metadata);
+        metadata["localizedNames"] =
+            localized_names_from_usfm(new_repo_path.clone(), project.language_code.clone(), &metadata["localizedNames"]);
+        Ok(())
+    })?;
+    add_and_commit_all(&new_repo, format!("Import from {}", project.source).as_str())
+}
+
+/// *`POST /import-text-translation`*
+///
+/// Typically mounted as **`/git/import-text-translation`**
+///
+/// Creates a new, local textTranslation repo from a Paratext project (Settings.xml plus SFM files), a USX bundle or a folder of USFM files.
+/// The source is either a zip uploaded as *file* or a local *folder* path. The following multipart fields are optional, and override
+/// values read from Paratext Settings.xml or DBL metadata.xml:
+/// - content_abbr
+/// - content_name
+/// - content_language_code
+/// - content_language_name
+/// - versification (string, defaulting to `eng`, which must be one of the vrs content templates)
+///
+/// localizedNames are made from the book headers. Returns the new repo path and the imported book codes.
+/// ```text
+/// {"repo_path": "_local_/_local_/XYZ", "books": ["MAT", "MRK"]}
+/// ```
+#[post("/import-text-translation", format = "multipart/form-data", data = "<form>")]
+pub async fn import_text_translation_repo(
+    state: &State<AppSettings>,
+    mut form: Form<ImportTextTranslationForm<'_>>,
+) -> status::Custom<(ContentType, String)> {
+    // Get source as a directory
+    let unpack_dir = match tempfile::tempdir() {
+        Ok(d) => d,
+        Err(e) => {
+            return not_ok_json_response(
+                Status::InternalServerError,
+                make_bad_json_data_response(format!("Could not make import directory: {}", e)),
+            )
+        }
+    };
+    let folder = form.folder.clone();
+    let source_dir = match (form.file.as_mut(), folder) {
+        (Some(file), _) => {
+            let zip_path = NamedTempFile::new().expect("tempfile");
+            if let Err(e) = file.move_copy_to(&zip_path).await {
+                return not_ok_json_response(
+                    Status::InternalServerError,
+                    make_bad_json_data_response(format!("Could not copy upload: {}", e)),
+                );
+            }
+            let unpack_path = unpack_dir.path().display().to_string();
+            if let Err(e) = unpack_zip_file(zip_path, unpack_path.clone(), None).await {
+                return not_ok_json_response(
+                    Status::BadRequest,
+                    make_bad_json_data_response(format!("Could not unpack zip: {}", e)),
+                );
+            }
+            unpack_path
+        }
+        (None, Some(folder)) => {
+            if !Path::new(&folder).is_dir() {
+                return not_ok_json_response(
+                    Status::BadRequest,
+                    make_bad_json_data_response(format!("Folder '{}' not found", folder)),
+                );
+            }
+            folder
+        }
+        (None, None) => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response("Either file or folder must be provided".to_string()),
+            )
+        }
+    };
+    // Work out details, preferring form values
+    let details = imported_project_details(&source_dir);
+    let books = imported_books(&source_dir);
+    if books.is_empty() {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response("No USFM or USX books found".to_string()),
+        );
+    }
+    let abbr = match form.content_abbr.clone().or(details.abbr) {
+        Some(a) => a,
+        None => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response("No content_abbr provided or found in project settings".to_string()),
+            )
+        }
+    };
+    let name = form.content_name.clone().or(details.name).unwrap_or(abbr.clone());
+    let language_code = match form.content_language_code.clone().or(details.language_code) {
+        Some(c) => c,
+        None => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response("No content_language_code provided or found in project settings".to_string()),
+            )
+        }
+    };
+    let language_name = match form.content_language_name.clone().or(details.language_name) {
+        Some(n) => n,
+        None => {
+            let path_to_language_lookup = format!(
+                "{}{}app_resources{}lookups{}bcp47-language_codes.json",
+                &state.app_resources_dir,
+                os_slash_str(),
+                os_slash_str(),
+                os_slash_str(),
+            );
+            match load_json(&path_to_language_lookup) {
+                Ok(lookup) => lookup[&language_code]["en"].as_str().unwrap_or(&language_code).to_string(),
+                Err(_) => language_code.clone(),
+            }
+        }
+    };
+    let versification = form.versification.clone().or(details.versification).unwrap_or("eng".to_string());
+    match versification_template_path(&state.app_resources_dir, &versification) {
+        Ok(p) if Path::new(&p).is_file() => {}
+        Ok(_) => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response(format!("Unknown versification '{}'", versification)),
+            )
+        }
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    }
+    // Make repo
+    let new_repo_path = match new_local_repo_path(&state.repo_dir.lock().unwrap().clone(), &abbr) {
+        Ok(p) => p,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    let project = ImportedTextTranslation {
+        source: details.source,
+        abbr: abbr.clone(),
+        name,
+        language_code,
+        language_name,
+        versification,
+    };
+    match make_imported_burrito(&state.app_resources_dir, &new_repo_path, &project, &books) {
+        Ok(_) => ok_json_response(
+            json!({
+                "repo_path": format!("_local_/_local_/{}", abbr),
+                "books": books.keys().collect::<Vec<&String>>()
+            })
+            .to_string(),
+        ),
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&new_repo_path);
+            not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0))
+        }
+    }
+}
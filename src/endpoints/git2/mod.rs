@@ -1,4 +1,5 @@
 pub mod new_text_translation;
+pub mod import_text_translation;
 pub mod new_audio_translation;
 pub mod new_bcv_resource;
 pub mod new_obs_resource;
@@ -0,0 +1,233 @@
+use crate::structs::{AppSettings, GlossaryTerm, GlossaryTermForm};
+use crate::utils::burrito::rewrite_ingredients_metadata;
+use crate::utils::glossary::{consistency_warnings, read_glossary, translation_memory, write_glossary};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::full_repo_path;
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use crate::utils::time::utc_now_timestamp_string;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{get, post, State};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+// Serializes read-modify-write of glossary ingredients
+static GLOSSARY_LOCK: Mutex<()> = Mutex::new(());
+
+fn save_glossary(
+    state: &State<AppSettings>,
+    full_path: &String,
+    terms: &[GlossaryTerm],
+) -> Option<status::Custom<(ContentType, String)>> {
+    if let Err(e) = write_glossary(full_path, terms) {
+        return Some(not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)));
+    }
+    if let Err(e) = rewrite_ingredients_metadata(state.app_resources_dir.clone(), full_path.clone()) {
+        return Some(not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)));
+    }
+    None
+}
+
+/// *`GET /lookup/<repo_path>?lemma=θεός&strong=G2316`*
+///
+/// Typically mounted as **`/glossary/lookup/<repo_path>?lemma=θεός&strong=G2316`**
+///
+/// Returns the glossary terms of the burrito at *repo_path* for a lemma or Strong's number, and a translation memory of the renderings
+/// of that lemma in the aligned USFM of every local burrito, most frequent first. At least one of *lemma* and *strong* is required.
+///
+/// ```text
+/// {
+///   "terms": [{"id": "1f0c...", "lemma": "θεός", "strong": "G2316", "renderings": ["god"], "note": null, "approved": true, ...}],
+///   "memory": [
+///     {"rendering": "god", "count": 12, "references": [{"repo_path": "_local_/_local_/my_bible", "book_code": "TIT", "chapter": 1, "verse": 1}, ...]}
+///   ]
+/// }
+/// ```
+#[get("/lookup/<repo_path..>?<lemma>&<strong>")]
+pub fn get_glossary_lookup(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    lemma: Option<String>,
+    strong: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    if lemma.is_none() && strong.is_none() {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response("At least one of lemma and strong must be provided".to_string()),
+        );
+    }
+    let terms: Vec<GlossaryTerm> = match read_glossary(&full_path) {
+        Ok(t) => t,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let terms: Vec<GlossaryTerm> = terms
+        .into_iter()
+        .filter(|t| {
+            lemma.as_ref().is_some_and(|l| *l == t.lemma)
+                || (strong.is_some() && strong == t.strong)
+        })
+        .collect();
+    let memory = translation_memory(&state.repo_dir.lock().unwrap().clone(), &lemma, &strong);
+    ok_json_response(json!({"terms": terms, "memory": memory}).to_string())
+}
+
+/// *`GET /terms/<repo_path>`*
+///
+/// Typically mounted as **`/glossary/terms/<repo_path>`**
+///
+/// Returns the glossary of a burrito, which is stored as the `glossary.json` ingredient.
+#[get("/terms/<repo_path..>")]
+pub fn get_glossary_terms(state: &State<AppSettings>, repo_path: PathBuf) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    match read_glossary(&full_path) {
+        Ok(terms) => ok_json_response(serde_json::to_string(&terms).unwrap()),
+        Err(e) => not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    }
+}
+
+/// *`POST /terms/<repo_path>`*
+///
+/// Typically mounted as **`/glossary/terms/<repo_path>`**
+///
+/// Adds a key term to the glossary of a burrito from JSON with *lemma* and optional *strong*, *renderings* and *note*, and returns it.
+/// New terms are not approved.
+///
+/// ```text
+/// {"lemma": "θεός", "strong": "G2316", "renderings": ["God"], "note": "Capitalized"}
+/// ```
+#[post("/terms/<repo_path..>", format = "json", data = "<json_form>")]
+pub fn post_glossary_term(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    json_form: Json<GlossaryTermForm>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    if json_form.lemma.trim().is_empty() {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response("Term lemma may not be empty".to_string()),
+        );
+    }
+    let _lock = GLOSSARY_LOCK.lock().unwrap();
+    let mut terms = match read_glossary(&full_path) {
+        Ok(t) => t,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let now = utc_now_timestamp_string();
+    let term = GlossaryTerm {
+        id: Uuid::new_v4().to_string(),
+        lemma: json_form.lemma.trim().to_string(),
+        strong: json_form.strong.clone(),
+        renderings: json_form
+            .renderings
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect(),
+        note: json_form.note.clone(),
+        approved: false,
+        created: now.clone(),
+        updated: now,
+    };
+    terms.push(term.clone());
+    if let Some(error_response) = save_glossary(state, &full_path, &terms) {
+        return error_response;
+    }
+    ok_json_response(serde_json::to_string(&term).unwrap())
+}
+
+/// *`POST /approve/<repo_path>?id=1f0c...&approved=true`*
+///
+/// Typically mounted as **`/glossary/approve/<repo_path>?id=1f0c...&approved=true`**
+///
+/// Approves a glossary term, or withdraws approval when *approved* is false, and returns the term.
+#[post("/approve/<repo_path..>?<id>&<approved>")]
+pub fn post_approve_glossary_term(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    id: String,
+    approved: Option<bool>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let _lock = GLOSSARY_LOCK.lock().unwrap();
+    let mut terms = match read_glossary(&full_path) {
+        Ok(t) => t,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let term = match terms.iter_mut().find(|t| t.id == id) {
+        Some(t) => {
+            t.approved = approved.unwrap_or(true);
+            t.updated = utc_now_timestamp_string();
+            t.clone()
+        }
+        None => {
+            return not_ok_json_response(
+                Status::NotFound,
+                make_bad_json_data_response(format!("No glossary term with id '{}'", id)),
+            )
+        }
+    };
+    if let Some(error_response) = save_glossary(state, &full_path, &terms) {
+        return error_response;
+    }
+    ok_json_response(serde_json::to_string(&term).unwrap())
+}
+
+/// *`GET /consistency/<repo_path>?book=TIT`*
+///
+/// Typically mounted as **`/glossary/consistency/<repo_path>?book=TIT`**
+///
+/// Returns verses of a burrito, optionally limited to one book, where a glossary term is aligned to an unexpected rendering.
+/// Terms with renderings are checked against them, other terms against their most frequent rendering.
+///
+/// ```text
+/// [
+///   {
+///     "term_id": "1f0c...",
+///     "lemma": "θεός",
+///     "rendering": "lord",
+///     "expected": ["god"],
+///     "reference": {"repo_path": "_local_/_local_/my_bible", "book_code": "TIT", "chapter": 2, "verse": 5}
+///   }
+/// ]
+/// ```
+#[get("/consistency/<repo_path..>?<book>")]
+pub fn get_glossary_consistency(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let terms = match read_glossary(&full_path) {
+        Ok(t) => t,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let warnings = consistency_warnings(
+        &full_path,
+        &repo_path.display().to_string(),
+        &terms,
+        &book.map(|b| b.to_uppercase()),
+    );
+    ok_json_response(serde_json::to_string(&warnings).unwrap())
+}
@@ -16,3 +16,10 @@ pub mod audio;
 pub mod temp_file;
 pub mod llm;
 pub mod html;
+pub mod search;
+pub mod bookmarks;
+pub mod print;
+pub mod alignment;
+pub mod glossary;
+pub mod spelling;
+pub mod tcore;
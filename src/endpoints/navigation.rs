@@ -1,14 +1,103 @@
-use crate::static_vars::ALIGNMENT_UPDATE_COUNT;
-use crate::structs::{AppSettings, Bcv};
+use crate::static_vars::{ALIGNMENT_UPDATE_COUNT, NAVIGATION_HISTORY_UPDATE_COUNT};
+use crate::structs::{AppSettings, Bcv, NavigationHistory};
 use crate::utils::files::write_app_state;
 use crate::utils::json_responses::make_bad_json_data_response;
 use crate::utils::response::{not_ok_json_response, ok_json_response, ok_ok_json_response};
 use rocket::http::{ContentType, Status};
 use rocket::response::status;
+use crate::utils::files::load_json;
+use crate::utils::metadata_index::local_repo_paths;
+use crate::utils::paths::os_slash_str;
+use crate::utils::references::{book_names_from_repos, format_reference, parse_reference};
 use rocket::{get, post, State};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::sync::atomic::Ordering;
 
+const MAX_NAVIGATION_HISTORY: usize = 100;
+
+// Returns the history with a position added, dropping any forward entries
+fn recorded_navigation(state: &State<AppSettings>, bcv: &Bcv) -> NavigationHistory {
+    let mut history = state.navigation_history.lock().unwrap().clone();
+    if let Some(current) = history.entries.get(history.position) {
+        if current.book_code == bcv.book_code
+            && current.chapter == bcv.chapter
+            && current.verse == bcv.verse
+            && current.to_verse == bcv.to_verse
+        {
+            return history;
+        }
+    }
+    if !history.entries.is_empty() {
+        let keep = history.position + 1;
+        history.entries.truncate(keep);
+    }
+    history.entries.push(bcv.clone());
+    if history.entries.len() > MAX_NAVIGATION_HISTORY {
+        let excess = history.entries.len() - MAX_NAVIGATION_HISTORY;
+        history.entries.drain(..excess);
+    }
+    history.position = history.entries.len() - 1;
+    history
+}
+
+// Replaces the history once the app state holding it has been written
+fn set_navigation_history(state: &State<AppSettings>, history: NavigationHistory) {
+    *state.navigation_history.lock().unwrap() = history;
+    NAVIGATION_HISTORY_UPDATE_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+// Moves through the history and sets global BCV to the entry there
+fn move_in_navigation_history(state: &State<AppSettings>, forward: bool) -> status::Custom<(ContentType, String)> {
+    let mut history = state.navigation_history.lock().unwrap().clone();
+    let new_position = match forward {
+        true => history.position + 1,
+        false => match history.position.checked_sub(1) {
+            Some(p) => p,
+            None => {
+                return not_ok_json_response(
+                    Status::BadRequest,
+                    make_bad_json_data_response("No earlier position in navigation history".to_string()),
+                )
+            }
+        },
+    };
+    let new_bcv = match history.entries.get(new_position) {
+        Some(b) => b.clone(),
+        None => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response("No later position in navigation history".to_string()),
+            )
+        }
+    };
+    history.position = new_position;
+    let new_state_json = json!(
+        {
+            "bcv": new_bcv.clone(),
+            "current_project": state.current_project.lock().unwrap().clone(),
+            "snippet": null,
+            "word": null,
+            "navigation_history": history.clone(),
+        }
+    );
+    match write_app_state(state, new_state_json) {
+        Ok(_) => {}
+        Err(e) => {
+            return not_ok_json_response(
+                Status::InternalServerError,
+                make_bad_json_data_response(format!("Could not write app state: '{}'", &e)),
+            )
+        }
+    }
+    set_navigation_history(state, history);
+    *state.bcv.lock().unwrap() = new_bcv.clone();
+    *state.snippet.lock().unwrap() = None;
+    *state.word.lock().unwrap() = None;
+    let current_alignment_count = ALIGNMENT_UPDATE_COUNT.load(Ordering::Relaxed);
+    ALIGNMENT_UPDATE_COUNT.store(current_alignment_count + 1, Ordering::Relaxed);
+    ok_json_response(serde_json::to_string(&new_bcv).unwrap())
+}
+
 /// *`GET /bcv`*
 ///
 /// Typically mounted as **`/navigation/bcv`**
@@ -44,15 +133,17 @@ pub fn post_bcv_range(
     let new_bcv = Bcv {
         book_code: book_code.to_string(),
         chapter,
-        verse: verse,
-        to_verse: to_verse,
+        verse,
+        to_verse,
     };
+    let history = recorded_navigation(state, &new_bcv);
     let new_state_json = json!(
         {
             "bcv": new_bcv.clone(),
             "current_project": state.current_project.lock().unwrap().clone(),
             "snippet": null,
             "word": null,
+            "navigation_history": history.clone(),
         }
     );
     match write_app_state(state, new_state_json) {
@@ -64,6 +155,7 @@ pub fn post_bcv_range(
             )
         }
     }
+    set_navigation_history(state, history);
     *state.bcv.lock().unwrap() = new_bcv;
     let current_alignment_count = ALIGNMENT_UPDATE_COUNT.load(Ordering::Relaxed);
     ALIGNMENT_UPDATE_COUNT.store(current_alignment_count + 1, Ordering::Relaxed);
@@ -85,15 +177,17 @@ pub fn post_bcv(
     let new_bcv = Bcv {
         book_code: book_code.to_string(),
         chapter,
-        verse: verse,
+        verse,
         to_verse: verse,
     };
+    let history = recorded_navigation(state, &new_bcv);
     let new_state_json = json!(
         {
             "bcv": new_bcv.clone(),
             "current_project": state.current_project.lock().unwrap().clone(),
             "snippet": null,
             "word": null,
+            "navigation_history": history.clone(),
         }
     );
     match write_app_state(state, new_state_json) {
@@ -105,6 +199,7 @@ pub fn post_bcv(
             )
         }
     }
+    set_navigation_history(state, history);
     *state.bcv.lock().unwrap() = new_bcv;
     let mut snippet_inner = state.snippet.lock().unwrap();
     *snippet_inner = None;
@@ -114,3 +209,114 @@ pub fn post_bcv(
     ALIGNMENT_UPDATE_COUNT.store(current_alignment_count + 1, Ordering::Relaxed);
     ok_ok_json_response()
 }
+
+/// *`GET /parse?q=Jn 3:16-18; 1 Cor 13&repo=<repo_path>&style=short|abbr|long`*
+///
+/// Typically mounted as **`/navigation/parse?q=Jn 3:16-18; 1 Cor 13&repo=<repo_path>&style=short|abbr|long`**
+///
+/// Parses one or more references, separated by `;`, into book-code ranges. Books may be given as book codes or as any of the names in the `localizedNames` of local burritos, in full or abbreviated. *repo* restricts the names to those of one local repo. A chapter without verses covers the whole chapter.
+///
+/// Each reference is also formatted back using the user's languages, with names of the given style (default `short`).
+///
+/// ```text
+/// [
+///   {"input": "Jn 3:16-18", "bcv": {"book_code": "JHN", "chapter": 3, "verse": 16, "to_verse": 18}, "formatted": "Jean 3:16-18"},
+///   {"input": "Foo 1", "error": "Unknown book 'Foo'"}
+/// ]
+/// ```
+#[get("/parse?<q>&<repo>&<style>")]
+pub fn parse_references(
+    state: &State<AppSettings>,
+    q: String,
+    repo: Option<String>,
+    style: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let mut repo_paths = local_repo_paths(&repo_dir, &None);
+    if let Some(r) = repo {
+        if !repo_paths.contains(&r) {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response(format!("Repo '{}' not found", r)),
+            );
+        }
+        repo_paths = vec![r];
+    }
+    let book_names = book_names_from_repos(&repo_dir, &repo_paths);
+    let path_to_versification = format!(
+        "{}{}templates{}content_templates{}vrs{}eng.json",
+        &state.app_resources_dir,
+        os_slash_str(),
+        os_slash_str(),
+        os_slash_str(),
+        os_slash_str(),
+    );
+    let max_verses = match load_json(&path_to_versification) {
+        Ok(j) => j["maxVerses"].clone(),
+        Err(e) => {
+            return not_ok_json_response(
+                Status::InternalServerError,
+                make_bad_json_data_response(format!("Could not load versification JSON: {}", e)),
+            )
+        }
+    };
+    let book_codes: Vec<String> = match max_verses.as_object() {
+        Some(o) => o.keys().cloned().collect(),
+        None => vec![],
+    };
+    let languages = state.languages.lock().unwrap().clone();
+    let style = style.unwrap_or("short".to_string());
+    let mut parsed: Vec<Value> = vec![];
+    for reference in q.split(";").map(|r| r.trim()).filter(|r| !r.is_empty()) {
+        parsed.push(match parse_reference(reference, &book_names, &book_codes, &max_verses) {
+            Ok(bcv) => json!({
+                "input": reference,
+                "formatted": format_reference(&bcv, &book_names, &style, &languages),
+                "bcv": bcv
+            }),
+            Err(e) => json!({
+                "input": reference,
+                "error": e
+            }),
+        });
+    }
+    ok_json_response(serde_json::to_string(&parsed).unwrap())
+}
+
+/// *`GET /history`*
+///
+/// Typically mounted as **`/navigation/history`**
+///
+/// Returns the navigation history, oldest first, and the position of the current entry. Up to 100 entries are kept, and the history is saved in the app state.
+///
+/// ```text
+/// {
+///   "entries": [{"book_code": "TIT", "chapter": 1, "verse": 1, "to_verse": 1}, {"book_code": "JHN", "chapter": 3, "verse": 16, "to_verse": 16}],
+///   "position": 1
+/// }
+/// ```
+#[get("/history")]
+pub fn get_navigation_history(state: &State<AppSettings>) -> status::Custom<(ContentType, String)> {
+    let history = state.navigation_history.lock().unwrap().clone();
+    ok_json_response(serde_json::to_string(&history).unwrap())
+}
+
+/// *`POST /history/back`*
+///
+/// Typically mounted as **`/navigation/history/back`**
+///
+/// Sets global BCV to the previous entry in the navigation history, and returns it.
+#[post("/history/back")]
+pub fn post_navigation_back(state: &State<AppSettings>) -> status::Custom<(ContentType, String)> {
+    move_in_navigation_history(state, false)
+}
+
+/// *`POST /history/forward`*
+///
+/// Typically mounted as **`/navigation/history/forward`**
+///
+/// Sets global BCV to the next entry in the navigation history, and returns it.
+#[post("/history/forward")]
+pub fn post_navigation_forward(state: &State<AppSettings>) -> status::Custom<(ContentType, String)> {
+    move_in_navigation_history(state, true)
+}
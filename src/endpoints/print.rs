@@ -0,0 +1,135 @@
+use crate::structs::{AppSettings, PrintJob};
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, os_slash_str};
+use crate::utils::print::{print_spec_source, read_print_spec, start_print_job, PrintRequest, PRINT_JOBS};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, post, State};
+use serde_json::json;
+use std::path::{Components, PathBuf};
+
+/// *`POST /render/<repo_path>?format=html&destination=temp&source=_local_/_local_/my_bible`*
+///
+/// Typically mounted as **`/print/render/<repo_path>?format=html&destination=temp&source=_local_/_local_/my_bible`**
+///
+/// Starts a job that prints the scripture burrito referenced by the x-printspec repo at *repo_path*, and returns the job id.
+/// The spec is read from `ingredients/plan.json`, as written by `/git/new-print-spec-resource`, or else from `ingredients/spec.json`, and may contain:
+/// - source (repo path of the scripture burrito, which *source* overrides)
+/// - books (array of book codes, defaulting to all books)
+/// - page.size (a paper name such as `A5`, the default, or `letter`) and page.margin (a length in mm, cm, in or pt, eg `15mm`, the default)
+/// - columns (1 to 4, defaulting to 1)
+///
+/// *format* is `html` (the default), for HTML with CSS for paged media, or `context`, for ConTeXt source.
+/// *destination* is `temp` (the default), for a temp file, or `repo`, for `ingredients/output/print.<html|tex>` in the print spec repo.
+///
+/// ```text
+/// {"job_id": "5b1c..."}
+/// ```
+#[post("/render/<repo_path..>?<format>&<destination>&<source>")]
+pub fn post_print_render(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    format: Option<String>,
+    destination: Option<String>,
+    source: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let path_components: Components<'_> = repo_path.components();
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let full_repo_path = format!("{}{}{}", repo_dir, os_slash_str(), &repo_path.display().to_string());
+    if !check_path_components(&mut path_components.clone()) || !std::path::Path::new(&full_repo_path).is_dir() {
+        return not_ok_bad_repo_json_response();
+    }
+    let spec = match read_print_spec(&full_repo_path) {
+        Ok(s) => s,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    let source_repo_path = match source.or(print_spec_source(&spec)) {
+        Some(s) => s,
+        None => {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response("No source burrito in print spec or request".to_string()),
+            )
+        }
+    };
+    if !check_path_components(&mut PathBuf::from(&source_repo_path).components())
+        || !std::path::Path::new(&format!("{}{}{}", repo_dir, os_slash_str(), source_repo_path)).is_dir()
+    {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response(format!("Source burrito '{}' not found", source_repo_path)),
+        );
+    }
+    let format = format.unwrap_or("html".to_string());
+    let destination = destination.unwrap_or("temp".to_string());
+    if !["html", "context"].contains(&format.as_str()) || !["temp", "repo"].contains(&destination.as_str()) {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response(format!("Unknown format '{}' or destination '{}'", format, destination)),
+        );
+    }
+    let job_id = start_print_job(PrintRequest {
+        app_resources_dir: state.app_resources_dir.clone(),
+        repo_dir,
+        working_dir: state.working_dir.clone(),
+        repo_path: repo_path.display().to_string(),
+        source_repo_path,
+        format,
+        destination,
+        spec,
+        typography: state.typography.lock().unwrap().clone(),
+    });
+    ok_json_response(json!({"job_id": job_id}).to_string())
+}
+
+/// *`GET /job/<job_id>`*
+///
+/// Typically mounted as **`/print/job/<job_id>`**
+///
+/// Returns a print job. *status* is `running`, `done` or `failed`. When done, *output* is a temp file id, which can be viewed at
+/// `/temp/html/<id>` or read at `/temp/bytes/<id>`, or the ingredient path of the output in the print spec repo.
+/// A job that is done or failed is removed once it has been returned, so later requests for it return 404.
+///
+/// ```text
+/// {
+///   "id": "5b1c...",
+///   "repo_path": "_local_/_local_/my_print_spec",
+///   "source_repo_path": "_local_/_local_/my_bible",
+///   "format": "html",
+///   "destination": "temp",
+///   "status": "done",
+///   "output": "a91e...",
+///   "error": null,
+///   "started": "2025-03-01T10:00:00.000Z",
+///   "finished": "2025-03-01T10:00:02.000Z"
+/// }
+/// ```
+#[get("/job/<job_id>")]
+pub fn get_print_job(job_id: String) -> status::Custom<(ContentType, String)> {
+    let mut jobs = PRINT_JOBS.lock().unwrap();
+    match jobs.get(&job_id).cloned() {
+        Some(job) => {
+            if job.status != "running" {
+                jobs.remove(&job_id);
+            }
+            ok_json_response(serde_json::to_string(&job).unwrap())
+        }
+        None => not_ok_json_response(
+            Status::NotFound,
+            make_bad_json_data_response(format!("No print job with id '{}'", job_id)),
+        ),
+    }
+}
+
+/// *`GET /jobs`*
+///
+/// Typically mounted as **`/print/jobs`**
+///
+/// Returns the print jobs that are running or have finished but not yet been fetched, oldest first.
+#[get("/jobs")]
+pub fn list_print_jobs() -> status::Custom<(ContentType, String)> {
+    let mut jobs: Vec<PrintJob> = PRINT_JOBS.lock().unwrap().values().cloned().collect();
+    jobs.sort_by(|a, b| a.started.cmp(&b.started));
+    ok_json_response(serde_json::to_string(&jobs).unwrap())
+}
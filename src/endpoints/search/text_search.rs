@@ -0,0 +1,64 @@
+use crate::structs::AppSettings;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::metadata_index::local_repo_paths;
+use crate::utils::response::{not_ok_json_response, ok_json_response};
+use crate::utils::search_index::search_repos;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, State};
+
+/// *`GET /?q=my+words&repo=<repo_path>&book=JHN&limit=100`*
+///
+/// Typically mounted as **`/api/search?q=my+words&repo=<repo_path>&book=JHN&limit=100`**
+///
+/// Searches the text ingredients of local repos (USFM without markup, TSV notes and markdown) for segments containing every word of *q*, ignoring case. *repo* restricts the search to one repo, *book* to one book, and *limit* (default 100) caps the number of hits.
+///
+/// The search index is kept per repo in the working dir and is brought up to date for changed ingredients on each search, so no network access is needed.
+///
+/// ```text
+/// [
+///   {
+///     "repo": "git.door43.org/BurritoTruck/fr_psle",
+///     "ipath": "JHN.usfm",
+///     "bcv": {"book_code": "JHN", "chapter": 3, "verse": 16, "to_verse": 16},
+///     "snippet": "Car Dieu a tant aimé le monde…"
+///   }
+/// ]
+/// ```
+#[get("/?<q>&<repo>&<book>&<limit>")]
+pub fn search(
+    state: &State<AppSettings>,
+    q: String,
+    repo: Option<String>,
+    book: Option<String>,
+    limit: Option<usize>,
+) -> status::Custom<(ContentType, String)> {
+    if q.trim().is_empty() {
+        return not_ok_json_response(
+            Status::BadRequest,
+            make_bad_json_data_response("Empty search query".to_string()),
+        );
+    }
+    let repo_dir = state.repo_dir.lock().unwrap().clone();
+    let mut repo_paths = local_repo_paths(&repo_dir, &None);
+    if let Some(r) = repo {
+        if !repo_paths.contains(&r) {
+            return not_ok_json_response(
+                Status::BadRequest,
+                make_bad_json_data_response(format!("Repo '{}' not found", r)),
+            );
+        }
+        repo_paths = vec![r];
+    }
+    match search_repos(
+        &state.working_dir,
+        &repo_dir,
+        &repo_paths,
+        &q,
+        &book,
+        limit.unwrap_or(100),
+    ) {
+        Ok(hits) => ok_json_response(serde_json::to_string(&hits).unwrap()),
+        Err(e) => not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    }
+}
@@ -0,0 +1,171 @@
+use crate::structs::{AppSettings, PankosmiaError, Wordlist};
+use crate::utils::alignment::read_book_usfm;
+use crate::utils::burrito::rewrite_ingredients_metadata;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::full_repo_path;
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use crate::utils::wordlist::{build_wordlist, mark_word, read_wordlist, repo_language, suspicious_words, write_wordlist};
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::{get, post, State};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Serializes read-modify-write of wordlist ingredients
+static WORDLIST_LOCK: Mutex<()> = Mutex::new(());
+
+// The wordlist ingredient of a burrito, or a new one built from local burritos in its language, or the status of the error
+fn current_wordlist(state: &State<AppSettings>, full_path: &String) -> Result<Wordlist, (Status, PankosmiaError)> {
+    match read_wordlist(full_path) {
+        Ok(Some(wordlist)) => return Ok(wordlist),
+        Ok(None) => {}
+        Err(e) => return Err((Status::InternalServerError, e)),
+    }
+    match repo_language(full_path) {
+        Ok(language) => Ok(build_wordlist(&state.repo_dir.lock().unwrap().clone(), &language, None)),
+        Err(e) => Err((Status::BadRequest, e)),
+    }
+}
+
+fn save_wordlist(
+    state: &State<AppSettings>,
+    full_path: &String,
+    wordlist: &Wordlist,
+) -> Option<status::Custom<(ContentType, String)>> {
+    if let Err(e) = write_wordlist(full_path, wordlist) {
+        return Some(not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)));
+    }
+    if let Err(e) = rewrite_ingredients_metadata(state.app_resources_dir.clone(), full_path.clone()) {
+        return Some(not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)));
+    }
+    None
+}
+
+/// *`GET /wordlist/<repo_path>`*
+///
+/// Typically mounted as **`/spelling/wordlist/<repo_path>`**
+///
+/// Returns the wordlist of a burrito, which is stored as the `wordlist.json` ingredient, or null if it has not been built.
+///
+/// ```text
+/// {
+///   "language": "fr",
+///   "built": "2025-03-01T10:00:00.000Z",
+///   "words": {"dieu": 412, "paul": 37, ...},
+///   "correct": ["tite"],
+///   "incorrect": ["appôtre"]
+/// }
+/// ```
+#[get("/wordlist/<repo_path..>")]
+pub fn get_wordlist(state: &State<AppSettings>, repo_path: PathBuf) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    match read_wordlist(&full_path) {
+        Ok(wordlist) => ok_json_response(serde_json::to_string(&wordlist).unwrap()),
+        Err(e) => not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    }
+}
+
+/// *`POST /build/<repo_path>`*
+///
+/// Typically mounted as **`/spelling/build/<repo_path>`**
+///
+/// Rebuilds the wordlist of a burrito from the USFM ingredients of every local burrito in the same language, keeping words already marked
+/// correct or incorrect, and writes it as the `wordlist.json` ingredient.
+///
+/// ```text
+/// {"language": "fr", "words": 5120}
+/// ```
+#[post("/build/<repo_path..>")]
+pub fn post_build_wordlist(state: &State<AppSettings>, repo_path: PathBuf) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let language = match repo_language(&full_path) {
+        Ok(l) => l,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    let _lock = WORDLIST_LOCK.lock().unwrap();
+    let previous_wordlist = match read_wordlist(&full_path) {
+        Ok(w) => w,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let wordlist = build_wordlist(&state.repo_dir.lock().unwrap().clone(), &language, previous_wordlist);
+    if let Some(error_response) = save_wordlist(state, &full_path, &wordlist) {
+        return error_response;
+    }
+    ok_json_response(json!({"language": wordlist.language, "words": wordlist.words.len()}).to_string())
+}
+
+/// *`POST /mark/<repo_path>?word=tite&status=correct`*
+///
+/// Typically mounted as **`/spelling/mark/<repo_path>?word=tite&status=correct`**
+///
+/// Marks a word as `correct` or `incorrect` in the wordlist of a burrito, or forgets that decision with `unknown`.
+/// The wordlist is built first if needed.
+#[post("/mark/<repo_path..>?<word>&<status>")]
+pub fn post_mark_word(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    word: String,
+    status: String,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let _lock = WORDLIST_LOCK.lock().unwrap();
+    let mut wordlist = match current_wordlist(state, &full_path) {
+        Ok(w) => w,
+        Err((error_status, e)) => return not_ok_json_response(error_status, make_bad_json_data_response(e.0)),
+    };
+    if let Err(e) = mark_word(&mut wordlist, &word, &status) {
+        return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0));
+    }
+    if let Some(error_response) = save_wordlist(state, &full_path, &wordlist) {
+        return error_response;
+    }
+    ok_json_response(json!({"word": word.trim().to_lowercase(), "status": status}).to_string())
+}
+
+/// *`GET /check/<repo_path>?book=TIT&chapter=1&verse=1&min_count=2`*
+///
+/// Typically mounted as **`/spelling/check/<repo_path>?book=TIT&chapter=1&verse=1&min_count=2`**
+///
+/// Returns suspicious words in a USFM book, optionally limited to a chapter or verse: words marked incorrect, and words not marked correct
+/// that occur fewer than *min_count* (default 2) times in the wordlist. References are `[chapter, verse]`.
+///
+/// ```text
+/// [
+///   {"word": "appôtre", "reason": "incorrect", "count": 1, "references": [[1, 1]]},
+///   {"word": "crétois", "reason": "rare", "count": 1, "references": [[1, 12]]}
+/// ]
+/// ```
+#[get("/check/<repo_path..>?<book>&<chapter>&<verse>&<min_count>")]
+pub fn get_spelling_check(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    chapter: Option<u16>,
+    verse: Option<u16>,
+    min_count: Option<usize>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let (_, usfm) = match read_book_usfm(&state.app_resources_dir, &full_path, &book.to_uppercase()) {
+        Ok(u) => u,
+        Err(e) => return not_ok_json_response(Status::NotFound, make_bad_json_data_response(e.0)),
+    };
+    let wordlist = match current_wordlist(state, &full_path) {
+        Ok(w) => w,
+        Err((error_status, e)) => return not_ok_json_response(error_status, make_bad_json_data_response(e.0)),
+    };
+    let suspicious = suspicious_words(&usfm, &wordlist, chapter, verse, min_count.unwrap_or(2));
+    ok_json_response(serde_json::to_string(&suspicious).unwrap())
+}
@@ -1,4 +1,4 @@
-use crate::static_vars::{DEBUG_IS_ENABLED, I18N_UPDATE_COUNT, ALIGNMENT_UPDATE_COUNT, NET_IS_ENABLED};
+use crate::static_vars::{DEBUG_IS_ENABLED, I18N_UPDATE_COUNT, ALIGNMENT_UPDATE_COUNT, BOOKMARKS_UPDATE_COUNT, NAVIGATION_HISTORY_UPDATE_COUNT, NET_IS_ENABLED};
 use crate::structs::AppSettings;
 use crate::MsgQueue;
 use rocket::response::stream;
@@ -24,6 +24,8 @@ pub async fn notifications_stream<'a>(
         let mut languages = state.languages.lock().unwrap().clone().join("/");
         let mut i18n_update_count = I18N_UPDATE_COUNT.load(Ordering::Relaxed);
         let mut alignment_update_count = ALIGNMENT_UPDATE_COUNT.load(Ordering::Relaxed);
+        let mut navigation_history_update_count = NAVIGATION_HISTORY_UPDATE_COUNT.load(Ordering::Relaxed);
+        let mut bookmarks_update_count = BOOKMARKS_UPDATE_COUNT.load(Ordering::Relaxed);
         let mut typography = state.typography.lock().unwrap().clone();
         let mut bcv = state.bcv.lock().unwrap().clone();
         let gitea_endpoints = state.gitea_endpoints.clone();
@@ -163,6 +165,27 @@ pub async fn notifications_stream<'a>(
                 .id(format!("{}", count));
                 count+=1;
             }
+            let new_navigation_history_update = NAVIGATION_HISTORY_UPDATE_COUNT.load(Ordering::Relaxed);
+            if first_time || new_navigation_history_update > navigation_history_update_count {
+                navigation_history_update_count = new_navigation_history_update;
+                let history = state.navigation_history.lock().unwrap().clone();
+                yield stream::Event::data(
+                    format!("{}--{}", history.position, history.entries.len())
+                )
+                .event("navigation_history")
+                .id(format!("{}", count));
+                count+=1;
+            }
+            let new_bookmarks_update = BOOKMARKS_UPDATE_COUNT.load(Ordering::Relaxed);
+            if new_bookmarks_update > bookmarks_update_count {
+                bookmarks_update_count = new_bookmarks_update;
+                yield stream::Event::data(
+                    "update"
+                )
+                .event("bookmarks")
+                .id(format!("{}", count));
+                count+=1;
+            }
             first_time = false;
             interval.tick().await;
         }
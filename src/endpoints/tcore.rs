@@ -0,0 +1,284 @@
+use crate::structs::{AppSettings, CheckItemForm};
+use crate::utils::bcv_ref::canonical_book_code;
+use crate::utils::burrito::rewrite_ingredients_metadata;
+use crate::utils::json_responses::make_bad_json_data_response;
+use crate::utils::paths::{check_path_components, full_repo_path};
+use crate::utils::response::{not_ok_bad_repo_json_response, not_ok_json_response, ok_json_response};
+use crate::utils::tcore_checks::{
+    book_check_progress, book_check_report, generate_book_checks, read_all_book_checks, read_book_checks,
+    write_book_checks,
+};
+use crate::utils::time::utc_now_timestamp_string;
+use rocket::http::{ContentType, Status};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{get, post, State};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Serializes read-modify-write of check items
+static CHECKS_LOCK: Mutex<()> = Mutex::new(());
+
+// The canonical book code for a book parameter, which is then safe to use in the path of a checks file
+fn canonical_book(state: &State<AppSettings>, book: &str) -> Option<String> {
+    canonical_book_code(&state.app_resources_dir, book)
+}
+
+fn unknown_book_response(book: &str) -> status::Custom<(ContentType, String)> {
+    not_ok_json_response(
+        Status::BadRequest,
+        make_bad_json_data_response(format!("Unknown book '{}'", book)),
+    )
+}
+
+fn linked_repo_ok(repo_path: &Option<String>) -> bool {
+    match repo_path {
+        Some(p) => check_path_components(&mut PathBuf::from(p).components()),
+        None => true,
+    }
+}
+
+/// *`POST /generate/<repo_path>?book=TIT&target=_local_/_local_/my_bible&notes=git.door43.org/unfoldingWord/en_tn&words=git.door43.org/unfoldingWord/en_twl`*
+///
+/// Typically mounted as **`/tcore/generate/<repo_path>?book=TIT&target=...&notes=...&words=...`**
+///
+/// Generates the check items of a book in the x-tcore repo at *repo_path*, from the rows of a translation notes repo (*notes*)
+/// and of a translation words links repo (*words*) that have a quote. *target* is the textTranslation being checked.
+/// Items that were generated before keep their status, and checks that cannot be parsed are not regenerated. Returns the number
+/// of items by category.
+///
+/// ```text
+/// {"book_code": "TIT", "total": 412, "categories": {"translationNotes": {"total": 180, "selected": 0, "percent": 0.0}, ...}, ...}
+/// ```
+#[post("/generate/<repo_path..>?<book>&<target>&<notes>&<words>")]
+pub fn post_generate_checks(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    target: String,
+    notes: Option<String>,
+    words: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    if !linked_repo_ok(&Some(target.clone())) || !linked_repo_ok(&notes) || !linked_repo_ok(&words) {
+        return not_ok_bad_repo_json_response();
+    }
+    let book_code = match canonical_book(state, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    let _lock = CHECKS_LOCK.lock().unwrap();
+    // Checks that cannot be parsed are not regenerated, since that would lose their status
+    let previous = match read_book_checks(&full_path, &book_code) {
+        Ok(c) => c,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let checks = match generate_book_checks(
+        &state.repo_dir.lock().unwrap().clone(),
+        previous,
+        &book_code,
+        &target,
+        &notes,
+        &words,
+    ) {
+        Ok(c) => c,
+        Err(e) => return not_ok_json_response(Status::BadRequest, make_bad_json_data_response(e.0)),
+    };
+    if let Err(e) = write_book_checks(&full_path, &checks) {
+        return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0));
+    }
+    if let Err(e) = rewrite_ingredients_metadata(state.app_resources_dir.clone(), full_path) {
+        return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0));
+    }
+    ok_json_response(serde_json::to_string(&book_check_progress(&checks)).unwrap())
+}
+
+/// *`GET /items/<repo_path>?book=TIT&category=translationWords&group=god`*
+///
+/// Typically mounted as **`/tcore/items/<repo_path>?book=TIT&category=translationWords&group=god`**
+///
+/// Returns the check items of a book, optionally filtered by *category* (`translationNotes` or `translationWords`) and *group*
+/// (the translation academy article or translation word).
+///
+/// ```text
+/// [
+///   {
+///     "id": "tw:xyz9",
+///     "category": "translationWords",
+///     "group": "god",
+///     "chapter": 1,
+///     "verse": 1,
+///     "quote": "Θεοῦ",
+///     "occurrence": 1,
+///     "note": "rc://*/tw/dict/bible/kt/god",
+///     "selections": ["God"],
+///     "comment": null,
+///     "verse_edited": false,
+///     "updated": "2025-03-01T10:00:00.000Z"
+///   }
+/// ]
+/// ```
+#[get("/items/<repo_path..>?<book>&<category>&<group>")]
+pub fn get_check_items(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    category: Option<String>,
+    group: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let book_code = match canonical_book(state, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    let checks = match read_book_checks(&full_path, &book_code) {
+        Ok(Some(c)) => c,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+        Ok(None) => {
+            return not_ok_json_response(
+                Status::NotFound,
+                make_bad_json_data_response(format!("No checks generated for {}", book)),
+            )
+        }
+    };
+    let items: Vec<_> = checks
+        .items
+        .into_iter()
+        .filter(|i| category.as_ref().is_none_or(|c| *c == i.category))
+        .filter(|i| group.as_ref().is_none_or(|g| *g == i.group))
+        .collect();
+    ok_json_response(serde_json::to_string(&items).unwrap())
+}
+
+/// *`POST /item/<repo_path>?book=TIT&id=tw:xyz9`*
+///
+/// Typically mounted as **`/tcore/item/<repo_path>?book=TIT&id=tw:xyz9`**
+///
+/// Records the status of a check item from JSON with optional *selections* (the selected target text), *comment* and *verse_edited*,
+/// and returns the item. Fields that are not provided are unchanged, and an empty comment removes the comment.
+///
+/// ```text
+/// {"selections": ["God"], "comment": "Capitalized", "verse_edited": false}
+/// ```
+#[post("/item/<repo_path..>?<book>&<id>", format = "json", data = "<json_form>")]
+pub fn post_check_item(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: String,
+    id: String,
+    json_form: Json<CheckItemForm>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let book_code = match canonical_book(state, &book) {
+        Some(b) => b,
+        None => return unknown_book_response(&book),
+    };
+    let _lock = CHECKS_LOCK.lock().unwrap();
+    let mut checks = match read_book_checks(&full_path, &book_code) {
+        Ok(Some(c)) => c,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+        Ok(None) => {
+            return not_ok_json_response(
+                Status::NotFound,
+                make_bad_json_data_response(format!("No checks generated for {}", book)),
+            )
+        }
+    };
+    let item = match checks.items.iter_mut().find(|i| i.id == id) {
+        Some(i) => {
+            if let Some(selections) = &json_form.selections {
+                i.selections = selections.iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+            }
+            if let Some(comment) = &json_form.comment {
+                i.comment = if comment.trim().is_empty() { None } else { Some(comment.trim().to_string()) };
+            }
+            if let Some(verse_edited) = json_form.verse_edited {
+                i.verse_edited = verse_edited;
+            }
+            i.updated = Some(utc_now_timestamp_string());
+            i.clone()
+        }
+        None => {
+            return not_ok_json_response(
+                Status::NotFound,
+                make_bad_json_data_response(format!("No check item with id '{}'", id)),
+            )
+        }
+    };
+    if let Err(e) = write_book_checks(&full_path, &checks) {
+        return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0));
+    }
+    if let Err(e) = rewrite_ingredients_metadata(state.app_resources_dir.clone(), full_path) {
+        return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0));
+    }
+    ok_json_response(serde_json::to_string(&item).unwrap())
+}
+
+/// *`GET /progress/<repo_path>`*
+///
+/// Typically mounted as **`/tcore/progress/<repo_path>`**
+///
+/// Returns, for each book with generated checks, the number of items with a selection, by category and overall.
+///
+/// ```text
+/// [
+///   {
+///     "book_code": "TIT",
+///     "categories": {"translationNotes": {"total": 180, "selected": 45, "percent": 25.0}, "translationWords": {...}},
+///     "total": 412,
+///     "selected": 101,
+///     "percent": 24.5
+///   }
+/// ]
+/// ```
+#[get("/progress/<repo_path..>")]
+pub fn get_checks_progress(state: &State<AppSettings>, repo_path: PathBuf) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let all_checks = match read_all_book_checks(&full_path) {
+        Ok(c) => c,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let progress: Vec<_> = all_checks.iter().map(book_check_progress).collect();
+    ok_json_response(serde_json::to_string(&progress).unwrap())
+}
+
+/// *`GET /report/<repo_path>?book=TIT`*
+///
+/// Typically mounted as **`/tcore/report/<repo_path>?book=TIT`**
+///
+/// Returns a report for each book, or for *book*: counts of selected, commented and verse-edited items, selected items by group,
+/// and the items with a status.
+#[get("/report/<repo_path..>?<book>")]
+pub fn get_checks_report(
+    state: &State<AppSettings>,
+    repo_path: PathBuf,
+    book: Option<String>,
+) -> status::Custom<(ContentType, String)> {
+    let full_path = match full_repo_path(&state.repo_dir.lock().unwrap(), &repo_path) {
+        Some(p) => p,
+        None => return not_ok_bad_repo_json_response(),
+    };
+    let all_checks = match read_all_book_checks(&full_path) {
+        Ok(c) => c,
+        Err(e) => return not_ok_json_response(Status::InternalServerError, make_bad_json_data_response(e.0)),
+    };
+    let reports: Vec<_> = all_checks
+        .iter()
+        .filter(|c| book.as_ref().is_none_or(|b| b.to_uppercase() == c.book_code))
+        .map(book_check_report)
+        .collect();
+    ok_json_response(json!({"books": reports}).to_string())
+}
@@ -30,6 +30,21 @@ use crate::structs::ClientConfigSection;
 
 type MsgQueue = Arc<Mutex<VecDeque<String>>>;
 
+/// Makes the launch config for `rocket()` from the working dir, which may be empty for the default, and the app resources path,
+/// which must end with a path separator.
+pub fn launch_config(working_dir: String, app_resources_path: String) -> Value {
+    let webfont_path = format!("{}webfonts", app_resources_path);
+    let app_setup_path = format!("{}setup/app_setup.json", app_resources_path);
+    let local_setup_path = format!("{}setup/local_setup.json", app_resources_path);
+    json!({
+        "working_dir": working_dir,
+        "webfont_path": webfont_path,
+        "app_setup_path": app_setup_path,
+        "local_setup_path": local_setup_path,
+        "app_resources_path": app_resources_path,
+    })
+}
+
 pub fn rocket(launch_config: Value) -> Rocket<Build> {
     println!("OS = '{}'", env::consts::OS);
 
@@ -3,4 +3,6 @@ use std::sync::atomic::{AtomicBool, AtomicUsize};
 pub(crate) static NET_IS_ENABLED: AtomicBool = AtomicBool::new(false);
 pub(crate) static DEBUG_IS_ENABLED: AtomicBool = AtomicBool::new(false);
 pub(crate) static I18N_UPDATE_COUNT: AtomicUsize = AtomicUsize::new(0);
-pub(crate) static ALIGNMENT_UPDATE_COUNT: AtomicUsize = AtomicUsize::new(0);
\ No newline at end of file
+pub(crate) static ALIGNMENT_UPDATE_COUNT: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static NAVIGATION_HISTORY_UPDATE_COUNT: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static BOOKMARKS_UPDATE_COUNT: AtomicUsize = AtomicUsize::new(0);
@@ -28,6 +28,12 @@ pub struct Bcv {
     pub to_verse: u16
 }
 
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct NavigationHistory {
+    pub entries: Vec<Bcv>,
+    pub position: usize,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TypographyFeature {
     pub key: String,
@@ -62,6 +68,154 @@ pub struct ProjectIdentifier {
     pub project: String
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bookmark {
+    pub id: String,
+    pub bcv: Bcv,
+    pub project: Option<ProjectIdentifier>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+    pub created: String,
+    pub updated: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PrintJob {
+    pub id: String,
+    pub repo_path: String,
+    pub source_repo_path: String,
+    pub format: String,
+    pub destination: String,
+    pub status: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub started: String,
+    pub finished: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AlignmentSource {
+    pub content: String,
+    pub strong: Option<String>,
+    pub lemma: Option<String>,
+    pub morph: Option<String>,
+    pub occurrence: u16,
+    pub occurrences: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AlignmentTarget {
+    pub word: String,
+    pub occurrence: u16,
+    pub occurrences: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Alignment {
+    pub sources: Vec<AlignmentSource>,
+    pub targets: Vec<AlignmentTarget>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct VerseAlignment {
+    pub words: Vec<AlignmentTarget>,
+    pub alignments: Vec<Alignment>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AlignmentForm {
+    pub sources: Option<Vec<AlignmentSource>>,
+    pub targets: Option<Vec<AlignmentTarget>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GlossaryTerm {
+    pub id: String,
+    pub lemma: String,
+    pub strong: Option<String>,
+    pub renderings: Vec<String>,
+    pub note: Option<String>,
+    pub approved: bool,
+    pub created: String,
+    pub updated: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GlossaryTermForm {
+    pub lemma: String,
+    pub strong: Option<String>,
+    pub renderings: Option<Vec<String>>,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Wordlist {
+    pub language: String,
+    pub built: Option<String>,
+    pub words: BTreeMap<String, usize>,
+    pub correct: Vec<String>,
+    pub incorrect: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CheckItem {
+    pub id: String,
+    pub category: String,
+    pub group: String,
+    pub chapter: u16,
+    pub verse: u16,
+    pub quote: String,
+    pub occurrence: i32,
+    pub note: String,
+    pub selections: Vec<String>,
+    pub comment: Option<String>,
+    pub verse_edited: bool,
+    pub updated: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BookChecks {
+    pub book_code: String,
+    pub target: String,
+    pub notes: Option<String>,
+    pub words: Option<String>,
+    pub generated: String,
+    pub items: Vec<CheckItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CheckItemForm {
+    pub selections: Option<Vec<String>>,
+    pub comment: Option<String>,
+    pub verse_edited: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IngredientTransferForm {
+    pub target: String,
+    pub books: Option<Vec<String>>,
+    pub ipaths: Option<Vec<String>>,
+    pub delete_src: Option<bool>,
+    pub overwrite: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BurritoMergeForm {
+    pub abbr: String,
+    pub repo_paths: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BookmarkForm {
+    pub bcv: Bcv,
+    pub project: Option<ProjectIdentifier>,
+    pub note: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SelectedWord {
     pub target: Option<String>,
@@ -83,6 +237,7 @@ pub struct AppSettings {
     pub word: Mutex<Option<SelectedWord>>,
     pub typography: Mutex<Typography>,
     pub current_project: Mutex<Option<ProjectIdentifier>>,
+    pub navigation_history: Mutex<NavigationHistory>,
     pub product: ProductSpec,
     pub client_config: BTreeMap<String, Vec<ClientConfigSection>>
 }
@@ -133,7 +288,7 @@ pub struct GitStatusRecord {
     pub change_type: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct MetadataSummary {
     pub name: String,
     pub description: String,
@@ -146,6 +301,10 @@ pub struct MetadataSummary {
     pub script_direction: String,
     pub book_codes: Vec<String>,
     pub timestamp: u64,
+    pub names: BTreeMap<String, String>,
+    pub descriptions: BTreeMap<String, String>,
+    pub abbreviations: BTreeMap<String, String>,
+    pub language_names: BTreeMap<String, String>,
 }
 
 #[derive(Responder)]
@@ -171,6 +330,27 @@ pub struct NewBcvResourceBookForm {
     pub vrs_name: Option<String>
 }
 
+#[derive(FromForm, Debug)]
+pub struct MetadataIndexQuery {
+    pub org: Option<String>,
+    pub flavor: Option<String>,
+    pub language: Option<String>,
+    pub book: Option<String>,
+    pub updated_since: Option<u64>,
+    pub sort: Option<String>,
+    pub lang: Option<String>,
+}
+
+#[derive(FromForm, Debug)]
+pub struct VersesQuery {
+    pub book: Option<String>,
+    pub chapter: Option<u16>,
+    pub verse: Option<u16>,
+    pub to_verse: Option<u16>,
+    pub format: Option<String>,
+    pub versification: Option<String>,
+}
+
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BurritoMetadataIngredient {
@@ -4,13 +4,15 @@ use std::io::Read;
 use rocket::local::blocking::Client;
 use rocket::http::Status;
 
-use super::rocket;
+use super::{launch_config, rocket};
 
 #[track_caller]
 fn test_query_file<T> (path: &str, file: T, status: Status)
     where T: Into<Option<&'static str>>
 {
-    let client = Client::tracked(rocket()).unwrap();
+    // App resources are found as by main, from APP_RESOURCES_DIR or else the crate directory
+    let app_resources_path = std::env::var("APP_RESOURCES_DIR").unwrap_or(rocket::fs::relative!("").to_string());
+    let client = Client::tracked(rocket(launch_config("".to_string(), app_resources_path))).unwrap();
     let response = client.get(path).dispatch();
     assert_eq!(response.status(), status);
 
@@ -0,0 +1,571 @@
+use crate::structs::{Alignment, AlignmentSource, AlignmentTarget, PankosmiaError, VerseAlignment};
+use crate::utils::burrito::{rewrite_ingredients_metadata, usfm_ingredient_for_book};
+use crate::utils::files::write_file_atomically;
+use crate::utils::paths::os_slash_str;
+use crate::utils::usfm::{usfm_book_headers, usfm_verses, UsfmVerse, USFM_WORD_PATTERN};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+// USFM that never holds alignable words: headings, notes, milestones other than zaln, and markers
+const PROTECTED_PATTERN: &str = r"(?m:^[ \t]*\\(?:s\d?|ms\d?|mr|r|sr|sp|cl|rem|qa|sts|d)[ \t].*$)|(?s:\\(?:f|fe|x|ef|ex)\s.*?\\(?:f|fe|x|ef|ex)\*)|\\[a-z0-9]+-[se]\b[^\\]*\\\*|\\\+?[a-z0-9-]+\*?";
+
+static ATTRIBUTE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"([a-z0-9-]+)\s*=\s*"([^"]*)""#).unwrap());
+
+static ALIGNMENT_TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"\\zaln-s\s*(?:\|(?P<zaln>[^\\]*))?\\\*|(?P<zalne>\\zaln-e\\\*)|\\w\s+(?P<w>[^|\\]*?)\s*(?:\|[^\\]*)?\\w\*|(?P<protected>{})|(?P<word>{})",
+        PROTECTED_PATTERN, USFM_WORD_PATTERN
+    ))
+    .unwrap()
+});
+
+static SEGMENT_TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"(?P<protected>{})|(?P<word>{})", PROTECTED_PATTERN, USFM_WORD_PATTERN)).unwrap()
+});
+
+static WORD_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(USFM_WORD_PATTERN).unwrap());
+
+static ZALN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\zaln-[se]\s*(?:\|[^\\]*)?\\\*").unwrap());
+
+static W_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\w\s+([^|\\]*?)\s*(?:\|[^\\]*)?\\w\*").unwrap());
+
+#[derive(Serialize)]
+pub(crate) struct AlignmentSuggestion {
+    sources: Vec<AlignmentSource>,
+    targets: Vec<AlignmentTarget>,
+    count: usize,
+}
+
+fn milestone_attributes(attributes: &str) -> BTreeMap<String, String> {
+    ATTRIBUTE_RE
+        .captures_iter(attributes)
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect()
+}
+
+fn source_from_attributes(attributes: &str) -> AlignmentSource {
+    let attributes = milestone_attributes(attributes);
+    AlignmentSource {
+        content: attributes.get("x-content").cloned().unwrap_or_default(),
+        strong: attributes.get("x-strong").cloned(),
+        lemma: attributes.get("x-lemma").cloned(),
+        morph: attributes.get("x-morph").cloned(),
+        occurrence: attributes.get("x-occurrence").and_then(|o| o.parse().ok()).unwrap_or(1),
+        occurrences: attributes.get("x-occurrences").and_then(|o| o.parse().ok()).unwrap_or(1),
+    }
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace("\"", "&quot;")
+}
+
+fn zaln_start(source: &AlignmentSource) -> String {
+    let mut attributes = vec![];
+    if let Some(strong) = &source.strong {
+        attributes.push(format!("x-strong=\"{}\"", escape_attribute(strong)));
+    }
+    if let Some(lemma) = &source.lemma {
+        attributes.push(format!("x-lemma=\"{}\"", escape_attribute(lemma)));
+    }
+    if let Some(morph) = &source.morph {
+        attributes.push(format!("x-morph=\"{}\"", escape_attribute(morph)));
+    }
+    attributes.push(format!("x-occurrence=\"{}\"", source.occurrence));
+    attributes.push(format!("x-occurrences=\"{}\"", source.occurrences));
+    attributes.push(format!("x-content=\"{}\"", escape_attribute(&source.content)));
+    format!("\\zaln-s |{}\\*", attributes.join(" "))
+}
+
+// Numbers each word by occurrence within the verse, as in x-occurrence and x-occurrences
+fn number_words(words: &[String]) -> Vec<AlignmentTarget> {
+    let mut totals: BTreeMap<&String, u16> = BTreeMap::new();
+    for word in words {
+        *totals.entry(word).or_insert(0) += 1;
+    }
+    let mut seen: BTreeMap<&String, u16> = BTreeMap::new();
+    words
+        .iter()
+        .map(|word| {
+            let occurrence = seen.entry(word).or_insert(0);
+            *occurrence += 1;
+            AlignmentTarget {
+                word: word.clone(),
+                occurrence: *occurrence,
+                occurrences: totals[word],
+            }
+        })
+        .collect()
+}
+
+/// Reads the target words of a verse and their alignments from `\zaln-s` milestones. Words aligned to the same source words,
+/// whether or not they are contiguous, make one alignment.
+pub(crate) fn verse_alignment(verse_usfm: &str) -> VerseAlignment {
+    let mut source_stack: Vec<AlignmentSource> = vec![];
+    let mut words: Vec<String> = vec![];
+    let mut word_sources: Vec<Vec<AlignmentSource>> = vec![];
+    for captures in ALIGNMENT_TOKEN_RE.captures_iter(verse_usfm) {
+        if let Some(attributes) = captures.name("zaln") {
+            source_stack.push(source_from_attributes(attributes.as_str()));
+        } else if captures.name("zalne").is_some() {
+            source_stack.pop();
+        } else if let Some(w) = captures.name("w") {
+            for word in WORD_RE.find_iter(w.as_str()) {
+                words.push(word.as_str().to_string());
+                word_sources.push(source_stack.clone());
+            }
+        } else if let Some(word) = captures.name("word") {
+            words.push(word.as_str().to_string());
+            word_sources.push(source_stack.clone());
+        }
+    }
+    let numbered_words = number_words(&words);
+    let mut alignments: Vec<Alignment> = vec![];
+    for (target, sources) in numbered_words.iter().zip(word_sources) {
+        if sources.is_empty() {
+            continue;
+        }
+        match alignments.iter_mut().find(|a| a.sources == sources) {
+            Some(alignment) => alignment.targets.push(target.clone()),
+            None => alignments.push(Alignment {
+                sources,
+                targets: vec![target.clone()],
+            }),
+        }
+    }
+    VerseAlignment {
+        words: numbered_words,
+        alignments,
+    }
+}
+
+/// Removes `\zaln` milestones and `\w` markup from verse USFM, leaving the target text.
+pub(crate) fn strip_alignment(verse_usfm: &str) -> String {
+    let stripped = ZALN_RE.replace_all(verse_usfm, "");
+    W_RE.replace_all(&stripped, "$1").to_string()
+}
+
+enum VerseSegment {
+    Text(String),
+    Word(String),
+}
+
+fn verse_segments(stripped_usfm: &str) -> Vec<VerseSegment> {
+    let mut segments = vec![];
+    let mut position = 0;
+    for captures in SEGMENT_TOKEN_RE.captures_iter(stripped_usfm) {
+        let whole = captures.get(0).unwrap();
+        if whole.start() > position {
+            segments.push(VerseSegment::Text(stripped_usfm[position..whole.start()].to_string()));
+        }
+        match captures.name("word") {
+            Some(w) => segments.push(VerseSegment::Word(w.as_str().to_string())),
+            None => segments.push(VerseSegment::Text(whole.as_str().to_string())),
+        }
+        position = whole.end();
+    }
+    if position < stripped_usfm.len() {
+        segments.push(VerseSegment::Text(stripped_usfm[position..].to_string()));
+    }
+    segments
+}
+
+/// Rewrites verse USFM with alignments as `\zaln-s` milestones around `\w` words. Contiguous words of one alignment share milestones.
+/// Without alignments, the verse is returned as plain text.
+pub(crate) fn aligned_verse_usfm(verse_usfm: &str, alignments: &[Alignment]) -> String {
+    let stripped = strip_alignment(verse_usfm);
+    if alignments.is_empty() {
+        return stripped;
+    }
+    let segments = verse_segments(&stripped);
+    let words: Vec<String> = segments
+        .iter()
+        .filter_map(|s| match s {
+            VerseSegment::Word(w) => Some(w.clone()),
+            _ => None,
+        })
+        .collect();
+    let numbered_words = number_words(&words);
+    let word_groups: Vec<Option<usize>> = numbered_words
+        .iter()
+        .map(|target| {
+            alignments.iter().position(|a| {
+                a.targets
+                    .iter()
+                    .any(|t| t.word == target.word && t.occurrence == target.occurrence)
+            })
+        })
+        .collect();
+    let close_group = |group: usize| "\\zaln-e\\*".repeat(alignments[group].sources.len());
+    let mut output = String::new();
+    let mut open_group: Option<usize> = None;
+    let mut word_n = 0;
+    for segment in segments.iter() {
+        match segment {
+            VerseSegment::Text(text) => {
+                if let Some(group) = open_group {
+                    let next_group = word_groups.get(word_n).copied().flatten();
+                    if !text.trim().is_empty() || next_group != Some(group) {
+                        output.push_str(&close_group(group));
+                        open_group = None;
+                    }
+                }
+                output.push_str(text);
+            }
+            VerseSegment::Word(_) => {
+                let target = &numbered_words[word_n];
+                let group = word_groups[word_n];
+                if open_group.is_some() && open_group != group {
+                    output.push_str(&close_group(open_group.unwrap()));
+                    open_group = None;
+                }
+                if let (Some(g), None) = (group, open_group) {
+                    for source in &alignments[g].sources {
+                        output.push_str(&zaln_start(source));
+                    }
+                    open_group = Some(g);
+                }
+                output.push_str(&format!(
+                    "\\w {}|x-occurrence=\"{}\" x-occurrences=\"{}\"\\w*",
+                    target.word, target.occurrence, target.occurrences
+                ));
+                word_n += 1;
+            }
+        }
+    }
+    if let Some(group) = open_group {
+        output.push_str(&close_group(group));
+    }
+    output
+}
+
+/// True when verse USFM already holds `\zaln` milestones or `\w` words.
+pub(crate) fn has_alignment_markup(verse_usfm: &str) -> bool {
+    verse_usfm.contains("\\zaln-") || verse_usfm.contains("\\w ")
+}
+
+/// Applies the alignments of the old content of a verse to its new, unaligned content. Target words that are still in the verse,
+/// with the same word and occurrence, keep their alignments, and alignments left without target words are dropped.
+pub(crate) fn realigned_verse_usfm(old_verse_usfm: &str, new_verse_usfm: &str) -> String {
+    let old_alignment = verse_alignment(old_verse_usfm);
+    if old_alignment.alignments.is_empty() {
+        return new_verse_usfm.to_string();
+    }
+    let new_words = verse_alignment(new_verse_usfm).words;
+    let alignments: Vec<Alignment> = old_alignment
+        .alignments
+        .into_iter()
+        .map(|a| Alignment {
+            sources: a.sources,
+            targets: a
+                .targets
+                .into_iter()
+                .filter(|t| new_words.iter().any(|w| same_target(w, t)))
+                .collect(),
+        })
+        .filter(|a| !a.targets.is_empty())
+        .collect();
+    aligned_verse_usfm(new_verse_usfm, &alignments)
+}
+
+fn same_target(a: &AlignmentTarget, b: &AlignmentTarget) -> bool {
+    a.word == b.word && a.occurrence.max(1) == b.occurrence.max(1)
+}
+
+fn same_source(a: &AlignmentSource, b: &AlignmentSource) -> bool {
+    a.content == b.content && a.occurrence.max(1) == b.occurrence.max(1)
+}
+
+/// Aligns target words to source words. The target words are first removed from other alignments,
+/// then added to the alignment with exactly these source words, which is made if needed.
+pub(crate) fn align_words(
+    current: &VerseAlignment,
+    sources: &[AlignmentSource],
+    targets: &[AlignmentTarget],
+) -> Result<Vec<Alignment>, PankosmiaError> {
+    if sources.is_empty() || targets.is_empty() {
+        return Err(PankosmiaError("Alignment needs at least one source and one target word".to_string()));
+    }
+    let mut verse_targets = vec![];
+    for target in targets {
+        match current.words.iter().find(|w| same_target(w, target)) {
+            Some(w) => verse_targets.push(w.clone()),
+            None => {
+                return Err(PankosmiaError(format!(
+                    "Word '{}' (occurrence {}) not found in verse",
+                    target.word, target.occurrence
+                )))
+            }
+        }
+    }
+    let mut alignments = unalign_words(current, &None, &Some(verse_targets.clone()));
+    match alignments.iter_mut().find(|a| {
+        a.sources.len() == sources.len() && a.sources.iter().zip(sources).all(|(a, b)| same_source(a, b))
+    }) {
+        Some(alignment) => alignment.targets.extend(verse_targets),
+        None => alignments.push(Alignment {
+            sources: sources
+                .iter()
+                .map(|s| AlignmentSource {
+                    occurrence: s.occurrence.max(1),
+                    occurrences: s.occurrences.max(s.occurrence).max(1),
+                    ..s.clone()
+                })
+                .collect(),
+            targets: verse_targets,
+        }),
+    }
+    Ok(alignments)
+}
+
+/// Removes target words from their alignments, and removes the alignments of source words. Alignments left without targets are dropped.
+pub(crate) fn unalign_words(
+    current: &VerseAlignment,
+    sources: &Option<Vec<AlignmentSource>>,
+    targets: &Option<Vec<AlignmentTarget>>,
+) -> Vec<Alignment> {
+    current
+        .alignments
+        .iter()
+        .filter(|a| match sources {
+            Some(s) => !a.sources.iter().any(|a_source| s.iter().any(|source| same_source(a_source, source))),
+            None => true,
+        })
+        .map(|a| Alignment {
+            sources: a.sources.clone(),
+            targets: a
+                .targets
+                .iter()
+                .filter(|a_target| match targets {
+                    Some(t) => !t.iter().any(|target| same_target(a_target, target)),
+                    None => true,
+                })
+                .cloned()
+                .collect(),
+        })
+        .filter(|a| !a.targets.is_empty())
+        .collect()
+}
+
+/// Reads the USFM ingredient for a book, returning its path relative to ingredients and its content.
+pub(crate) fn read_book_usfm(
+    app_resources_dir: &str,
+    repo_path: &str,
+    book_code: &str,
+) -> Result<(String, String), PankosmiaError> {
+    let ipath = match usfm_ingredient_for_book(app_resources_dir, repo_path, book_code) {
+        Some(p) => p,
+        None => return Err(PankosmiaError(format!("No USFM for book {}", book_code))),
+    };
+    match std::fs::read_to_string(format!("{}{}ingredients{}{}", repo_path, os_slash_str(), os_slash_str(), ipath)) {
+        Ok(s) => Ok((ipath, s)),
+        Err(e) => Err(PankosmiaError(format!("Could not read {}: {}", ipath, e))),
+    }
+}
+
+/// Finds the verse, or the verse bridge, containing a verse.
+pub(crate) fn find_verse(usfm: &str, chapter: u16, verse: u16) -> Result<UsfmVerse, PankosmiaError> {
+    usfm_verses(usfm)
+        .into_iter()
+        .find(|v| v.chapter == chapter && v.verse <= verse && v.to_verse.max(v.verse) >= verse)
+        .ok_or(PankosmiaError(format!("Verse {}:{} not found", chapter, verse)))
+}
+
+/// Rewrites the alignments of one verse in the USFM ingredient for a book, then the ingredients metadata.
+pub(crate) fn write_verse_alignments(
+    app_resources_dir: &str,
+    repo_path: &str,
+    book_code: &str,
+    chapter: u16,
+    verse: u16,
+    alignments: &[Alignment],
+) -> Result<(), PankosmiaError> {
+    let (ipath, usfm) = read_book_usfm(app_resources_dir, repo_path, book_code)?;
+    let usfm_verse = find_verse(&usfm, chapter, verse)?;
+    let new_usfm = format!(
+        "{}{}{}",
+        &usfm[..usfm_verse.start],
+        aligned_verse_usfm(&usfm_verse.usfm, alignments),
+        &usfm[usfm_verse.end..]
+    );
+    let usfm_path = format!("{}{}ingredients{}{}", repo_path, os_slash_str(), os_slash_str(), ipath);
+    if let Err(e) = write_file_atomically(&usfm_path, &new_usfm) {
+        return Err(PankosmiaError(format!("Could not write {}: {}", ipath, e)));
+    }
+    rewrite_ingredients_metadata(app_resources_dir.to_string(), repo_path.to_string())
+}
+
+/// An alignment found in a USFM ingredient, with its reference.
+pub(crate) struct ReferencedAlignment {
+    pub(crate) book_code: String,
+    pub(crate) chapter: u16,
+    pub(crate) verse: u16,
+    pub(crate) alignment: Alignment,
+}
+
+/// Reads every alignment in the USFM ingredients of a repo.
+pub(crate) fn repo_alignments(repo_path: &String) -> Vec<ReferencedAlignment> {
+    let mut alignments = vec![];
+    let path_to_ingredients = format!("{}{}ingredients", repo_path, os_slash_str());
+    for entry in WalkDir::new(&path_to_ingredients).into_iter().filter_map(|e| e.ok()) {
+        if !entry.path().is_file() || entry.path().extension().and_then(|e| e.to_str()) != Some("usfm") {
+            continue;
+        }
+        let usfm = match std::fs::read_to_string(entry.path()) {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        if !usfm.contains("\\zaln-s") {
+            continue;
+        }
+        let book_code = usfm_book_headers(&usfm).book_code.unwrap_or_default();
+        for usfm_verse in usfm_verses(&usfm) {
+            for alignment in verse_alignment(&usfm_verse.usfm).alignments {
+                alignments.push(ReferencedAlignment {
+                    book_code: book_code.clone(),
+                    chapter: usfm_verse.chapter,
+                    verse: usfm_verse.verse,
+                    alignment,
+                });
+            }
+        }
+    }
+    alignments
+}
+
+fn source_key(source: &AlignmentSource) -> String {
+    source
+        .lemma
+        .clone()
+        .or(source.strong.clone())
+        .unwrap_or(source.content.clone())
+}
+
+/// Suggests alignments for source words of a verse from the single-source alignments already made in the USFM ingredients of the repo,
+/// keyed by lemma, then by Strong's number, then by source word. Only unaligned target words are suggested, most frequent decisions first.
+pub(crate) fn alignment_suggestions(
+    repo_path: &String,
+    current: &VerseAlignment,
+    sources: &[AlignmentSource],
+) -> Vec<AlignmentSuggestion> {
+    let mut decisions: BTreeMap<String, BTreeMap<Vec<String>, usize>> = BTreeMap::new();
+    for verse_alignment in repo_alignments(repo_path) {
+        if verse_alignment.alignment.sources.len() != 1 {
+            continue;
+        }
+        let target_words = verse_alignment
+            .alignment
+            .targets
+            .iter()
+            .map(|t| t.word.to_lowercase())
+            .collect();
+        *decisions
+            .entry(source_key(&verse_alignment.alignment.sources[0]))
+            .or_default()
+            .entry(target_words)
+            .or_insert(0) += 1;
+    }
+    let mut available: Vec<AlignmentTarget> = current
+        .words
+        .iter()
+        .filter(|w| !current.alignments.iter().any(|a| a.targets.iter().any(|t| same_target(t, w))))
+        .cloned()
+        .collect();
+    let mut suggestions = vec![];
+    for source in sources {
+        if current.alignments.iter().any(|a| a.sources.iter().any(|s| same_source(s, source))) {
+            continue;
+        }
+        let mut candidates: Vec<(&Vec<String>, &usize)> = match decisions.get(&source_key(source)) {
+            Some(c) => c.iter().collect(),
+            None => continue,
+        };
+        candidates.sort_by(|a, b| b.1.cmp(a.1));
+        for (target_words, count) in candidates {
+            let mut targets = vec![];
+            for target_word in target_words {
+                match available
+                    .iter()
+                    .position(|w| w.word.to_lowercase() == *target_word && !targets.contains(w))
+                {
+                    Some(n) => targets.push(available[n].clone()),
+                    None => break,
+                }
+            }
+            if targets.len() == target_words.len() {
+                available.retain(|w| !targets.contains(w));
+                suggestions.push(AlignmentSuggestion {
+                    sources: vec![source.clone()],
+                    targets,
+                    count: *count,
+                });
+                break;
+            }
+        }
+    }
+    suggestions
+}
+
+#[derive(Serialize)]
+pub(crate) struct QuoteResolution {
+    quote: String,
+    occurrence: i32,
+    sources: Vec<AlignmentSource>,
+    targets: Vec<AlignmentTarget>,
+    text: String,
+    missing: Vec<String>,
+}
+
+fn quote_key(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+/// Resolves an original-language quote, as found in the Quote and Occurrence columns of translation notes, to the target words aligned to it
+/// in a verse. Each word of the quote (parts may be separated by `&`) matches the aligned source word with the same content and occurrence,
+/// or its only occurrence in the verse. An occurrence of -1 matches every occurrence. Target words are returned in verse order.
+pub(crate) fn resolve_quote(current: &VerseAlignment, quote: &str, occurrence: i32) -> QuoteResolution {
+    let mut sources: Vec<AlignmentSource> = vec![];
+    let mut missing = vec![];
+    for quote_word in WORD_RE.find_iter(quote).map(|w| w.as_str()) {
+        let key = quote_key(quote_word);
+        let candidates: Vec<&AlignmentSource> = current
+            .alignments
+            .iter()
+            .flat_map(|a| a.sources.iter())
+            .filter(|s| quote_key(&s.content) == key)
+            .collect();
+        let matched: Vec<&AlignmentSource> = candidates
+            .iter()
+            .filter(|s| occurrence < 0 || s.occurrence as i32 == occurrence.max(1) || s.occurrences == 1)
+            .copied()
+            .collect();
+        if matched.is_empty() {
+            missing.push(quote_word.to_string());
+        }
+        for source in matched {
+            if !sources.contains(source) {
+                sources.push(source.clone());
+            }
+        }
+    }
+    let targets: Vec<AlignmentTarget> = current
+        .words
+        .iter()
+        .filter(|w| {
+            current.alignments.iter().any(|a| {
+                a.sources.iter().any(|s| sources.contains(s)) && a.targets.iter().any(|t| same_target(t, w))
+            })
+        })
+        .cloned()
+        .collect();
+    QuoteResolution {
+        quote: quote.to_string(),
+        occurrence,
+        sources,
+        text: targets.iter().map(|t| t.word.clone()).collect::<Vec<String>>().join(" "),
+        targets,
+        missing,
+    }
+}
@@ -26,4 +26,32 @@ pub fn canonical_book_codes(app_resources_dir: String) -> Vec<String> {
         Err(_) => panic!("Read VRS")
     }
     books
-}
\ No newline at end of file
+}
+
+/// Returns the canonical book code for a book parameter, trimmed and upper-cased, or None if it is not one.
+/// Canonical book codes are safe to use in ingredient paths.
+pub(crate) fn canonical_book_code(app_resources_dir: &str, book: &str) -> Option<String> {
+    let book_code = book.trim().to_uppercase();
+    canonical_book_codes(app_resources_dir.to_string())
+        .contains(&book_code)
+        .then_some(book_code)
+}
+
+/// Book codes in the usual Paratext order: Old Testament, New Testament, then deuterocanonical and peripheral books.
+pub const BOOK_ORDER: [&str; 94] = [
+    "GEN", "EXO", "LEV", "NUM", "DEU", "JOS", "JDG", "RUT", "1SA", "2SA", "1KI", "2KI", "1CH", "2CH",
+    "EZR", "NEH", "EST", "JOB", "PSA", "PRO", "ECC", "SNG", "ISA", "JER", "LAM", "EZK", "DAN", "HOS",
+    "JOL", "AMO", "OBA", "JON", "MIC", "NAM", "HAB", "ZEP", "HAG", "ZEC", "MAL", "MAT", "MRK", "LUK",
+    "JHN", "ACT", "ROM", "1CO", "2CO", "GAL", "EPH", "PHP", "COL", "1TH", "2TH", "1TI", "2TI", "TIT",
+    "PHM", "HEB", "JAS", "1PE", "2PE", "1JN", "2JN", "3JN", "JUD", "REV", "TOB", "JDT", "ESG", "WIS",
+    "SIR", "BAR", "LJE", "S3Y", "SUS", "BEL", "1MA", "2MA", "3MA", "4MA", "1ES", "2ES", "MAN", "PS2",
+    "ODA", "PSS", "EZA", "5EZ", "6EZ", "DAG", "PS3", "2BA", "LBA", "JUB",
+];
+
+/// Returns the position of a book code in BOOK_ORDER, with unknown books sorted after known ones.
+pub fn book_order_index(book_code: &str) -> usize {
+    BOOK_ORDER
+        .iter()
+        .position(|b| *b == book_code)
+        .unwrap_or(BOOK_ORDER.len())
+}
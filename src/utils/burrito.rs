@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
-use crate::structs::{BurritoMetadataIngredient, MetadataSummary};
+use crate::structs::{BurritoMetadataIngredient, MetadataSummary, PankosmiaError};
+use crate::utils::burrito_api::checks::metadata_validation::{validate_metadata_json, MetadataValidationError};
 use crate::utils::bcv_ref::canonical_book_codes;
 use serde_json::{json, Map, Value};
 use std::fs;
@@ -8,12 +9,63 @@ use std::io;
 use std::path::Path;
 use walkdir::WalkDir;
 use crate::utils::paths::os_slash_str;
+use crate::utils::usfm::usfm_book_headers;
 use regex::Regex;
 use chksum_md5::chksum;
 use mime_infer;
 
+// Keeps the string values of a localized object such as identification.name
+fn localized_string_map(value: &Value) -> BTreeMap<String, String> {
+    let mut localized = BTreeMap::new();
+    if let Some(o) = value.as_object() {
+        for (language, text) in o {
+            if let Some(t) = text.as_str() {
+                localized.insert(language.clone(), t.to_string());
+            }
+        }
+    }
+    localized
+}
+
+/// Picks the value for the first preferred language, falling back to English and then to any language.
+pub(crate) fn negotiated_localized_string(
+    localized: &BTreeMap<String, String>,
+    languages: &[String],
+) -> Option<String> {
+    for language in languages.iter() {
+        if let Some(v) = localized.get(language) {
+            return Some(v.clone());
+        }
+    }
+    match localized.get("en") {
+        Some(v) => Some(v.clone()),
+        None => localized.values().next().cloned(),
+    }
+}
+
+pub(crate) fn bad_metadata_summary() -> MetadataSummary {
+    MetadataSummary {
+        name: "? Bad Metadata JSON ?".to_string(),
+        description: "?".to_string(),
+        abbreviation: "?".to_string(),
+        generated_date: "?".to_string(),
+        flavor_type: "?".to_string(),
+        flavor: "?".to_string(),
+        language_code: "?".to_string(),
+        language_name: "?".to_string(),
+        script_direction: "?".to_string(),
+        book_codes: vec![],
+        timestamp: 0,
+        names: BTreeMap::new(),
+        descriptions: BTreeMap::new(),
+        abbreviations: BTreeMap::new(),
+        language_names: BTreeMap::new(),
+    }
+}
+
 pub(crate) fn summary_metadata_from_file(
     repo_metadata_path: String,
+    languages: &[String],
 ) -> Result<MetadataSummary, io::Error> {
     let file_string = match fs::read_to_string(&repo_metadata_path) {
         Ok(v) => v,
@@ -25,6 +77,9 @@ pub(crate) fn summary_metadata_from_file(
             return Err(io::Error::from(e));
         }
     };
+    if !raw_metadata_struct.is_object() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Metadata is not a JSON object"));
+    }
     let current_scope_values =
         match raw_metadata_struct["type"]["flavorType"]["currentScope"].as_object() {
             Some(v) => v,
@@ -34,55 +89,63 @@ pub(crate) fn summary_metadata_from_file(
     for (map_key, _) in current_scope_values.clone().iter() {
         book_codes.push(format!("{}", map_key));
     }
-    Ok(MetadataSummary {
-        name: raw_metadata_struct["identification"]["name"]["en"]
-            .as_str()
-            .unwrap()
-            .to_string(),
-        description: match raw_metadata_struct["identification"]["description"]["en"].clone() {
-            Value::String(v) => v.as_str().to_string(),
-            Value::Null => "".to_string(),
-            _ => "?".to_string(),
-        },
-        abbreviation: match raw_metadata_struct["identification"]["abbreviation"]["en"].clone() {
-            Value::String(v) => v.as_str().to_string(),
-            Value::Null => "".to_string(),
-            _ => "?".to_string(),
+    let names = localized_string_map(&raw_metadata_struct["identification"]["name"]);
+    let descriptions = localized_string_map(&raw_metadata_struct["identification"]["description"]);
+    let abbreviations = localized_string_map(&raw_metadata_struct["identification"]["abbreviation"]);
+    let language_names = localized_string_map(&raw_metadata_struct["languages"][0]["name"]);
+    let timestamp = match fs::metadata(&repo_metadata_path).and_then(|m| m.modified()) {
+        Ok(t) => match t.duration_since(std::time::SystemTime::UNIX_EPOCH) {
+            Ok(d) => d.as_secs(),
+            Err(_) => 0,
         },
+        Err(_) => 0,
+    };
+    let mut summary = MetadataSummary {
+        name: "?".to_string(),
+        description: "".to_string(),
+        abbreviation: "".to_string(),
         generated_date: match raw_metadata_struct["meta"]["dateCreated"].clone() {
             Value::String(v) => v.as_str().to_string(),
             Value::Null => "".to_string(),
             _ => "?".to_string(),
         },
-        flavor_type: raw_metadata_struct["type"]["flavorType"]["name"]
-            .as_str()
-            .unwrap()
-            .to_string(),
-        flavor: raw_metadata_struct["type"]["flavorType"]["flavor"]["name"]
-            .as_str()
-            .unwrap()
-            .to_string(),
-        language_code: raw_metadata_struct["languages"][0]["tag"]
-            .as_str()
-            .unwrap()
-            .to_string(),
-        language_name: raw_metadata_struct["languages"][0]["name"]["en"]
-            .as_str()
-            .unwrap()
-            .to_string(),
+        flavor_type: match raw_metadata_struct["type"]["flavorType"]["name"].as_str() {
+            Some(v) => v.to_string(),
+            None => "?".to_string(),
+        },
+        flavor: match raw_metadata_struct["type"]["flavorType"]["flavor"]["name"].as_str() {
+            Some(v) => v.to_string(),
+            None => "?".to_string(),
+        },
+        language_code: match raw_metadata_struct["languages"][0]["tag"].as_str() {
+            Some(v) => v.to_string(),
+            None => "?".to_string(),
+        },
+        language_name: "?".to_string(),
         script_direction: match raw_metadata_struct["languages"][0]["scriptDirection"].clone() {
             Value::String(v) => v.as_str().to_string(),
             _ => "?".to_string(),
         },
-        book_codes: book_codes,
-        timestamp: fs::metadata(&repo_metadata_path)
-            .expect("Could not read fs metadata")
-            .modified()
-            .expect("Could not get modified for fs")
-            .duration_since(std::time::SystemTime::UNIX_EPOCH)
-            .expect("Could not get elapsed")
-            .as_secs(),
-    })
+        book_codes,
+        timestamp,
+        names,
+        descriptions,
+        abbreviations,
+        language_names,
+    };
+    negotiate_metadata_summary(&mut summary, languages);
+    Ok(summary)
+}
+
+/// Sets the flat localized fields of a summary from its localized maps, for the given language preferences.
+pub(crate) fn negotiate_metadata_summary(summary: &mut MetadataSummary, languages: &[String]) {
+    summary.name = negotiated_localized_string(&summary.names, languages).unwrap_or("?".to_string());
+    summary.description =
+        negotiated_localized_string(&summary.descriptions, languages).unwrap_or("".to_string());
+    summary.abbreviation =
+        negotiated_localized_string(&summary.abbreviations, languages).unwrap_or("".to_string());
+    summary.language_name =
+        negotiated_localized_string(&summary.language_names, languages).unwrap_or("?".to_string());
 }
 
 pub(crate) fn destination_parent(destination: String) -> String {
@@ -131,14 +194,18 @@ pub fn ingredients_metadata_from_files(
                     let chk_file = File::open(&entry_string).unwrap();
                     let ingredient_md5 = chksum(chk_file).unwrap().to_string();
                     // mimeType
-                    let ingredient_mime_type = match mime_infer::from_path(&entry_string).first() {
-                        Some(mime_type) => mime_type.to_string(),
-                        None => {
-                            if file_name_parts.len() == 2 && (file_name_parts[1] == "usfm" || file_name_parts[1] == "vrs") {
-                                "text/plain".to_string()
-                            } else {
-                                "application/octet-stream".to_string()
-                            }
+                    let ingredient_mime_type = match (file_name_parts.len(), file_name_parts.last()) {
+                        (2, Some(&"usx")) => "text/xml".to_string(),
+                        (2, Some(&"usj")) => "application/json".to_string(),
+                        _ => match mime_infer::from_path(&entry_string).first() {
+                            Some(mime_type) => mime_type.to_string(),
+                            None => {
+                                if file_name_parts.len() == 2 && (file_name_parts[1] == "usfm" || file_name_parts[1] == "vrs") {
+                                    "text/plain".to_string()
+                                } else {
+                                    "application/octet-stream".to_string()
+                                }
+                            },
                         },
                     };
                     let ingredient_details = BurritoMetadataIngredient {
@@ -178,4 +245,179 @@ pub fn ingredients_scopes_from_files(
         }
     }
     scopes
-}
\ No newline at end of file
+}
+/// Maps a flavor name onto the content template directory used to create burritos of that flavor.
+pub(crate) fn flavor_template_name(flavor: &str) -> String {
+    match flavor {
+        "textTranslation" => "text_translation".to_string(),
+        "audioTranslation" => "audio_translation".to_string(),
+        "textStories" => "text_stories".to_string(),
+        _ => flavor.to_string(),
+    }
+}
+
+/// Rewrites ingredients and currentScope in metadata held as JSON, from the files under the repo path.
+pub(crate) fn refresh_ingredients_in_metadata_value(
+    app_resources_dir: String,
+    repo_path: String,
+    metadata: &mut Value,
+) {
+    let new_ingredients = ingredients_metadata_from_files(app_resources_dir.clone(), repo_path.clone());
+    metadata["ingredients"] = serde_json::to_value(&new_ingredients).unwrap();
+    if metadata["type"]["flavorType"].is_object() {
+        let new_current_scope = ingredients_scopes_from_files(app_resources_dir, repo_path);
+        metadata["type"]["flavorType"]["currentScope"] = serde_json::to_value(&new_current_scope).unwrap();
+    }
+}
+
+/// Applies an edit to the metadata of the repo at the given path, then writes it if it is still valid against the schema in the app resources.
+pub(crate) fn edit_metadata<F>(app_resources_dir: &str, repo_path: &str, edit: F) -> Result<Value, PankosmiaError>
+where
+    F: FnOnce(&mut Value) -> Result<(), PankosmiaError>,
+{
+    let path_to_repo_metadata = format!("{}{}metadata.json", repo_path, os_slash_str());
+    let metadata_string = match fs::read_to_string(&path_to_repo_metadata) {
+        Ok(v) => v,
+        Err(e) => return Err(PankosmiaError(format!("Could not load metadata as string: {}", e))),
+    };
+    let mut metadata: Value = match serde_json::from_str(&metadata_string) {
+        Ok(v) => v,
+        Err(e) => return Err(PankosmiaError(format!("Could not parse metadata: {}", e))),
+    };
+    if !metadata.is_object() {
+        return Err(PankosmiaError("Metadata is not a JSON object".to_string()));
+    }
+    edit(&mut metadata)?;
+    match validate_metadata_json(app_resources_dir, &metadata) {
+        Ok(_) => (),
+        Err(MetadataValidationError::Schema(e)) => return Err(e),
+        Err(MetadataValidationError::Invalid(e)) => {
+            return Err(PankosmiaError(format!("Edited metadata is not schema valid: {}", e.0)))
+        }
+    }
+    let metadata_output_string = match serde_json::to_string(&metadata) {
+        Ok(s) => s,
+        Err(e) => return Err(PankosmiaError(format!("Could not make metadata as JSON: {}", e))),
+    };
+    match fs::write(&path_to_repo_metadata, &metadata_output_string) {
+        Ok(_) => Ok(metadata),
+        Err(e) => Err(PankosmiaError(format!("Could not write metadata to repo: {}", e))),
+    }
+}
+
+/// Returns the tag of the first language in the metadata, which is treated as the main language.
+pub(crate) fn metadata_language_tag(metadata: &Value) -> String {
+    match metadata["languages"][0]["tag"].as_str() {
+        Some(t) => t.to_string(),
+        None => "en".to_string(),
+    }
+}
+
+/// Rebuilds localizedNames for one language from the headers of the USFM ingredients. Entries for other languages are kept.
+pub(crate) fn localized_names_from_usfm(repo_path: String, language: String, existing: &Value) -> Value {
+    let mut localized_names = match existing {
+        Value::Object(o) => o.clone(),
+        _ => Map::new(),
+    };
+    let ingredients_path = format!("{}{}ingredients", repo_path, os_slash_str());
+    for entry in WalkDir::new(&ingredients_path).into_iter().filter_map(|e| e.ok()) {
+        let entry_path = entry.path();
+        if !entry_path.is_file() || entry.file_name().to_string_lossy().starts_with(".") {
+            continue;
+        }
+        let extension = match entry_path.extension() {
+            Some(e) => e.to_string_lossy().to_lowercase(),
+            None => continue,
+        };
+        if extension != "usfm" && extension != "sfm" {
+            continue;
+        }
+        let usfm_string = match fs::read_to_string(entry_path) {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        let headers = usfm_book_headers(&usfm_string);
+        let book_code = match headers.book_code.clone() {
+            Some(c) => c,
+            None => match entry_path.file_stem() {
+                Some(s) => s.to_string_lossy().to_uppercase(),
+                None => continue,
+            },
+        };
+        let book_entry = localized_names
+            .entry(format!("book-{}", book_code.to_lowercase()))
+            .or_insert(json!({}));
+        if !book_entry.is_object() {
+            *book_entry = json!({});
+        }
+        let derived_names = [
+            ("short", headers.toc2.clone().or(headers.h.clone())),
+            ("abbr", headers.toc3.clone()),
+            ("long", headers.toc1.clone().or(headers.h.clone())),
+        ];
+        for (name_kind, name_value) in derived_names {
+            if let Some(v) = name_value {
+                if !book_entry[name_kind].is_object() {
+                    book_entry[name_kind] = json!({});
+                }
+                book_entry[name_kind][language.as_str()] = Value::String(v);
+            }
+        }
+    }
+    Value::Object(localized_names)
+}
+
+/// Splits a comma-separated language preference such as `fr,en`.
+pub(crate) fn preferred_languages(lang: String) -> Vec<String> {
+    lang.split(",")
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Finds the USFM ingredient for a book, as a path relative to ingredients, from the conventional file name or from ingredient scopes in the metadata.
+/// Returns None for anything but a canonical book code, which is never used in a path.
+pub(crate) fn usfm_ingredient_for_book(app_resources_dir: &str, repo_path: &str, book_code: &str) -> Option<String> {
+    if !canonical_book_codes(app_resources_dir.to_string()).iter().any(|b| b == book_code) {
+        return None;
+    }
+    for extension in ["usfm", "sfm", "SFM"] {
+        let ipath = format!("{}.{}", book_code, extension);
+        if Path::new(&format!("{}{}ingredients{}{}", repo_path, os_slash_str(), os_slash_str(), ipath)).is_file() {
+            return Some(ipath);
+        }
+    }
+    let metadata: Value = match fs::read_to_string(format!("{}{}metadata.json", repo_path, os_slash_str())) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or(Value::Null),
+        Err(_) => return None,
+    };
+    if let Some(ingredients) = metadata["ingredients"].as_object() {
+        for (ingredient_path, ingredient) in ingredients {
+            let lower_path = ingredient_path.to_lowercase();
+            if !(lower_path.ends_with(".usfm") || lower_path.ends_with(".sfm")) {
+                continue;
+            }
+            if ingredient["scope"].get(book_code).is_some() {
+                return ingredient_path.strip_prefix("ingredients/").map(|p| p.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Rewrites ingredients and currentScope in the metadata file of the repo at the given path.
+pub(crate) fn rewrite_ingredients_metadata(app_resources_dir: String, repo_path: String) -> Result<(), PankosmiaError> {
+    let path_to_repo_metadata = format!("{}{}metadata.json", repo_path, os_slash_str());
+    let mut metadata: Value = match fs::read_to_string(&path_to_repo_metadata) {
+        Ok(s) => match serde_json::from_str(&s) {
+            Ok(v) => v,
+            Err(e) => return Err(PankosmiaError(format!("Could not parse metadata: {}", e))),
+        },
+        Err(e) => return Err(PankosmiaError(format!("Could not load metadata as string: {}", e))),
+    };
+    refresh_ingredients_in_metadata_value(app_resources_dir, repo_path, &mut metadata);
+    match fs::write(&path_to_repo_metadata, serde_json::to_string(&metadata).unwrap()) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(PankosmiaError(format!("Could not write metadata to repo: {}", e))),
+    }
+}
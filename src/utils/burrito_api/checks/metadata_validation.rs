@@ -1,34 +1,127 @@
-use boon::{Compiler, Schemas};
+use boon::{Compiler, ErrorKind, SchemaIndex, Schemas, ValidationError};
+use serde_json::Value;
+use std::sync::Mutex;
+use crate::structs::PankosmiaError;
 use crate::utils::burrito_api::checks::report_helpers::{ok_check_report, CheckReport};
+use crate::utils::paths::os_slash_str;
+
+// The compiled metadata schema, with the app resources dir it was loaded from
+static METADATA_SCHEMA: Mutex<Option<(String, Schemas, SchemaIndex)>> = Mutex::new(None);
+
+fn metadata_schema_path(app_resources_dir: &str) -> String {
+    format!(
+        "{}{}schema{}scripture_burrito_metadata_schema{}source_metadata.schema.json",
+        app_resources_dir,
+        os_slash_str(),
+        os_slash_str(),
+        os_slash_str(),
+    )
+}
+
+/// Why metadata could not be validated against the schema.
+pub(crate) enum MetadataValidationError {
+    /// The schema could not be loaded or compiled, which is an internal error.
+    Schema(PankosmiaError),
+    /// The metadata is not valid against the schema. The detailed validation output is the error.
+    Invalid(PankosmiaError),
+}
+
+// Calls f with the Scripture Burrito schema in the app resources, which is compiled once
+fn with_metadata_schema<T, F>(app_resources_dir: &str, f: F) -> Result<T, PankosmiaError>
+where
+    F: FnOnce(&Schemas, SchemaIndex) -> T,
+{
+    let mut cached_schema = METADATA_SCHEMA.lock().unwrap();
+    if !matches!(cached_schema.as_ref(), Some((dir, _, _)) if dir == app_resources_dir) {
+        let schema_path = match std::path::absolute(metadata_schema_path(app_resources_dir)) {
+            Ok(p) => p.display().to_string(),
+            Err(e) => return Err(PankosmiaError(format!("Could not find metadata schema: {}", e))),
+        };
+        let mut schemas = Schemas::new();
+        let mut compiler = Compiler::new();
+        let sch_index = match compiler.compile(schema_path.as_str(), &mut schemas) {
+            Ok(i) => i,
+            Err(e) => return Err(PankosmiaError(format!("Could not compile metadata schema {}: {}", schema_path, e))),
+        };
+        *cached_schema = Some((app_resources_dir.to_string(), schemas, sch_index));
+    }
+    let (_, schemas, sch_index) = cached_schema.as_ref().unwrap();
+    Ok(f(schemas, *sch_index))
+}
+
+/// Validates metadata against the Scripture Burrito schema in the app resources.
+pub(crate) fn validate_metadata_json(app_resources_dir: &str, metadata_json: &Value) -> Result<(), MetadataValidationError> {
+    let validation = with_metadata_schema(app_resources_dir, |schemas, sch_index| {
+        schemas
+            .validate(metadata_json, sch_index)
+            .map_err(|errors| format!("{}", errors.detailed_output()))
+    });
+    match validation {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(errors)) => Err(MetadataValidationError::Invalid(PankosmiaError(errors))),
+        Err(e) => Err(MetadataValidationError::Schema(e)),
+    }
+}
+
+// Collects the JSON pointers of objects missing required properties, with the names of those properties
+fn collect_required(error: &ValidationError, missing: &mut Vec<(String, Vec<String>)>) {
+    if let ErrorKind::Required { want } = &error.kind {
+        missing.push((
+            error.instance_location.to_string(),
+            want.iter().map(|w| w.to_string()).collect(),
+        ));
+    }
+    for cause in error.causes.iter() {
+        collect_required(cause, missing);
+    }
+}
+
+/// Returns the required properties that the schema in the app resources reports as missing from the metadata,
+/// as the JSON pointer of each object with the names of the properties missing from it.
+pub(crate) fn missing_required_fields(
+    app_resources_dir: &str,
+    metadata_json: &Value,
+) -> Result<Vec<(String, Vec<String>)>, PankosmiaError> {
+    with_metadata_schema(app_resources_dir, |schemas, sch_index| {
+        let mut missing = vec![];
+        if let Err(errors) = schemas.validate(metadata_json, sch_index) {
+            collect_required(&errors, &mut missing);
+        }
+        missing
+    })
+}
 
 // Run basic_shape checks first
-pub(crate) fn check_metadata_validation(burrito_path: String) -> Vec<CheckReport> {
+pub(crate) fn check_metadata_validation(app_resources_dir: &str, burrito_path: String) -> Vec<CheckReport> {
     let mut reports = vec![];
-    let schema_path = std::path::absolute(std::path::Path::new(
-        "lib/app_resources/schema/scripture_burrito_metadata_schema/source_metadata.schema.json",
-    ))
-    .unwrap();
-    let schema_path_str = schema_path.to_str().unwrap();
-    let mut schemas = Schemas::new();
-    let mut compiler = Compiler::new();
-    let sch_index = compiler.compile(schema_path_str, &mut schemas).expect("Cannot comple schema");
     let metadata_path = format!("{}/metadata.json", burrito_path);
     let metadata_string = std::fs::read_to_string(&metadata_path)
         .expect(format!("Unable to read metadata from {}", metadata_path).as_str());
     let metadata_json = serde_json::from_str(&metadata_string).unwrap();
-    match schemas.validate(&metadata_json, sch_index) {
+    match validate_metadata_json(app_resources_dir, &metadata_json) {
         Ok(_) => reports.push(ok_check_report(
             "Metadata:Validation".to_string(),
             burrito_path.clone(),
         )),
-        Err(errors) => {
+        Err(MetadataValidationError::Schema(e)) => {
+            reports.push(
+                CheckReport {
+                    name: "Metadata:Validation:Schema".to_string(),
+                    path: burrito_path.clone(),
+                    success: false,
+                    comment: Some("Internal error: could not load the metadata schema".to_string()),
+                    data: Some(vec!(e.0)),
+                }
+            );
+        }
+        Err(MetadataValidationError::Invalid(errors)) => {
             reports.push(
                 CheckReport {
                     name: "Metadata:Validation:Validates".to_string(),
                     path: burrito_path.clone(),
                     success: false,
                     comment: Some("Metadata is not schema valid".to_string()),
-                    data: Some(vec!(format!("{}", errors.detailed_output()))),
+                    data: Some(vec!(errors.0)),
                 }
             );
         }
@@ -1,3 +1,19 @@
 pub(crate) mod basic_shape;
 pub(crate) mod report_helpers;
-pub(crate) mod metadata_validation;
\ No newline at end of file
+pub(crate) mod metadata_validation;
+
+use crate::utils::burrito_api::checks::basic_shape::check_basic_shape;
+use crate::utils::burrito_api::checks::metadata_validation::check_metadata_validation;
+use crate::utils::burrito_api::checks::report_helpers::CheckReport;
+
+// Schema validation is only attempted once basic_shape has found readable JSON metadata
+pub(crate) fn audit_report(app_resources_dir: &String, burrito_path: String) -> Vec<CheckReport> {
+    let mut report = check_basic_shape(burrito_path.clone());
+    if report
+        .iter()
+        .any(|r| r.name == "BurritoShape:Metadata" && r.success)
+    {
+        report.extend(check_metadata_validation(app_resources_dir, burrito_path).iter().cloned());
+    }
+    report
+}
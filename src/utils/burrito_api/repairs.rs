@@ -0,0 +1,253 @@
+use crate::structs::PankosmiaError;
+use crate::utils::burrito::{flavor_template_name, refresh_ingredients_in_metadata_value};
+use crate::utils::burrito_api::checks::metadata_validation::missing_required_fields;
+use crate::utils::burrito_api::checks::report_helpers::{ok_check_report, CheckReport};
+use crate::utils::json::replace_in_json_strings;
+use crate::utils::paths::os_slash_str;
+use crate::utils::time::utc_now_timestamp_string;
+use regex::{Captures, Regex};
+use serde_json::{json, Value};
+use std::path::Path;
+
+// Keys that are rebuilt from the files and should never be copied from a template
+const NOT_FROM_TEMPLATE: [&str; 2] = ["ingredients", "currentScope"];
+
+pub(crate) fn repair_ingredients_dir(burrito_path: String) -> Vec<CheckReport> {
+    let mut reports = vec![];
+    let ingredients_path = format!("{}{}ingredients", burrito_path, os_slash_str());
+    if Path::new(&ingredients_path).exists() {
+        return reports;
+    }
+    match std::fs::create_dir(&ingredients_path) {
+        Ok(_) => reports.push(ok_check_report(
+            "Repair:Ingredients:Created".to_string(),
+            burrito_path.clone(),
+        )),
+        Err(e) => reports.push(CheckReport {
+            name: "Repair:Ingredients:Created".to_string(),
+            path: burrito_path.clone(),
+            success: false,
+            comment: Some("Could not create ingredients dir".to_string()),
+            data: Some(vec![e.to_string()]),
+        }),
+    }
+    reports
+}
+
+// Moves content reported by BurritoShape:Content:Unexpected into ingredients/
+pub(crate) fn repair_unexpected_content(burrito_path: String, unexpected: &Vec<String>) -> Vec<CheckReport> {
+    let mut reports = vec![];
+    for entry_name in unexpected {
+        let from_path = format!("{}{}{}", burrito_path, os_slash_str(), entry_name);
+        let to_path = format!(
+            "{}{}ingredients{}{}",
+            burrito_path,
+            os_slash_str(),
+            os_slash_str(),
+            entry_name
+        );
+        if Path::new(&to_path).exists() {
+            reports.push(CheckReport {
+                name: "Repair:Content:Moved".to_string(),
+                path: burrito_path.clone(),
+                success: false,
+                comment: Some("An ingredient with that name already exists".to_string()),
+                data: Some(vec![entry_name.clone()]),
+            });
+            continue;
+        }
+        match std::fs::rename(&from_path, &to_path) {
+            Ok(_) => reports.push(CheckReport {
+                name: "Repair:Content:Moved".to_string(),
+                path: burrito_path.clone(),
+                success: true,
+                comment: Some("Moved into ingredients".to_string()),
+                data: Some(vec![entry_name.clone()]),
+            }),
+            Err(e) => reports.push(CheckReport {
+                name: "Repair:Content:Moved".to_string(),
+                path: burrito_path.clone(),
+                success: false,
+                comment: Some("Could not move into ingredients".to_string()),
+                data: Some(vec![entry_name.clone(), e.to_string()]),
+            }),
+        }
+    }
+    reports
+}
+
+fn first_localized_string(value: &Value) -> Option<String> {
+    match value.as_object() {
+        Some(o) => match o.get("en") {
+            Some(Value::String(s)) => Some(s.clone()),
+            _ => o.values().filter_map(|v| v.as_str()).next().map(|s| s.to_string()),
+        },
+        None => None,
+    }
+}
+
+/// Loads the content template for the flavor of the metadata, using existing metadata values for placeholders.
+pub(crate) fn metadata_template_for(
+    app_resources_dir: &String,
+    burrito_path: &String,
+    metadata: &Value,
+) -> Result<Value, PankosmiaError> {
+    let flavor = match metadata["type"]["flavorType"]["flavor"]["name"].as_str() {
+        Some(f) => f.to_string(),
+        None => return Err(PankosmiaError("No flavor name in metadata".to_string())),
+    };
+    let path_to_template = format!(
+        "{}{}templates{}content_templates{}{}{}metadata.json",
+        app_resources_dir,
+        os_slash_str(),
+        os_slash_str(),
+        os_slash_str(),
+        flavor_template_name(&flavor),
+        os_slash_str(),
+    );
+    let template_string = match std::fs::read_to_string(&path_to_template) {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(PankosmiaError(format!(
+                "Could not read metadata template for {}: {}",
+                flavor, e
+            )))
+        }
+    };
+    let repo_name = Path::new(burrito_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or("".to_string());
+    let abbr = first_localized_string(&metadata["identification"]["abbreviation"]).unwrap_or(repo_name.clone());
+    let content_name = first_localized_string(&metadata["identification"]["name"]).unwrap_or(repo_name);
+    let created = match metadata["meta"]["dateCreated"].as_str() {
+        Some(d) => d.to_string(),
+        None => utc_now_timestamp_string(),
+    };
+    let language_json = match &metadata["languages"][0] {
+        Value::Object(l) => Value::Object(l.clone()),
+        _ => json!({"tag": "und", "name": {"en": "Undetermined"}}),
+    };
+    let substituted = template_string
+        .replace("%%CREATED_TIMESTAMP%%", created.as_str())
+        .replace("%%LANGUAGE%%", serde_json::to_string(&language_json).unwrap().as_str())
+        .replace("%%INGREDIENTS%%", "{}")
+        .replace("%%SCOPE%%", "");
+    let leftover_re = Regex::new("%%[A-Z_]+%%").unwrap();
+    let cleaned = leftover_re.replace_all(&substituted, |c: &Captures| match &c[0] {
+        "%%ABBR%%" | "%%CONTENT_NAME%%" => c[0].to_string(),
+        _ => "".to_string(),
+    });
+    let mut template: Value = match serde_json::from_str(&cleaned) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(PankosmiaError(format!(
+                "Could not parse metadata template for {}: {}",
+                flavor, e
+            )))
+        }
+    };
+    // Names from existing metadata may contain characters that would need escaping in JSON text
+    replace_in_json_strings(&mut template, "%%ABBR%%", abbr.as_str());
+    replace_in_json_strings(&mut template, "%%CONTENT_NAME%%", content_name.as_str());
+    Ok(template)
+}
+
+// Copies the required fields that the schema reports as missing from the template, until no more can be filled.
+// Filled objects may have required fields of their own, which are reported on the next pass.
+fn fill_required_fields(
+    app_resources_dir: &str,
+    metadata: &mut Value,
+    template: &Value,
+) -> Result<Vec<String>, PankosmiaError> {
+    let mut filled = vec![];
+    loop {
+        let mut filled_this_pass = false;
+        for (pointer, keys) in missing_required_fields(app_resources_dir, metadata)? {
+            let template_ob = match template.pointer(&pointer) {
+                Some(Value::Object(o)) => o,
+                _ => continue,
+            };
+            let target_ob = match metadata.pointer_mut(&pointer) {
+                Some(Value::Object(o)) => o,
+                _ => continue,
+            };
+            for key in keys {
+                if NOT_FROM_TEMPLATE.contains(&key.as_str()) || target_ob.contains_key(&key) {
+                    continue;
+                }
+                if let Some(template_value) = template_ob.get(&key) {
+                    target_ob.insert(key.clone(), template_value.clone());
+                    filled.push(format!("{}/{}", pointer, key));
+                    filled_this_pass = true;
+                }
+            }
+        }
+        if !filled_this_pass {
+            return Ok(filled);
+        }
+    }
+}
+
+pub(crate) fn repair_metadata_fields(
+    app_resources_dir: String,
+    burrito_path: String,
+    metadata: &mut Value,
+) -> Vec<CheckReport> {
+    let mut reports = vec![];
+    let template = match metadata_template_for(&app_resources_dir, &burrito_path, metadata) {
+        Ok(t) => t,
+        Err(e) => {
+            reports.push(CheckReport {
+                name: "Repair:Metadata:Template".to_string(),
+                path: burrito_path.clone(),
+                success: false,
+                comment: Some("Could not use a metadata template to fill missing fields".to_string()),
+                data: Some(vec![e.to_string()]),
+            });
+            return reports;
+        }
+    };
+    let filled = match fill_required_fields(&app_resources_dir, metadata, &template) {
+        Ok(f) => f,
+        Err(e) => {
+            reports.push(CheckReport {
+                name: "Repair:Metadata:Filled".to_string(),
+                path: burrito_path.clone(),
+                success: false,
+                comment: Some("Internal error: could not load the metadata schema".to_string()),
+                data: Some(vec![e.to_string()]),
+            });
+            return reports;
+        }
+    };
+    if !filled.is_empty() {
+        reports.push(CheckReport {
+            name: "Repair:Metadata:Filled".to_string(),
+            path: burrito_path.clone(),
+            success: true,
+            comment: Some("Missing required fields filled from template".to_string()),
+            data: Some(filled),
+        });
+    }
+    reports
+}
+
+pub(crate) fn repair_ingredients_metadata(
+    app_resources_dir: String,
+    burrito_path: String,
+    metadata: &mut Value,
+) -> Vec<CheckReport> {
+    refresh_ingredients_in_metadata_value(app_resources_dir, burrito_path.clone(), metadata);
+    let ingredient_keys = match metadata["ingredients"].as_object() {
+        Some(o) => o.keys().cloned().collect(),
+        None => vec![],
+    };
+    vec![CheckReport {
+        name: "Repair:Metadata:Ingredients".to_string(),
+        path: burrito_path.clone(),
+        success: true,
+        comment: Some("Ingredients and currentScope rebuilt from files".to_string()),
+        data: Some(ingredient_keys),
+    }]
+}
@@ -0,0 +1,167 @@
+use crate::utils::bcv_ref::book_order_index;
+use crate::utils::burrito::{ingredients_scopes_from_files, usfm_ingredient_for_book};
+use crate::utils::paths::os_slash_str;
+use crate::utils::usfm::{usfm_plain_text, usfm_verse_content, usfm_verses};
+use crate::utils::versification::{book_max_verses, repo_max_verses};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// The bitrate of paragraph mp3s made by compile_audio, used when there is no project to read
+const COMPILED_AUDIO_BYTES_PER_SECOND: f64 = 8000.0;
+
+#[derive(Serialize, Default)]
+pub(crate) struct ChapterStats {
+    chapter: u16,
+    expected_verses: usize,
+    present_verses: usize,
+    empty_verses: Vec<u16>,
+    words: usize,
+    recorded_paragraphs: usize,
+    audio_duration: f64,
+}
+
+#[derive(Serialize, Default)]
+pub(crate) struct BookStats {
+    book_code: String,
+    expected_verses: usize,
+    present_verses: usize,
+    empty_verses: usize,
+    words: usize,
+    recorded_paragraphs: usize,
+    audio_duration: f64,
+    chapters: Vec<ChapterStats>,
+}
+
+// Seconds of audio in a paragraph directory, from the edit list of its project or the size of its compiled mp3
+fn paragraph_duration(paragraph_dir: &Path, paragraph_name: &str) -> Option<f64> {
+    let path_to_project = paragraph_dir.join(format!("{}_project.json", paragraph_name));
+    if let Some(project) = std::fs::read_to_string(&path_to_project)
+        .ok()
+        .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+    {
+        let duration = project["tracks"][0]["edl"]
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .map(|seg| {
+                let src_start = seg["srcStart"].as_f64().unwrap_or(0.0);
+                let src_end = seg["srcEnd"].as_f64().unwrap_or(0.0);
+                seg["vStart"].as_f64().unwrap_or(0.0) + (src_end - src_start).max(0.0)
+            })
+            .fold(0.0, f64::max);
+        if duration > 0.0 {
+            return Some(duration);
+        }
+    }
+    let path_to_mp3 = paragraph_dir.join(format!("{}.mp3", paragraph_name));
+    std::fs::metadata(path_to_mp3)
+        .ok()
+        .map(|m| m.len() as f64 / COMPILED_AUDIO_BYTES_PER_SECOND)
+}
+
+// Recorded paragraphs and their duration by chapter, for the `CC-PP` directories of an audio_content directory
+fn audio_chapter_stats(audio_dir: &Path) -> BTreeMap<u16, (usize, f64)> {
+    let mut chapters = BTreeMap::new();
+    let entries = match std::fs::read_dir(audio_dir) {
+        Ok(e) => e,
+        Err(_) => return chapters,
+    };
+    for entry in entries.flatten() {
+        if !entry.path().is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let chapter = match name.split_once("-").and_then(|(c, p)| {
+            p.parse::<u16>().ok()?;
+            c.parse::<u16>().ok()
+        }) {
+            Some(c) => c,
+            None => continue,
+        };
+        if let Some(duration) = paragraph_duration(&entry.path(), &name) {
+            let chapter_stats = chapters.entry(chapter).or_insert((0, 0.0));
+            chapter_stats.0 += 1;
+            chapter_stats.1 += duration;
+        }
+    }
+    chapters
+}
+
+/// Drafting statistics for every book of a USFM or audio burrito, by chapter. Verses are expected from `ingredients/vrs.json`.
+/// Audio is read from `audio_content/<book>/CC-PP` or, for burritos with a single book, `audio_content/CC-PP`. Durations are in seconds.
+pub(crate) fn burrito_stats(app_resources_dir: &str, repo_path: &str) -> Vec<BookStats> {
+    let max_verses = repo_max_verses(repo_path);
+    let path_to_audio = Path::new(repo_path).join("ingredients").join("audio_content");
+    let mut book_codes: Vec<String> = ingredients_scopes_from_files(app_resources_dir.to_string(), repo_path.to_string())
+        .into_keys()
+        .collect();
+    let mut audio_dirs: BTreeMap<String, std::path::PathBuf> = BTreeMap::new();
+    if let Ok(entries) = std::fs::read_dir(&path_to_audio) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_uppercase();
+            if entry.path().is_dir() && name.len() == 3 && !name.contains("-") {
+                audio_dirs.insert(name, entry.path());
+            }
+        }
+    }
+    book_codes.extend(audio_dirs.keys().cloned());
+    book_codes.sort_by_key(|b| book_order_index(b));
+    book_codes.dedup();
+    let single_book = book_codes.len() == 1;
+    let mut books = vec![];
+    for book_code in book_codes {
+        let chapter_counts = book_max_verses(&max_verses, &book_code);
+        let usfm = usfm_ingredient_for_book(app_resources_dir, repo_path, &book_code)
+            .and_then(|p| std::fs::read_to_string(format!("{}{}ingredients{}{}", repo_path, os_slash_str(), os_slash_str(), p)).ok())
+            .unwrap_or_default();
+        let verse_content = usfm_verse_content(&usfm);
+        let mut words: BTreeMap<u16, usize> = BTreeMap::new();
+        for verse in usfm_verses(&usfm) {
+            *words.entry(verse.chapter).or_insert(0) += usfm_plain_text(&verse.usfm).split_whitespace().count();
+        }
+        let audio = match audio_dirs.get(&book_code) {
+            Some(d) => audio_chapter_stats(d),
+            None if single_book => audio_chapter_stats(&path_to_audio),
+            None => BTreeMap::new(),
+        };
+        let last_chapter = [
+            chapter_counts.len() as u16,
+            verse_content.drafted.keys().last().copied().unwrap_or(0),
+            verse_content.empty.keys().last().copied().unwrap_or(0),
+            audio.keys().last().copied().unwrap_or(0),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0);
+        let mut book_stats = BookStats {
+            book_code: book_code.clone(),
+            ..Default::default()
+        };
+        for chapter in 1..=last_chapter {
+            let (recorded_paragraphs, audio_duration) = audio.get(&chapter).copied().unwrap_or((0, 0.0));
+            let chapter_stats = ChapterStats {
+                chapter,
+                expected_verses: chapter_counts.get((chapter - 1) as usize).copied().unwrap_or(0) as usize,
+                present_verses: verse_content.drafted.get(&chapter).map_or(0, |v| v.len()),
+                empty_verses: verse_content
+                    .empty
+                    .get(&chapter)
+                    .map_or(vec![], |v| v.iter().copied().collect()),
+                words: words.get(&chapter).copied().unwrap_or(0),
+                recorded_paragraphs,
+                audio_duration,
+            };
+            book_stats.expected_verses += chapter_stats.expected_verses;
+            book_stats.present_verses += chapter_stats.present_verses;
+            book_stats.empty_verses += chapter_stats.empty_verses.len();
+            book_stats.words += chapter_stats.words;
+            book_stats.recorded_paragraphs += chapter_stats.recorded_paragraphs;
+            book_stats.audio_duration += chapter_stats.audio_duration;
+            book_stats.chapters.push(chapter_stats);
+        }
+        books.push(book_stats);
+    }
+    books
+}
@@ -0,0 +1,520 @@
+use crate::structs::PankosmiaError;
+use crate::utils::burrito::{edit_metadata, metadata_language_tag, refresh_ingredients_in_metadata_value};
+use crate::utils::local_repo::{add_and_commit_all, init_local_repo, new_local_repo_path, set_local_repo_user};
+use crate::utils::paths::os_slash_str;
+use crate::utils::time::utc_now_timestamp_string;
+use git2::{Repository, StatusOptions};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use walkdir::WalkDir;
+
+#[derive(Serialize)]
+pub(crate) struct IngredientTransfer {
+    books: Vec<String>,
+    ingredients: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BookSource {
+    repo_path: String,
+    commit: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BurritoMerge {
+    repo_path: String,
+    books: BTreeMap<String, BookSource>,
+}
+
+fn ingredients_dir(repo_path: &str) -> String {
+    format!("{}{}ingredients", repo_path, os_slash_str())
+}
+
+fn ingredient_path(repo_path: &str, ipath: &str) -> String {
+    format!("{}{}{}", ingredients_dir(repo_path), os_slash_str(), ipath)
+}
+
+/// Reads the metadata of the repo at the given path as JSON.
+pub(crate) fn read_repo_metadata(repo_path: &str) -> Result<Value, PankosmiaError> {
+    let metadata_string = match std::fs::read_to_string(format!("{}{}metadata.json", repo_path, os_slash_str())) {
+        Ok(s) => s,
+        Err(e) => return Err(PankosmiaError(format!("Could not load metadata as string: {}", e))),
+    };
+    match serde_json::from_str(&metadata_string) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(PankosmiaError(format!("Could not parse metadata: {}", e))),
+    }
+}
+
+fn metadata_flavor(metadata: &Value) -> (String, String) {
+    (
+        metadata["type"]["flavorType"]["name"].as_str().unwrap_or("?").to_string(),
+        metadata["type"]["flavorType"]["flavor"]["name"].as_str().unwrap_or("?").to_string(),
+    )
+}
+
+/// Ingredients may only be shared between burritos with the same flavorType and flavor.
+pub(crate) fn check_compatible_flavors(src_metadata: &Value, target_metadata: &Value) -> Result<(), PankosmiaError> {
+    let src_flavor = metadata_flavor(src_metadata);
+    let target_flavor = metadata_flavor(target_metadata);
+    if src_flavor.1 == "?" || src_flavor != target_flavor {
+        return Err(PankosmiaError(format!(
+            "Cannot use {}/{} ingredients in a {}/{} burrito",
+            src_flavor.0, src_flavor.1, target_flavor.0, target_flavor.1
+        )));
+    }
+    Ok(())
+}
+
+// Ingredient files under the ingredients directory, as paths relative to that directory, excluding backups and hidden files
+fn ingredient_files(repo_path: &str, ipath: Option<&str>) -> Vec<String> {
+    let ingredients_path = ingredients_dir(repo_path);
+    let root = match ipath {
+        Some(p) => ingredient_path(repo_path, p),
+        None => ingredients_path.clone(),
+    };
+    let mut files = vec![];
+    for entry in WalkDir::new(&root).into_iter().filter_map(|e| e.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !entry.path().is_file() || file_name.starts_with(".") || file_name.ends_with(".bak") {
+            continue;
+        }
+        if let Ok(relative_path) = entry.path().strip_prefix(&ingredients_path) {
+            files.push(relative_path.display().to_string().replace("\\", "/"));
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Returns the ingredients of a book, as paths relative to ingredients: files named after the book, files in a directory named after the book
+/// (eg audio_content/TIT/01-01.mp3), and files whose metadata scope is the book.
+pub(crate) fn book_ingredient_paths(repo_path: &str, metadata: &Value, book_code: &str) -> Vec<String> {
+    ingredient_files(repo_path, None)
+        .into_iter()
+        .filter(|ipath| {
+            let steps: Vec<&str> = ipath.split("/").collect();
+            let file_stem = steps.last().unwrap().split(".").next().unwrap_or("");
+            file_stem == book_code
+                || steps[..steps.len() - 1].contains(&book_code)
+                || metadata["ingredients"][format!("ingredients/{}", ipath)]["scope"]
+                    .get(book_code)
+                    .is_some()
+        })
+        .collect()
+}
+
+// Book codes of ingredients named after a book
+fn ingredient_book_codes(ipaths: &[String]) -> BTreeSet<String> {
+    let book_regex = Regex::new("^[1-6A-Z]{3}$").unwrap();
+    ipaths
+        .iter()
+        .filter_map(|ipath| ipath.split("/").last().and_then(|f| f.split(".").next()))
+        .filter(|stem| book_regex.is_match(stem))
+        .map(|stem| stem.to_string())
+        .collect()
+}
+
+// Copies an ingredient file, making parent directories and backing up any existing target
+fn copy_ingredient_file(src_path: &str, target_path: &str, ipath: &str) -> Result<(), PankosmiaError> {
+    let full_target_path = ingredient_path(target_path, ipath);
+    if let Some(parent) = Path::new(&full_target_path).parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return Err(PankosmiaError(format!("Could not create target parent directories: {}", e)));
+        }
+    }
+    if Path::new(&full_target_path).exists() {
+        if let Err(e) = std::fs::rename(&full_target_path, format!("{}.bak", &full_target_path)) {
+            return Err(PankosmiaError(format!("Could not write backup file: {}", e)));
+        }
+    }
+    match std::fs::copy(ingredient_path(src_path, ipath), &full_target_path) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(PankosmiaError(format!("Could not copy ingredient '{}': {}", ipath, e))),
+    }
+}
+
+// Removes directories left empty by moving ingredients, up to the ingredients directory
+fn remove_empty_parents(repo_path: &str, ipath: &str) {
+    let ingredients_path = ingredients_dir(repo_path);
+    let mut parent = Path::new(&ingredient_path(repo_path, ipath)).parent().map(|p| p.to_path_buf());
+    while let Some(dir) = parent {
+        if dir.display().to_string().len() <= ingredients_path.len() || std::fs::remove_dir(&dir).is_err() {
+            break;
+        }
+        parent = dir.parent().map(|p| p.to_path_buf());
+    }
+}
+
+/// Copies or moves ingredients, and the ingredients of whole books, from one burrito to another with the same flavor.
+/// Ingredients and currentScope are rewritten in both metadata files, localizedNames of the books are copied to the target,
+/// and localizedNames of books moved out of scope are removed from the source. Existing target ingredients are only replaced,
+/// with a backup, when *overwrite* is set. When moving, source files are deleted before either metadata file is written, and errors
+/// after the copy say which steps completed.
+pub(crate) fn transfer_ingredients(
+    app_resources_dir: &str,
+    src_path: &str,
+    target_path: &str,
+    books: &[String],
+    ipaths: &[String],
+    delete_src: bool,
+    overwrite: bool,
+) -> Result<IngredientTransfer, PankosmiaError> {
+    if src_path == target_path {
+        return Err(PankosmiaError("Source and target must be different".to_string()));
+    }
+    let src_metadata = read_repo_metadata(src_path)?;
+    let target_metadata = read_repo_metadata(target_path)?;
+    check_compatible_flavors(&src_metadata, &target_metadata)?;
+    let mut transferred: BTreeSet<String> = BTreeSet::new();
+    for book_code in books {
+        let book_ipaths = book_ingredient_paths(src_path, &src_metadata, book_code);
+        if book_ipaths.is_empty() {
+            return Err(PankosmiaError(format!("No ingredients for book {}", book_code)));
+        }
+        transferred.extend(book_ipaths);
+    }
+    for ipath in ipaths {
+        if !Path::new(&ingredient_path(src_path, ipath)).exists() {
+            return Err(PankosmiaError(format!("Source ingredient '{}' not found", ipath)));
+        }
+        transferred.extend(ingredient_files(src_path, Some(ipath)));
+    }
+    if transferred.is_empty() {
+        return Err(PankosmiaError("No ingredients to copy".to_string()));
+    }
+    let transferred: Vec<String> = transferred.into_iter().collect();
+    if !overwrite {
+        if let Some(existing) = transferred.iter().find(|p| Path::new(&ingredient_path(target_path, p)).exists()) {
+            return Err(PankosmiaError(format!("Target ingredient '{}' already exists", existing)));
+        }
+    }
+    for ipath in transferred.iter() {
+        copy_ingredient_file(src_path, target_path, ipath)?;
+    }
+    // Source files are deleted before either metadata file is written, so that both are rewritten from the files actually present,
+    // even when a delete fails part way
+    let mut delete_error = None;
+    if delete_src {
+        for ipath in transferred.iter() {
+            if let Err(e) = std::fs::remove_file(ingredient_path(src_path, ipath)) {
+                delete_error = Some(format!("could not delete source ingredient '{}': {}", ipath, e));
+                break;
+            }
+            remove_empty_parents(src_path, ipath);
+        }
+    }
+    let mut book_codes = ingredient_book_codes(&transferred);
+    book_codes.extend(books.iter().cloned());
+    if let Err(e) = edit_metadata(app_resources_dir, target_path, |metadata| {
+        refresh_ingredients_in_metadata_value(app_resources_dir.to_string(), target_path.to_string(), metadata);
+        for book_code in book_codes.iter() {
+            let names_key = format!("book-{}", book_code.to_lowercase());
+            let src_names = &src_metadata["localizedNames"][&names_key];
+            if src_names.is_object() && (overwrite || !metadata["localizedNames"][&names_key].is_object()) {
+                if !metadata["localizedNames"].is_object() {
+                    metadata["localizedNames"] = json!({});
+                }
+                metadata["localizedNames"][&names_key] = src_names.clone();
+            }
+        }
+        Ok(())
+    }) {
+        return Err(PankosmiaError(format!(
+            "Ingredients were copied{} but target metadata was not updated: {}",
+            if delete_src { " and source ingredients deleted" } else { "" },
+            e.0
+        )));
+    }
+    if delete_src {
+        if let Err(e) = edit_metadata(app_resources_dir, src_path, |metadata| {
+            refresh_ingredients_in_metadata_value(app_resources_dir.to_string(), src_path.to_string(), metadata);
+            for book_code in book_codes.iter() {
+                if metadata["type"]["flavorType"]["currentScope"].get(book_code.as_str()).is_none() {
+                    if let Some(names) = metadata["localizedNames"].as_object_mut() {
+                        names.remove(&format!("book-{}", book_code.to_lowercase()));
+                    }
+                }
+            }
+            Ok(())
+        }) {
+            return Err(PankosmiaError(format!(
+                "Ingredients were copied and target metadata updated, but source metadata was not updated: {}",
+                e.0
+            )));
+        }
+    }
+    if let Some(e) = delete_error {
+        return Err(PankosmiaError(format!(
+            "Ingredients were copied and both metadata files updated from the files present, but {}",
+            e
+        )));
+    }
+    Ok(IngredientTransfer {
+        books: book_codes.into_iter().collect(),
+        ingredients: transferred,
+    })
+}
+
+fn current_scope_books(metadata: &Value) -> Vec<String> {
+    match metadata["type"]["flavorType"]["currentScope"].as_object() {
+        Some(scope) => scope.keys().cloned().collect(),
+        None => vec![],
+    }
+}
+
+fn head_commit_id(repo: &Repository) -> Option<String> {
+    repo.head().ok().and_then(|h| h.peel_to_commit().ok()).map(|c| c.id().to_string())
+}
+
+fn has_uncommitted_changes(repo: &Repository) -> Result<bool, PankosmiaError> {
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(true).recurse_untracked_dirs(true);
+    match repo.statuses(Some(&mut status_opts)) {
+        // Backups made when ingredients are replaced are not content
+        Ok(statuses) => Ok(statuses
+            .iter()
+            .any(|entry| !entry.status().is_ignored() && !entry.path().unwrap_or("").ends_with(".bak"))),
+        Err(e) => Err(PankosmiaError(format!("Status check failed: {}", e))),
+    }
+}
+
+fn set_abbreviation(metadata: &mut Value, abbr: &str) {
+    if let Some(abbreviations) = metadata["identification"]["abbreviation"].as_object_mut() {
+        for (_, value) in abbreviations.iter_mut() {
+            *value = json!(abbr);
+        }
+    }
+}
+
+// Appends the short name of the book to each name of the burrito, in the same language when there is one, or else the book code
+fn append_book_name(metadata: &mut Value, book_code: &str) {
+    let book_names = metadata["localizedNames"][format!("book-{}", book_code.to_lowercase())]["short"].clone();
+    if let Some(names) = metadata["identification"]["name"].as_object_mut() {
+        for (language, value) in names.iter_mut() {
+            let book_name = book_names[language].as_str().unwrap_or(book_code);
+            *value = json!(format!("{} ({})", value.as_str().unwrap_or(""), book_name));
+        }
+    }
+}
+
+// Clones the source repo, keeping its history, then removes every book except one and commits
+fn make_book_burrito(
+    app_resources_dir: &str,
+    src_path: &str,
+    src_repo_path: &str,
+    src_metadata: &Value,
+    new_repo_path: &str,
+    book_code: &str,
+    abbr: &str,
+) -> Result<(), PankosmiaError> {
+    let new_repo = match Repository::clone(src_path, new_repo_path) {
+        Ok(r) => r,
+        Err(e) => return Err(PankosmiaError(format!("Could not clone repo: {}", e))),
+    };
+    if let Err(e) = new_repo.remote_delete("origin") {
+        return Err(PankosmiaError(format!("Could not remove origin of new repo: {}", e)));
+    }
+    set_local_repo_user(&new_repo)?;
+    let other_books: Vec<String> = current_scope_books(src_metadata)
+        .into_iter()
+        .filter(|b| b != book_code)
+        .collect();
+    for other_book in other_books.iter() {
+        for ipath in book_ingredient_paths(new_repo_path, src_metadata, other_book) {
+            if let Err(e) = std::fs::remove_file(ingredient_path(new_repo_path, &ipath)) {
+                return Err(PankosmiaError(format!("Could not remove ingredient '{}': {}", ipath, e)));
+            }
+            remove_empty_parents(new_repo_path, &ipath);
+        }
+    }
+    edit_metadata(app_resources_dir, new_repo_path, |metadata| {
+        refresh_ingredients_in_metadata_value(app_resources_dir.to_string(), new_repo_path.to_string(), metadata);
+        if let Some(names) = metadata["localizedNames"].as_object_mut() {
+            for other_book in other_books.iter() {
+                names.remove(&format!("book-{}", other_book.to_lowercase()));
+            }
+        }
+        append_book_name(metadata, book_code);
+        set_abbreviation(metadata, abbr);
+        Ok(())
+    })?;
+    add_and_commit_all(&new_repo, format!("Split {} from {}", book_code, src_repo_path).as_str())
+}
+
+/// Splits a burrito into one new local burrito per book, for every book in currentScope or for *books*. Each new repo is a clone
+/// of the source, so it keeps the history of the source, with the ingredients and localizedNames of other books removed in a new commit.
+/// New repos are called after the source repo and the book, eg `my_bible_TIT`, and the book name is added to their names. The source repo is
+/// unchanged, and must have no uncommitted changes. Returns the repo paths of the new repos. If any new repo cannot be made, every repo made
+/// by the split is removed.
+pub(crate) fn split_burrito(
+    app_resources_dir: &str,
+    repo_dir: &str,
+    src_repo_path: &str,
+    books: &Option<Vec<String>>,
+) -> Result<Vec<String>, PankosmiaError> {
+    let src_path = format!("{}{}{}", repo_dir, os_slash_str(), src_repo_path);
+    let src_metadata = read_repo_metadata(&src_path)?;
+    let src_repo = match Repository::open(&src_path) {
+        Ok(r) => r,
+        Err(e) => return Err(PankosmiaError(format!("Could not open repo: {}", e))),
+    };
+    if has_uncommitted_changes(&src_repo)? {
+        return Err(PankosmiaError("Uncommitted changes detected. Commit before splitting.".to_string()));
+    }
+    let scope_books = current_scope_books(&src_metadata);
+    let split_books = match books {
+        Some(b) => b.clone(),
+        None => scope_books.clone(),
+    };
+    if let Some(missing) = split_books.iter().find(|b| !scope_books.contains(b)) {
+        return Err(PankosmiaError(format!("Book {} is not in the scope of the burrito", missing)));
+    }
+    if split_books.is_empty() {
+        return Err(PankosmiaError("No books to split".to_string()));
+    }
+    if split_books.len() < 2 && books.is_none() {
+        return Err(PankosmiaError("Only burritos with several books can be split".to_string()));
+    }
+    let src_name = src_repo_path.split("/").last().unwrap_or("burrito").to_string();
+    let mut new_repo_paths = vec![];
+    let mut made_paths: Vec<String> = vec![];
+    for book_code in split_books.iter() {
+        let abbr = format!("{}_{}", src_name, book_code);
+        let made = new_local_repo_path(repo_dir, &abbr).and_then(|new_repo_path| {
+            made_paths.push(new_repo_path.clone());
+            make_book_burrito(
+                app_resources_dir,
+                &src_path,
+                src_repo_path,
+                &src_metadata,
+                &new_repo_path,
+                book_code,
+                &abbr,
+            )
+        });
+        if let Err(e) = made {
+            for made_path in made_paths.iter() {
+                let _ = std::fs::remove_dir_all(made_path);
+            }
+            return Err(e);
+        }
+        new_repo_paths.push(format!("_local_/_local_/{}", abbr));
+    }
+    Ok(new_repo_paths)
+}
+
+// Copies the ingredients of each source into the new repo, with one commit per source and one metadata comment per book recording
+// where its books came from
+fn make_merged_burrito(
+    app_resources_dir: &str,
+    sources: &[(String, String, Value)],
+    new_repo_path: &str,
+    abbr: &str,
+) -> Result<BTreeMap<String, BookSource>, PankosmiaError> {
+    let new_repo = init_local_repo(new_repo_path)?;
+    let path_to_new_metadata = format!("{}{}metadata.json", new_repo_path, os_slash_str());
+    if let Err(e) = std::fs::write(&path_to_new_metadata, sources[0].2.to_string()) {
+        return Err(PankosmiaError(format!("Could not write metadata to new repo: {}", e)));
+    }
+    let mut book_sources = BTreeMap::new();
+    for (src_repo_path, src_path, src_metadata) in sources.iter() {
+        let src_books = current_scope_books(src_metadata);
+        let commit = Repository::open(src_path).ok().and_then(|r| head_commit_id(&r));
+        let provenance = format!(
+            "from {}{}",
+            src_repo_path,
+            commit.as_ref().map(|c| format!(" at {}", c)).unwrap_or_default()
+        );
+        for ipath in ingredient_files(src_path, None) {
+            // Shared ingredients such as versification come from the first source that has them
+            if !Path::new(&ingredient_path(new_repo_path, &ipath)).exists() {
+                copy_ingredient_file(src_path, new_repo_path, &ipath)?;
+            }
+        }
+        edit_metadata(app_resources_dir, new_repo_path, |metadata| {
+            refresh_ingredients_in_metadata_value(app_resources_dir.to_string(), new_repo_path.to_string(), metadata);
+            for book_code in src_books.iter() {
+                let names_key = format!("book-{}", book_code.to_lowercase());
+                if src_metadata["localizedNames"][&names_key].is_object() {
+                    if !metadata["localizedNames"].is_object() {
+                        metadata["localizedNames"] = json!({});
+                    }
+                    metadata["localizedNames"][&names_key] = src_metadata["localizedNames"][&names_key].clone();
+                }
+            }
+            set_abbreviation(metadata, abbr);
+            metadata["meta"]["dateCreated"] = json!(utc_now_timestamp_string());
+            if !metadata["meta"]["comments"].is_array() {
+                metadata["meta"]["comments"] = json!([]);
+            }
+            if let Some(comments) = metadata["meta"]["comments"].as_array_mut() {
+                for book_code in src_books.iter() {
+                    comments.push(json!(format!("{} merged {}", book_code, provenance)));
+                }
+            }
+            Ok(())
+        })?;
+        add_and_commit_all(&new_repo, format!("Merge {} {}", src_books.join(", "), provenance).as_str())?;
+        for book_code in src_books {
+            book_sources.insert(
+                book_code,
+                BookSource {
+                    repo_path: src_repo_path.clone(),
+                    commit: commit.clone(),
+                },
+            );
+        }
+    }
+    Ok(book_sources)
+}
+
+/// Merges burritos with the same flavor and language, and no books in common, into a new local burrito called *abbr*.
+/// The metadata of the first burrito is used as a starting point. The source repos are unchanged and keep their history.
+/// The new repo has one commit per source, whose message records the source repo and commit of its books, and `meta.comments` in its
+/// metadata records the same for each book, eg `TIT merged from _local_/_local_/my_tit at 1f2e...`.
+pub(crate) fn merge_burritos(
+    app_resources_dir: &str,
+    repo_dir: &str,
+    src_repo_paths: &[String],
+    abbr: &str,
+) -> Result<BurritoMerge, PankosmiaError> {
+    if src_repo_paths.len() < 2 {
+        return Err(PankosmiaError("At least two burritos are needed for a merge".to_string()));
+    }
+    let mut sources: Vec<(String, String, Value)> = vec![];
+    let mut seen_books: BTreeSet<String> = BTreeSet::new();
+    for src_repo_path in src_repo_paths {
+        let src_path = format!("{}{}{}", repo_dir, os_slash_str(), src_repo_path);
+        let src_metadata = read_repo_metadata(&src_path)?;
+        if let Some((_, _, first_metadata)) = sources.first() {
+            check_compatible_flavors(&src_metadata, first_metadata)?;
+            if metadata_language_tag(&src_metadata) != metadata_language_tag(first_metadata) {
+                return Err(PankosmiaError(format!(
+                    "{} is not in the same language as {}",
+                    src_repo_path, sources[0].0
+                )));
+            }
+        }
+        for book_code in current_scope_books(&src_metadata) {
+            if !seen_books.insert(book_code.clone()) {
+                return Err(PankosmiaError(format!("Book {} is in more than one burrito", book_code)));
+            }
+        }
+        sources.push((src_repo_path.clone(), src_path, src_metadata));
+    }
+    let new_repo_path = new_local_repo_path(repo_dir, abbr)?;
+    match make_merged_burrito(app_resources_dir, &sources, &new_repo_path, abbr) {
+        Ok(books) => Ok(BurritoMerge {
+            repo_path: format!("_local_/_local_/{}", abbr),
+            books,
+        }),
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&new_repo_path);
+            Err(e)
+        }
+    }
+}
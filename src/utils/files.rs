@@ -20,6 +20,13 @@ pub(crate) fn customize_and_copy_template_file(from_path: &str, to_path: &String
     Ok(())
 }
 
+/// Writes a file through a temporary file in the same directory, so that readers never see a partly written file.
+pub(crate) fn write_file_atomically(to_path: &String, contents: &str) -> Result<(), std::io::Error> {
+    let temp_path = format!("{}.tmp", to_path);
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, to_path)
+}
+
 pub(crate) fn load_json(from_path: &str) -> Result<Value, std::io::Error> {
     let json_string = match fs::read_to_string(&from_path) {
         Ok(s) => s,
@@ -51,6 +58,11 @@ pub(crate) fn write_user_settings(state: &State<AppSettings>, clients: &State<Cl
 }
 
 pub(crate) fn write_app_state(state: &State<AppSettings>, new_json: Value) -> Result<(), std::io::Error> {
+    let mut new_json = new_json;
+    // Navigation history is kept whatever the caller is updating
+    if new_json.is_object() && new_json.get("navigation_history").is_none() {
+        new_json["navigation_history"] = serde_json::to_value(&*state.navigation_history.lock().unwrap())?;
+    }
     let working_dir = state.working_dir.clone();
     let to_path = app_state_path(&working_dir);
     let file_handle = fs::File::create(&to_path)?;
@@ -0,0 +1,164 @@
+use crate::structs::{AlignmentSource, GlossaryTerm, PankosmiaError};
+use crate::utils::alignment::{repo_alignments, ReferencedAlignment};
+use crate::utils::files::write_file_atomically;
+use crate::utils::metadata_index::local_repo_paths;
+use crate::utils::paths::os_slash_str;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Clone)]
+pub(crate) struct GlossaryReference {
+    repo_path: String,
+    book_code: String,
+    chapter: u16,
+    verse: u16,
+}
+
+#[derive(Serialize)]
+pub(crate) struct MemoryRendering {
+    rendering: String,
+    count: usize,
+    references: Vec<GlossaryReference>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ConsistencyWarning {
+    term_id: String,
+    lemma: String,
+    rendering: String,
+    expected: Vec<String>,
+    reference: GlossaryReference,
+}
+
+/// Path of the glossary of a burrito, which is an ingredient so that it is shared with the rest of the project.
+pub(crate) fn glossary_path(repo_path: &String) -> String {
+    format!("{}{}ingredients{}glossary.json", repo_path, os_slash_str(), os_slash_str())
+}
+
+/// Reads the glossary of a burrito. A missing glossary is empty, but one that cannot be read or parsed is an error, so that it is never overwritten.
+pub(crate) fn read_glossary(repo_path: &String) -> Result<Vec<GlossaryTerm>, PankosmiaError> {
+    match std::fs::read_to_string(glossary_path(repo_path)) {
+        Ok(s) => serde_json::from_str(&s).map_err(|e| PankosmiaError(format!("Could not parse glossary: {}", e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(PankosmiaError(format!("Could not read glossary: {}", e))),
+    }
+}
+
+pub(crate) fn write_glossary(repo_path: &String, terms: &[GlossaryTerm]) -> Result<(), PankosmiaError> {
+    let glossary_string = match serde_json::to_string_pretty(terms) {
+        Ok(s) => s,
+        Err(e) => return Err(PankosmiaError(format!("Could not make glossary as JSON: {}", e))),
+    };
+    match write_file_atomically(&glossary_path(repo_path), &glossary_string) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(PankosmiaError(format!("Could not write glossary: {}", e))),
+    }
+}
+
+fn source_matches(source: &AlignmentSource, lemma: &Option<String>, strong: &Option<String>) -> bool {
+    if let Some(l) = lemma {
+        if source.lemma.as_ref() == Some(l) || (source.lemma.is_none() && source.content == *l) {
+            return true;
+        }
+    }
+    // Strong's numbers may carry a trailing sense digit, as in G23160
+    match (strong, &source.strong) {
+        (Some(s), Some(source_strong)) => {
+            source_strong == s || *source_strong == format!("{}0", s) || *s == format!("{}0", source_strong)
+        }
+        _ => false,
+    }
+}
+
+fn rendering(referenced: &ReferencedAlignment) -> String {
+    referenced
+        .alignment
+        .targets
+        .iter()
+        .map(|t| t.word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn reference(repo_path: &str, referenced: &ReferencedAlignment) -> GlossaryReference {
+    GlossaryReference {
+        repo_path: repo_path.to_string(),
+        book_code: referenced.book_code.clone(),
+        chapter: referenced.chapter,
+        verse: referenced.verse,
+    }
+}
+
+/// Renderings of a lemma or Strong's number in the aligned USFM of every local burrito, most frequent first.
+pub(crate) fn translation_memory(repo_dir: &String, lemma: &Option<String>, strong: &Option<String>) -> Vec<MemoryRendering> {
+    let mut renderings: BTreeMap<String, Vec<GlossaryReference>> = BTreeMap::new();
+    for repo_path in local_repo_paths(repo_dir, &None) {
+        let full_repo_path = format!("{}{}{}", repo_dir, os_slash_str(), repo_path);
+        for referenced in repo_alignments(&full_repo_path) {
+            if !referenced.alignment.sources.iter().any(|s| source_matches(s, lemma, strong)) {
+                continue;
+            }
+            renderings
+                .entry(rendering(&referenced))
+                .or_default()
+                .push(reference(&repo_path, &referenced));
+        }
+    }
+    let mut memory: Vec<MemoryRendering> = renderings
+        .into_iter()
+        .map(|(rendering, references)| MemoryRendering {
+            rendering,
+            count: references.len(),
+            references,
+        })
+        .collect();
+    memory.sort_by_key(|m| std::cmp::Reverse(m.count));
+    memory
+}
+
+/// Finds verses of a burrito where a glossary term is rendered differently. Terms with renderings are checked against them.
+/// For other terms, renderings other than the most frequent one are reported.
+pub(crate) fn consistency_warnings(
+    repo_path: &String,
+    display_repo_path: &str,
+    terms: &[GlossaryTerm],
+    book_code: &Option<String>,
+) -> Vec<ConsistencyWarning> {
+    let alignments: Vec<ReferencedAlignment> = repo_alignments(repo_path)
+        .into_iter()
+        .filter(|a| book_code.as_ref().is_none_or(|b| *b == a.book_code))
+        .collect();
+    let mut warnings = vec![];
+    for term in terms {
+        let lemma = Some(term.lemma.clone());
+        let term_alignments: Vec<(String, &ReferencedAlignment)> = alignments
+            .iter()
+            .filter(|a| a.alignment.sources.iter().any(|s| source_matches(s, &lemma, &term.strong)))
+            .map(|a| (rendering(a), a))
+            .collect();
+        let expected: Vec<String> = if term.renderings.is_empty() {
+            let mut counts: BTreeMap<&String, usize> = BTreeMap::new();
+            for (r, _) in term_alignments.iter() {
+                *counts.entry(r).or_insert(0) += 1;
+            }
+            match counts.into_iter().max_by_key(|(_, count)| *count) {
+                Some((r, _)) => vec![r.clone()],
+                None => continue,
+            }
+        } else {
+            term.renderings.iter().map(|r| r.to_lowercase()).collect()
+        };
+        for (r, referenced) in term_alignments {
+            if !expected.contains(&r) {
+                warnings.push(ConsistencyWarning {
+                    term_id: term.id.clone(),
+                    lemma: term.lemma.clone(),
+                    rendering: r,
+                    expected: expected.clone(),
+                    reference: reference(display_repo_path, referenced),
+                });
+            }
+        }
+    }
+    warnings
+}
@@ -0,0 +1,455 @@
+use crate::structs::{PankosmiaError, Typography};
+use crate::utils::bcv_ref::book_order_index;
+use crate::utils::burrito::metadata_language_tag;
+use crate::utils::files::copy_and_customize_webfont_css2;
+use crate::utils::paths::{os_slash_str, source_webfonts_path};
+use crate::utils::usfm::usfm_book_headers;
+use crate::utils::usj::{attribute, marker_base, parse_usfm, xml_escape, UsfmElement, UsfmNode};
+use copy_dir::copy_dir;
+use serde_json::Value;
+use std::path::Path;
+use walkdir::WalkDir;
+
+const SITE_CSS: &str = "body {max-width: 50em; margin: 0 auto; padding: 1em; line-height: 1.6}
+nav {margin: 1em 0; padding-bottom: 0.5em; border-bottom: 1px solid #ccc}
+nav a {margin-right: 0.75em}
+.usfm-c {margin-top: 1.5em}
+.usfm-v {font-size: 0.7em; margin: 0 0.2em; color: #666}
+.usfm-mt, .usfm-mt1 {font-size: 2em; text-align: center; font-weight: bold}
+.usfm-mt2, .usfm-mt3 {font-size: 1.4em; text-align: center}
+.usfm-s, .usfm-s1, .usfm-s2, .usfm-ms, .usfm-ms1 {font-weight: bold; margin-top: 1em}
+.usfm-r, .usfm-sr, .usfm-mr, .usfm-d {font-style: italic}
+.usfm-p, .usfm-m {margin: 0.5em 0}
+.usfm-p {text-indent: 1.5em}
+.usfm-q, .usfm-q1 {margin-left: 2em}
+.usfm-q2 {margin-left: 3em}
+.usfm-q3 {margin-left: 4em}
+.usfm-pi, .usfm-pi1 {margin-left: 1.5em}
+.usfm-add, .usfm-tl, .usfm-it {font-style: italic}
+.usfm-nd, .usfm-sc {font-variant: small-caps}
+.usfm-bd, .usfm-fr, .usfm-xo {font-weight: bold}
+.usfm-wj {color: #a00}
+.usfm-note-caller {font-size: 0.7em; vertical-align: super; text-decoration: none}
+.usfm-notes {font-size: 0.85em; border-top: 1px solid #ccc; margin-top: 2em; padding-top: 1em}
+";
+
+// Paragraphs that hold book metadata rather than text to display
+const HTML_SKIPPED_PARAS: [&str; 9] = ["h", "toc1", "toc2", "toc3", "toca1", "toca2", "toca3", "rem", "ide"];
+
+#[derive(Default)]
+struct HtmlRenderer {
+    book_code: String,
+    chapter: String,
+    chapters: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl HtmlRenderer {
+    fn render_content(&mut self, e: &UsfmElement, out: &mut String) {
+        for child in e.content.iter() {
+            self.render_node(child, out);
+        }
+    }
+
+    fn render_node(&mut self, node: &UsfmNode, out: &mut String) {
+        let e = match node {
+            UsfmNode::Text(t) => {
+                out.push_str(&xml_escape(t));
+                return;
+            }
+            UsfmNode::Element(e) => e,
+        };
+        if attribute(e, "eid").is_some() {
+            return;
+        }
+        let class_name = format!("usfm-{}", xml_escape(&e.marker));
+        match e.kind.as_str() {
+            "book" => {}
+            "chapter" => {
+                self.chapter = attribute(e, "number").unwrap_or("").to_string();
+                self.chapters.push(self.chapter.clone());
+                out.push_str(&format!(
+                    "\n<h2 class=\"usfm-c\" id=\"{}-{}\">{}</h2>",
+                    self.book_code,
+                    xml_escape(&self.chapter),
+                    xml_escape(&self.chapter)
+                ));
+            }
+            "verse" => {
+                let number = xml_escape(attribute(e, "number").unwrap_or(""));
+                out.push_str(&format!(
+                    "<sup class=\"usfm-v\" id=\"{}-{}-{}\">{}</sup>",
+                    self.book_code,
+                    xml_escape(&self.chapter),
+                    number,
+                    number
+                ));
+            }
+            "para" => {
+                if HTML_SKIPPED_PARAS.contains(&marker_base(&e.marker)) {
+                    return;
+                }
+                out.push_str(&format!("\n<div class=\"{}\">", class_name));
+                self.render_content(e, out);
+                out.push_str("</div>");
+            }
+            "table" => {
+                out.push_str("\n<table class=\"usfm-table\">");
+                self.render_content(e, out);
+                out.push_str("</table>");
+            }
+            "table:row" => {
+                out.push_str("<tr>");
+                self.render_content(e, out);
+                out.push_str("</tr>");
+            }
+            "table:cell" => {
+                let tag = if e.marker.starts_with("th") { "th" } else { "td" };
+                out.push_str(&format!("<{} class=\"{}\">", tag, class_name));
+                self.render_content(e, out);
+                out.push_str(&format!("</{}>", tag));
+            }
+            "note" => {
+                let note_number = self.notes.len() + 1;
+                let caller = match attribute(e, "caller") {
+                    Some("+") | None => note_number.to_string(),
+                    Some(c) => xml_escape(c),
+                };
+                let mut note_html = String::new();
+                self.render_content(e, &mut note_html);
+                if caller == "-" {
+                    out.push_str(&format!("<span class=\"{}\"> ({})</span>", class_name, note_html));
+                    return;
+                }
+                out.push_str(&format!(
+                    "<a class=\"usfm-note-caller\" href=\"#{}-note-{}\" id=\"{}-noteref-{}\">{}</a>",
+                    self.book_code, note_number, self.book_code, note_number, caller
+                ));
+                self.notes.push(format!(
+                    "<li class=\"{}\" id=\"{}-note-{}\"><a href=\"#{}-noteref-{}\">{}</a> {}</li>",
+                    class_name, self.book_code, note_number, self.book_code, note_number, caller, note_html
+                ));
+            }
+            "char" => {
+                out.push_str(&format!("<span class=\"{}\">", class_name));
+                self.render_content(e, out);
+                out.push_str("</span>");
+            }
+            _ => self.render_content(e, out),
+        }
+    }
+}
+
+/// A USFM book rendered as an HTML fragment, with footnotes collected at the end.
+pub(crate) struct HtmlBook {
+    pub(crate) book_code: String,
+    pub(crate) chapters: Vec<String>,
+    pub(crate) html: String,
+}
+
+/// Converts a USFM book to HTML, using `usfm-<marker>` classes and `<book>-<chapter>-<verse>` ids for verses.
+pub(crate) fn usfm_to_html(usfm: &str) -> HtmlBook {
+    let root = parse_usfm(usfm);
+    let mut renderer = HtmlRenderer::default();
+    for node in root.content.iter() {
+        if let UsfmNode::Element(e) = node {
+            if e.kind == "book" {
+                renderer.book_code = attribute(e, "code").unwrap_or("").to_string();
+            }
+        }
+    }
+    let mut html = String::new();
+    for node in root.content.iter() {
+        renderer.render_node(node, &mut html);
+    }
+    if !renderer.notes.is_empty() {
+        html.push_str(&format!("\n<ol class=\"usfm-notes\">\n{}\n</ol>", renderer.notes.join("\n")));
+    }
+    HtmlBook {
+        book_code: renderer.book_code,
+        chapters: renderer.chapters,
+        html,
+    }
+}
+
+// A rendered book with its display title
+struct HtmlExportBook {
+    title: String,
+    book: HtmlBook,
+}
+
+// Reads and renders the USFM ingredients, optionally only for some books, in canonical order
+fn html_export_books(repo_path: &String, book_codes: &Option<Vec<String>>) -> Result<Vec<HtmlExportBook>, PankosmiaError> {
+    let path_to_ingredients = format!("{}{}ingredients", repo_path, os_slash_str());
+    let mut books = vec![];
+    for entry in WalkDir::new(&path_to_ingredients).into_iter().filter_map(|e| e.ok()) {
+        if !entry.path().is_file() || entry.path().extension().and_then(|e| e.to_str()) != Some("usfm") {
+            continue;
+        }
+        let usfm = match std::fs::read_to_string(entry.path()) {
+            Ok(s) => s,
+            Err(e) => return Err(PankosmiaError(format!("Could not read {}: {}", entry.path().display(), e))),
+        };
+        let headers = usfm_book_headers(&usfm);
+        let book = usfm_to_html(&usfm);
+        if book.book_code.is_empty() {
+            continue;
+        }
+        if let Some(codes) = book_codes {
+            if !codes.contains(&book.book_code) {
+                continue;
+            }
+        }
+        books.push(HtmlExportBook {
+            title: headers.toc2.or(headers.h).unwrap_or(book.book_code.clone()),
+            book,
+        });
+    }
+    if books.is_empty() {
+        return Err(PankosmiaError("No USFM ingredients found".to_string()));
+    }
+    books.sort_by_key(|b| book_order_index(&b.book.book_code));
+    Ok(books)
+}
+
+// Font features are keyed by font name, and each font has a pankosmia-<font>.css webfont file
+fn font_css_links(typography: &Typography, webfonts_url: &str) -> String {
+    typography
+        .features
+        .keys()
+        .map(|font_name| {
+            format!(
+                "<link rel=\"stylesheet\" href=\"{}/pankosmia-{}.css\">",
+                webfonts_url,
+                xml_escape(font_name)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// The font set is a class name made of font family names, eg fonts-Pankosmia-CardoPankosmia-Gentium
+fn font_family_css(typography: &Typography) -> String {
+    let families: Vec<String> = typography
+        .font_set
+        .trim_start_matches("fonts-")
+        .split("Pankosmia-")
+        .filter(|f| !f.is_empty())
+        .map(|f| format!("\"Pankosmia-{}\"", f))
+        .collect();
+    format!(
+        "body {{font-family: {}serif; font-size: {}}}",
+        families.iter().map(|f| format!("{}, ", f)).collect::<String>(),
+        typography.size
+    )
+}
+
+fn html_page(title: &str, language: &str, typography: &Typography, webfonts_url: &str, extra_css: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"{}\" dir=\"{}\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{}</title>
+{}
+<style>
+{}{}
+{}
+</style>
+</head>
+<body class=\"{}\">
+{}
+</body>
+</html>
+",
+        xml_escape(language),
+        xml_escape(&typography.direction),
+        xml_escape(title),
+        font_css_links(typography, webfonts_url),
+        SITE_CSS,
+        font_family_css(typography),
+        extra_css,
+        xml_escape(&typography.font_set),
+        body
+    )
+}
+
+fn chapter_links(book: &HtmlBook) -> String {
+    book.chapters
+        .iter()
+        .map(|c| format!("<a href=\"#{}-{}\">{}</a>", book.book_code, xml_escape(c), xml_escape(c)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Name in the main language of the metadata, or in any language
+fn site_title(metadata: &Value, language: &str) -> String {
+    let names = &metadata["identification"]["name"];
+    names[language]
+        .as_str()
+        .or(names.as_object().and_then(|o| o.values().next()).and_then(|v| v.as_str()))
+        .unwrap_or("Scripture")
+        .to_string()
+}
+
+/// Writes a static site for the USFM books of a repo to the destination directory: an index page, a page per book and
+/// webfonts customized with the font features of the typography settings.
+pub(crate) fn write_html_site(
+    app_resources_dir: &String,
+    repo_path: &String,
+    metadata: &Value,
+    typography: &Typography,
+    destination: &String,
+) -> Result<(), PankosmiaError> {
+    let books = html_export_books(repo_path, &None)?;
+    let language = metadata_language_tag(metadata);
+    let title = site_title(metadata, &language);
+    let write_page = |file_name: &str, page: String| match std::fs::write(
+        format!("{}{}{}", destination, os_slash_str(), file_name),
+        page,
+    ) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(PankosmiaError(format!("Could not write {}: {}", file_name, e))),
+    };
+    // Index
+    let book_list = books
+        .iter()
+        .map(|b| format!("<li><a href=\"{}.html\">{}</a></li>", b.book.book_code, xml_escape(&b.title)))
+        .collect::<Vec<String>>()
+        .join("\n");
+    write_page(
+        "index.html",
+        html_page(
+            &title,
+            &language,
+            typography,
+            "webfonts",
+            "",
+            format!("<h1>{}</h1>\n<ul class=\"books\">\n{}\n</ul>", xml_escape(&title), book_list).as_str(),
+        ),
+    )?;
+    // Books, with links to neighbours
+    for (n, book) in books.iter().enumerate() {
+        let mut nav = vec![format!("<a href=\"index.html\">{}</a>", xml_escape(&title))];
+        if n > 0 {
+            nav.push(format!("<a href=\"{}.html\">&larr; {}</a>", books[n - 1].book.book_code, xml_escape(&books[n - 1].title)));
+        }
+        if n + 1 < books.len() {
+            nav.push(format!("<a href=\"{}.html\">{} &rarr;</a>", books[n + 1].book.book_code, xml_escape(&books[n + 1].title)));
+        }
+        let body = format!(
+            "<nav>{}</nav>\n<nav class=\"chapters\">{}</nav>\n<main>{}\n</main>",
+            nav.join(" "),
+            chapter_links(&book.book),
+            book.book.html
+        );
+        write_page(
+            format!("{}.html", book.book.book_code).as_str(),
+            html_page(&format!("{} - {}", book.title, title), &language, typography, "webfonts", "", &body),
+        )?;
+    }
+    // Webfonts
+    let source_webfonts_dir = source_webfonts_path(app_resources_dir);
+    let target_webfonts_dir = format!("{}{}webfonts", destination, os_slash_str());
+    if Path::new(&source_webfonts_dir).is_dir() {
+        if let Err(e) = copy_dir(&source_webfonts_dir, &target_webfonts_dir) {
+            return Err(PankosmiaError(format!("Could not copy webfonts: {}", e)));
+        }
+        for (font_name, font_features) in typography.features.iter() {
+            copy_and_customize_webfont_css2(&source_webfonts_dir, &target_webfonts_dir, font_features, font_name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns a single HTML page for the USFM books of a repo, using the webfonts served by the server.
+pub(crate) fn html_single_page(
+    repo_path: &String,
+    metadata: &Value,
+    typography: &Typography,
+    webfonts_url: &str,
+) -> Result<String, PankosmiaError> {
+    let books = html_export_books(repo_path, &None)?;
+    let language = metadata_language_tag(metadata);
+    let title = site_title(metadata, &language);
+    let book_links = books
+        .iter()
+        .map(|b| format!("<a href=\"#{}\">{}</a>", b.book.book_code, xml_escape(&b.title)))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let sections = books
+        .iter()
+        .map(|b| {
+            format!(
+                "<section id=\"{}\">\n<nav class=\"chapters\">{}</nav>{}\n</section>",
+                b.book.book_code,
+                chapter_links(&b.book),
+                b.book.html
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    Ok(html_page(
+        &title,
+        &language,
+        typography,
+        webfonts_url,
+        "",
+        format!("<h1>{}</h1>\n<nav>{}</nav>\n<main>\n{}\n</main>", xml_escape(&title), book_links, sections).as_str(),
+    ))
+}
+
+/// Options for paged media output, usually read from a print spec.
+pub(crate) struct PagedMediaOptions {
+    pub(crate) books: Option<Vec<String>>,
+    pub(crate) page_size: String,
+    pub(crate) page_margin: String,
+    pub(crate) columns: u8,
+}
+
+/// Returns a single HTML page for the USFM books of a repo with CSS for paged media, to be paginated by a browser or a paged media tool.
+pub(crate) fn html_paged_media_page(
+    repo_path: &String,
+    metadata: &Value,
+    typography: &Typography,
+    webfonts_url: &str,
+    options: &PagedMediaOptions,
+) -> Result<String, PankosmiaError> {
+    let books = html_export_books(repo_path, &options.books)?;
+    let language = metadata_language_tag(metadata);
+    let title = site_title(metadata, &language);
+    let sections = books
+        .iter()
+        .map(|b| {
+            format!(
+                "<section class=\"book\" id=\"{}\">\n<h1 class=\"running-title\">{}</h1>{}\n</section>",
+                b.book.book_code,
+                xml_escape(&b.title),
+                b.book.html
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let paged_css = format!(
+        "@page {{size: {}; margin: {}; @top-center {{content: string(book-title)}}; @bottom-center {{content: counter(page)}}}}
+body {{max-width: none; padding: 0}}
+main {{column-count: {}; column-gap: 1.5em}}
+.book {{break-before: page}}
+.running-title {{string-set: book-title content(); column-span: all; text-align: center}}
+.usfm-c {{break-after: avoid}}
+.usfm-s, .usfm-s1, .usfm-s2 {{break-after: avoid}}
+.usfm-notes {{column-span: all}}",
+        xml_escape(&options.page_size),
+        xml_escape(&options.page_margin),
+        options.columns.max(1)
+    );
+    Ok(html_page(
+        &title,
+        &language,
+        typography,
+        webfonts_url,
+        &paged_css,
+        format!("<main>\n{}\n</main>", sections).as_str(),
+    ))
+}
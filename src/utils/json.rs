@@ -11,3 +11,13 @@ pub(crate) fn get_string_value_by_key<'a>(value: &'a Value, key: &'a str) -> &'a
         }
     }
 }
+
+/// Replaces a placeholder in every string of a JSON value. Unlike replacing in JSON text, the replacement needs no escaping.
+pub(crate) fn replace_in_json_strings(value: &mut Value, placeholder: &str, replacement: &str) {
+    match value {
+        Value::String(s) => *s = s.replace(placeholder, replacement),
+        Value::Array(a) => a.iter_mut().for_each(|v| replace_in_json_strings(v, placeholder, replacement)),
+        Value::Object(o) => o.values_mut().for_each(|v| replace_in_json_strings(v, placeholder, replacement)),
+        _ => {}
+    }
+}
@@ -1,5 +1,5 @@
 use crate::endpoints;
-use crate::structs::{AppSettings, Client, ClientConfigSection, ProductSpec, ProjectIdentifier, SelectedWord};
+use crate::structs::{AppSettings, Client, ClientConfigSection, NavigationHistory, ProductSpec, ProjectIdentifier, SelectedWord};
 use crate::utils::paths::{os_slash_str, source_app_resources_path};
 use rocket::fs::FileServer;
 use rocket::{catchers, routes, Build, Rocket};
@@ -70,10 +70,58 @@ pub(crate) fn add_routes(rocket_instance: Rocket<Build>) -> Rocket<Build> {
                 endpoints::i18n2::used_languages::used_languages
             ],
         )
+        .mount("/api/search", routes![
+            endpoints::search::text_search::search
+        ])
         .mount("/api/navigation", routes![
             endpoints::navigation::get_bcv,
             endpoints::navigation::post_bcv,
-            endpoints::navigation::post_bcv_range
+            endpoints::navigation::post_bcv_range,
+            endpoints::navigation::parse_references,
+            endpoints::navigation::get_navigation_history,
+            endpoints::navigation::post_navigation_back,
+            endpoints::navigation::post_navigation_forward
+        ])
+        .mount("/api/print", routes![
+            endpoints::print::post_print_render,
+            endpoints::print::get_print_job,
+            endpoints::print::list_print_jobs
+        ])
+        .mount("/api/alignment", routes![
+            endpoints::alignment::get_verse_alignment,
+            endpoints::alignment::post_align,
+            endpoints::alignment::post_unalign,
+            endpoints::alignment::post_suggest_alignments,
+            endpoints::alignment::get_resolved_quote
+        ])
+        .mount("/api/glossary", routes![
+            endpoints::glossary::get_glossary_lookup,
+            endpoints::glossary::get_glossary_terms,
+            endpoints::glossary::post_glossary_term,
+            endpoints::glossary::post_approve_glossary_term,
+            endpoints::glossary::get_glossary_consistency
+        ])
+        .mount("/api/spelling", routes![
+            endpoints::spelling::get_wordlist,
+            endpoints::spelling::post_build_wordlist,
+            endpoints::spelling::post_mark_word,
+            endpoints::spelling::get_spelling_check
+        ])
+        .mount("/api/tcore", routes![
+            endpoints::tcore::post_generate_checks,
+            endpoints::tcore::get_check_items,
+            endpoints::tcore::post_check_item,
+            endpoints::tcore::get_checks_progress,
+            endpoints::tcore::get_checks_report
+        ])
+        .mount("/api/bookmarks", routes![
+            endpoints::bookmarks::list_bookmarks,
+            endpoints::bookmarks::get_bookmark,
+            endpoints::bookmarks::post_new_bookmark,
+            endpoints::bookmarks::post_update_bookmark,
+            endpoints::bookmarks::post_delete_bookmark,
+            endpoints::bookmarks::list_bookmark_tags,
+            endpoints::bookmarks::export_bookmarks
         ])
         .mount("/api/app-state", routes![
             endpoints::app_state::get_current_project,
@@ -96,6 +144,7 @@ pub(crate) fn add_routes(rocket_instance: Rocket<Build>) -> Rocket<Build> {
             "/api/git",
             routes![
                 endpoints::git2::new_text_translation::new_text_translation_repo,
+                endpoints::git2::import_text_translation::import_text_translation_repo,
                 endpoints::git2::new_audio_translation::new_audio_translation_repo,
                 endpoints::git2::new_bcv_resource::new_bcv_resource_repo,
                 endpoints::git2::new_obs_resource::new_obs_resource_repo,
@@ -131,6 +180,7 @@ pub(crate) fn add_routes(rocket_instance: Rocket<Build>) -> Rocket<Build> {
                 endpoints::content_utils2::content_template::content_template,
                 endpoints::content_utils2::list_versifications::list_versifications,
                 endpoints::content_utils2::versification::versification,
+                endpoints::content_utils2::map_reference::map_reference,
                 endpoints::content_utils2::product_content::product_content_catalog,
             ]
         )
@@ -138,6 +188,8 @@ pub(crate) fn add_routes(rocket_instance: Rocket<Build>) -> Rocket<Build> {
             "/api/burrito",
             routes![
                 endpoints::burrito2::raw_text_ingredient::raw_text_ingredient,
+                endpoints::burrito2::verses::verses,
+                endpoints::burrito2::post_verses::post_verses,
                 endpoints::burrito2::raw_text_ingredients::raw_text_ingredients,
                 endpoints::burrito2::raw_bytes_ingredient::raw_bytes_ingredient,
                 endpoints::burrito2::post_raw_ingredient::post_raw_ingredient,
@@ -149,13 +201,29 @@ pub(crate) fn add_routes(rocket_instance: Rocket<Build>) -> Rocket<Build> {
                 endpoints::burrito2::raw_metadata::raw_metadata,
                 endpoints::burrito2::summary_metadata::summary_metadata,
                 endpoints::burrito2::summary_metadatas::summary_metadatas,
+                endpoints::burrito2::summary_metadata_index::summary_metadata_index,
+                endpoints::burrito2::post_metadata_identification::post_metadata_identification,
+                endpoints::burrito2::post_metadata_languages::post_metadata_languages,
+                endpoints::burrito2::post_metadata_copyright::post_metadata_copyright,
+                endpoints::burrito2::post_metadata_localized_names::post_metadata_localized_names,
+                endpoints::burrito2::post_metadata_confidential::post_metadata_confidential,
                 endpoints::burrito2::get_repo_file_paths::get_repo_file_paths,
                 endpoints::burrito2::get_repo_file_info::get_repo_file_paths_info,
                 endpoints::burrito2::audit::audit,
+                endpoints::burrito2::repair::repair,
                 endpoints::burrito2::post_remake_ingredients_metadata::remake_ingredients_metadata,
+                endpoints::burrito2::post_remake_localized_names::remake_localized_names,
                 endpoints::burrito2::post_zipped_ingredient::post_zipped_ingredient,
                 endpoints::burrito2::get_zipped_ingredients::raw_zipped_ingredient,
                 endpoints::burrito2::get_zipped_repo::get_zipped_repo,
+                endpoints::burrito2::get_exported_usfm::get_exported_usfm,
+                endpoints::burrito2::post_exported_usfm_burrito::post_exported_usfm_burrito,
+                endpoints::burrito2::get_exported_html::get_exported_html,
+                endpoints::burrito2::plan_progress::plan_progress_report,
+                endpoints::burrito2::stats::stats,
+                endpoints::burrito2::post_transfer_ingredients::post_transfer_ingredients,
+                endpoints::burrito2::post_split_burrito::post_split_burrito,
+                endpoints::burrito2::post_merge_burritos::post_merge_burritos,
                 endpoints::burrito2::post_zipped_repo::post_zipped_repo,
                 endpoints::burrito2::remake_burrito_from_zip::remake_burrito_from_zip
 
@@ -282,7 +350,10 @@ pub(crate) fn add_app_settings(
             })),
             _ => Mutex::new(None),
         },
-
+        navigation_history: match serde_json::from_value(app_state_json["navigation_history"].clone()) {
+            Ok(h) => Mutex::new(h),
+            Err(_) => Mutex::new(NavigationHistory::default()),
+        },
         product: ProductSpec {
             name: product_json["name"].as_str().unwrap().to_string(),
             short_name: product_json["short_name"].as_str().unwrap().to_string(),
@@ -0,0 +1,77 @@
+use crate::structs::PankosmiaError;
+use crate::utils::paths::{check_path_string_components, os_slash_str};
+use git2::{Repository, RepositoryInitOptions};
+
+/// Returns the path of a new local repo with the given abbreviation, making its parent directories, or an error if the abbreviation
+/// is not a single safe path component or that repo already exists.
+pub(crate) fn new_local_repo_path(repo_dir: &str, abbr: &str) -> Result<String, PankosmiaError> {
+    if !check_path_string_components(abbr.to_string()) || abbr.contains('/') || abbr.contains('\\') {
+        return Err(PankosmiaError(format!("Bad content abbreviation '{}'", abbr)));
+    }
+    let path_to_new_repo_parent = format!(
+        "{}{}_local_{}_local_",
+        repo_dir,
+        os_slash_str(),
+        os_slash_str(),
+    );
+    let path_to_new_repo = format!("{}{}{}", path_to_new_repo_parent, os_slash_str(), abbr);
+    if std::path::Path::new(&path_to_new_repo).exists() {
+        return Err(PankosmiaError(format!("Local content called '{}' already exists", abbr)));
+    }
+    match std::fs::create_dir_all(path_to_new_repo_parent) {
+        Ok(_) => Ok(path_to_new_repo),
+        Err(e) => Err(PankosmiaError(format!("Could not create local content directories: {}", e))),
+    }
+}
+
+/// Initializes a git repo at the given path, with a main branch and local user info.
+pub(crate) fn init_local_repo(path_to_new_repo: &str) -> Result<Repository, PankosmiaError> {
+    let mut repo_options = RepositoryInitOptions::new();
+    let repo_options2 = repo_options.initial_head("main");
+    let new_repo = match Repository::init_opts(path_to_new_repo, repo_options2) {
+        Ok(repo) => repo,
+        Err(e) => return Err(PankosmiaError(format!("Could not create repo: {}", e))),
+    };
+    set_local_repo_user(&new_repo)?;
+    Ok(new_repo)
+}
+
+/// Sets the user name and email in the config of the repo, so that commits do not depend on any global git config.
+pub(crate) fn set_local_repo_user(repo: &Repository) -> Result<(), PankosmiaError> {
+    let mut config = match repo.config() {
+        Ok(c) => c,
+        Err(e) => return Err(PankosmiaError(format!("Could not read repo config: {}", e))),
+    };
+    let user_name = whoami::username();
+    if let Err(e) = config
+        .set_str("user.name", user_name.as_str())
+        .and_then(|_| config.set_str("user.email", format!("{}@localhost", user_name).as_str()))
+    {
+        return Err(PankosmiaError(format!("Could not set repo user: {}", e)));
+    }
+    Ok(())
+}
+
+/// Adds every file in the working tree of the repo and commits it on top of HEAD, if there is one.
+pub(crate) fn add_and_commit_all(repo: &Repository, message: &str) -> Result<(), PankosmiaError> {
+    let git_error = |e: git2::Error| PankosmiaError(format!("Could not commit to repo: {}", e));
+    let mut index = repo.index().map_err(git_error)?;
+    index
+        .add_all(["."], git2::IndexAddOption::DEFAULT, None)
+        .map_err(git_error)?;
+    index
+        .update_all(["."], None)
+        .map_err(git_error)?;
+    index.write().map_err(git_error)?;
+    let tree_id = index.write_tree().map_err(git_error)?;
+    let tree = repo.find_tree(tree_id).map_err(git_error)?;
+    let sig = repo.signature().map_err(git_error)?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit().map_err(git_error)?),
+        Err(_) => None,
+    };
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+        .map_err(git_error)?;
+    Ok(())
+}
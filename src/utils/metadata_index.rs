@@ -0,0 +1,177 @@
+use crate::structs::{MetadataSummary, PankosmiaError};
+use crate::utils::burrito::{bad_metadata_summary, summary_metadata_from_file};
+use crate::utils::files::write_file_atomically;
+use crate::utils::paths::{metadata_index_path, os_slash_str};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Mutex;
+
+// Orgs that hold repos which are not part of the user's resources
+const HIDDEN_ORGS: [&str; 3] = ["_local_/_quarantine_", "_local_/_archive_", "_local_/_updates_"];
+
+// Serializes read-modify-write of the metadata index
+static METADATA_INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct MetadataIndexEntry {
+    pub(crate) metadata_modified: u64,
+    pub(crate) git_head: Option<String>,
+    pub(crate) summary: MetadataSummary,
+}
+
+fn visible_subdirs(dir_path: &String) -> Vec<String> {
+    let mut subdirs = vec![];
+    if let Ok(entries) = fs::read_dir(dir_path) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let leaf = entry.file_name().to_string_lossy().to_string();
+            if leaf.starts_with(".") || !entry.path().is_dir() {
+                continue;
+            }
+            subdirs.push(leaf);
+        }
+    }
+    subdirs.sort();
+    subdirs
+}
+
+/// Returns `<server>/<org>/<repo>` paths for every local repo, optionally only for one org.
+pub(crate) fn local_repo_paths(repo_dir: &String, org: &Option<String>) -> Vec<String> {
+    let mut repo_paths = vec![];
+    for server in visible_subdirs(repo_dir) {
+        let server_path = format!("{}{}{}", repo_dir, os_slash_str(), server);
+        for org_leaf in visible_subdirs(&server_path) {
+            let server_org = format!("{}/{}", server, org_leaf);
+            match org {
+                Some(o) => {
+                    if *o != server_org {
+                        continue;
+                    }
+                }
+                None => {
+                    if HIDDEN_ORGS.contains(&server_org.as_str()) {
+                        continue;
+                    }
+                }
+            }
+            let org_path = format!("{}{}{}", server_path, os_slash_str(), org_leaf);
+            for repo in visible_subdirs(&org_path) {
+                repo_paths.push(format!("{}/{}", server_org, repo));
+            }
+        }
+    }
+    repo_paths
+}
+
+fn metadata_modified(metadata_path: &String) -> u64 {
+    match fs::metadata(metadata_path).and_then(|m| m.modified()) {
+        Ok(t) => match t.duration_since(std::time::SystemTime::UNIX_EPOCH) {
+            Ok(d) => d.as_nanos() as u64,
+            Err(_) => 0,
+        },
+        Err(_) => 0,
+    }
+}
+
+// Reads HEAD from the .git directory, which is much cheaper than opening every repo on every request
+fn git_head(full_repo_path: &String) -> Option<String> {
+    let git_dir = format!("{}{}.git", full_repo_path, os_slash_str());
+    let head = fs::read_to_string(format!("{}{}HEAD", git_dir, os_slash_str())).ok()?;
+    let ref_name = match head.trim().strip_prefix("ref: ") {
+        Some(r) => r.to_string(),
+        // Detached HEAD
+        None => return Some(head.trim().to_string()),
+    };
+    if let Ok(oid) = fs::read_to_string(format!("{}{}{}", git_dir, os_slash_str(), ref_name)) {
+        return Some(oid.trim().to_string());
+    }
+    let packed_refs = fs::read_to_string(format!("{}{}packed-refs", git_dir, os_slash_str())).ok()?;
+    packed_refs.lines().find_map(|line| match line.split_once(' ') {
+        Some((oid, name)) if name == ref_name => Some(oid.to_string()),
+        _ => None,
+    })
+}
+
+fn read_metadata_index(working_dir: &String) -> BTreeMap<String, MetadataIndexEntry> {
+    match fs::read_to_string(metadata_index_path(working_dir)) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// Returns index entries for the local repos, rebuilding entries whose metadata.json or git HEAD have changed.
+/// The index is persisted in the working dir, so only stale entries are reparsed.
+pub(crate) fn metadata_index(
+    working_dir: &String,
+    repo_dir: &String,
+    org: &Option<String>,
+) -> Result<BTreeMap<String, MetadataIndexEntry>, PankosmiaError> {
+    let _lock = METADATA_INDEX_LOCK.lock().unwrap();
+    let mut stored_index = read_metadata_index(working_dir);
+    let mut changed = false;
+    let mut index = BTreeMap::new();
+    for repo_path in local_repo_paths(repo_dir, org) {
+        let full_repo_path = format!("{}{}{}", repo_dir, os_slash_str(), repo_path);
+        let metadata_path = format!("{}{}metadata.json", full_repo_path, os_slash_str());
+        let modified = metadata_modified(&metadata_path);
+        let head = git_head(&full_repo_path);
+        let entry = match stored_index.get(&repo_path) {
+            Some(e) if e.metadata_modified == modified && e.git_head == head => e.clone(),
+            _ => {
+                changed = true;
+                let new_entry = MetadataIndexEntry {
+                    metadata_modified: modified,
+                    git_head: head,
+                    summary: summary_metadata_from_file(metadata_path, &[])
+                        .unwrap_or_else(|_| bad_metadata_summary()),
+                };
+                stored_index.insert(repo_path.clone(), new_entry.clone());
+                new_entry
+            }
+        };
+        index.insert(repo_path, entry);
+    }
+    // Forget repos that no longer exist, but only when every org was walked
+    if org.is_none() {
+        let before = stored_index.len();
+        stored_index.retain(|k, _| {
+            index.contains_key(k) || HIDDEN_ORGS.iter().any(|o| k.starts_with(&format!("{}/", o)))
+        });
+        changed = changed || stored_index.len() != before;
+    }
+    if changed {
+        let index_string = match serde_json::to_string(&stored_index) {
+            Ok(s) => s,
+            Err(e) => return Err(PankosmiaError(format!("Could not make metadata index as JSON: {}", e))),
+        };
+        if let Err(e) = write_file_atomically(&metadata_index_path(working_dir), &index_string) {
+            return Err(PankosmiaError(format!("Could not write metadata index: {}", e)));
+        }
+    }
+    Ok(index)
+}
+
+/// Filters on flavor, language code and book code. Each filter is ignored when None.
+pub(crate) fn summary_matches(
+    summary: &MetadataSummary,
+    flavor: &Option<String>,
+    language: &Option<String>,
+    book: &Option<String>,
+) -> bool {
+    if let Some(f) = flavor {
+        if summary.flavor != *f && summary.flavor_type != *f {
+            return false;
+        }
+    }
+    if let Some(l) = language {
+        if summary.language_code != *l {
+            return false;
+        }
+    }
+    if let Some(b) = book {
+        if !summary.book_codes.contains(&b.to_uppercase()) {
+            return false;
+        }
+    }
+    true
+}
@@ -13,3 +13,23 @@ pub(crate) mod burrito_api;
 pub(crate) mod time;
 pub(crate) mod bcv_ref;
 pub(crate) mod zip;
+pub(crate) mod usfm;
+pub(crate) mod metadata_index;
+pub(crate) mod search_index;
+pub(crate) mod versification;
+pub(crate) mod references;
+pub(crate) mod usj;
+pub(crate) mod local_repo;
+pub(crate) mod html_export;
+pub(crate) mod print;
+pub(crate) mod plan_progress;
+pub(crate) mod burrito_stats;
+pub(crate) mod alignment;
+pub(crate) mod glossary;
+pub(crate) mod wordlist;
+pub(crate) mod tsv;
+pub(crate) mod tcore_checks;
+pub(crate) mod burrito_transfer;
+
+#[cfg(test)]
+mod tests;
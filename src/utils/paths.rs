@@ -1,5 +1,5 @@
 use std::env;
-use std::path::{Components};
+use std::path::{Components, Path};
 use home::home_dir;
 
 pub(crate) fn os_slash_str() -> &'static str {
@@ -49,6 +49,16 @@ pub(crate) fn check_path_components(path_components: &mut Components<'_>) -> boo
     check_path_components1(path_components, 3)
 }
 
+/// Returns the full path of a repo below the repo dir, if the repo path is safe and is a directory.
+pub(crate) fn full_repo_path(repo_dir: &String, repo_path: &Path) -> Option<String> {
+    let full_path = format!("{}{}{}", repo_dir, os_slash_str(), repo_path.display());
+    if check_path_components(&mut repo_path.components()) && Path::new(&full_path).is_dir() {
+        Some(full_path)
+    } else {
+        None
+    }
+}
+
 pub(crate) fn check_path_components1(path_components: &mut Components<'_>, min_length: usize) -> bool {
     let mut ret = true;
     if path_components.clone().collect::<Vec<_>>().len() < min_length {
@@ -170,4 +180,16 @@ pub(crate) fn source_webfonts_path(app_resources_dir: &String) -> String {
 
 pub(crate) fn source_app_resources_path(app_resources_dir: &String) -> String {
     format!("{}/app_resources/", app_resources_dir)
-}
\ No newline at end of file
+}
+
+pub(crate) fn metadata_index_path (working_dir: &String) -> String {
+    format!("{}/metadata_index.json", working_dir)
+}
+
+pub(crate) fn search_index_path (working_dir: &String) -> String {
+    format!("{}/search_index", working_dir)
+}
+
+pub(crate) fn bookmarks_path (working_dir: &String) -> String {
+    format!("{}/bookmarks.json", working_dir)
+}
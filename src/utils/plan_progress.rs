@@ -0,0 +1,302 @@
+use crate::structs::PankosmiaError;
+use crate::utils::bcv_ref::book_order_index;
+use crate::utils::burrito_api::checks::audit_report;
+use crate::utils::paths::os_slash_str;
+use crate::utils::usfm::{usfm_book_headers, usfm_verse_content, UsfmVerseContent};
+use crate::utils::versification::{book_max_verses, repo_max_verses};
+use git2::Repository;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use walkdir::WalkDir;
+
+#[derive(Serialize)]
+pub(crate) struct ChapterProgress {
+    chapter: u16,
+    expected: usize,
+    drafted: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BookProgress {
+    book_code: String,
+    expected: usize,
+    drafted: usize,
+    percent: f64,
+    chapters: Vec<ChapterProgress>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SectionProgress {
+    book_code: String,
+    start: String,
+    end: String,
+    expected: usize,
+    drafted: usize,
+    percent: f64,
+    due: Option<String>,
+    overdue: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct StageProgress {
+    name: String,
+    commits: usize,
+    last_commit: Option<i64>,
+    due: Option<String>,
+    overdue: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct AuditStatus {
+    success: bool,
+    failed_checks: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct PlanProgress {
+    translation_repo_path: String,
+    expected: usize,
+    drafted: usize,
+    percent: f64,
+    books: Vec<BookProgress>,
+    sections: Vec<SectionProgress>,
+    stages: Vec<StageProgress>,
+    audit: AuditStatus,
+    overdue: Vec<String>,
+}
+
+fn percent(drafted: usize, expected: usize) -> f64 {
+    if expected == 0 {
+        return 0.0;
+    }
+    ((drafted.min(expected) as f64 / expected as f64) * 1000.0).round() / 10.0
+}
+
+// Names may be strings or localized objects
+fn plan_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(o) => o
+            .get("en")
+            .or(o.values().next())
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        _ => None,
+    }
+}
+
+// Dates are compared as YYYY-MM-DD
+fn is_overdue(due: &Option<String>, today: &str, complete: bool) -> bool {
+    match due {
+        Some(d) => !complete && d.get(..10).is_some_and(|date| date < today),
+        None => false,
+    }
+}
+
+fn parse_cv(cv: &str) -> Option<(u16, u16)> {
+    let (c, v) = cv.split_once(":")?;
+    Some((c.trim().parse().ok()?, v.trim().parse().ok()?))
+}
+
+/// Returns the repo path of the textTranslation linked to a translation plan, as `repo_path` or `translation`.
+pub(crate) fn plan_translation_repo_path(plan: &Value) -> Option<String> {
+    plan["repo_path"]
+        .as_str()
+        .or(plan["translation"].as_str())
+        .map(|s| s.to_string())
+}
+
+// Reads the USFM of every book in the translation, by book code
+fn translation_verse_content(translation_path: &String) -> BTreeMap<String, UsfmVerseContent> {
+    let mut books = BTreeMap::new();
+    let path_to_ingredients = format!("{}{}ingredients", translation_path, os_slash_str());
+    for entry in WalkDir::new(&path_to_ingredients).into_iter().filter_map(|e| e.ok()) {
+        if !entry.path().is_file() || entry.path().extension().and_then(|e| e.to_str()) != Some("usfm") {
+            continue;
+        }
+        if let Ok(usfm) = std::fs::read_to_string(entry.path()) {
+            if let Some(book_code) = usfm_book_headers(&usfm).book_code {
+                books.insert(book_code, usfm_verse_content(&usfm));
+            }
+        }
+    }
+    books
+}
+
+// Counts commits whose message mentions each stage name, with the time of the latest one
+fn stage_commits(translation_path: &String, stage_names: &[String]) -> Vec<(usize, Option<i64>)> {
+    let mut counts = vec![(0, None); stage_names.len()];
+    let repo = match Repository::open(translation_path) {
+        Ok(r) => r,
+        Err(_) => return counts,
+    };
+    let mut revwalk = match repo.revwalk() {
+        Ok(r) => r,
+        Err(_) => return counts,
+    };
+    if revwalk.push_head().is_err() {
+        return counts;
+    }
+    for commit_id in revwalk.filter_map(|r| r.ok()) {
+        let commit = match repo.find_commit(commit_id) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        let message = commit.message().unwrap_or("").to_lowercase();
+        for (n, stage_name) in stage_names.iter().enumerate() {
+            if !stage_name.is_empty() && message.contains(&stage_name.to_lowercase()) {
+                counts[n].0 += 1;
+                let commit_time = commit.time().seconds();
+                if counts[n].1.is_none_or(|t| commit_time > t) {
+                    counts[n].1 = Some(commit_time);
+                }
+            }
+        }
+    }
+    counts
+}
+
+/// Compares a translation plan with the state of its textTranslation repo. The plan may contain:
+/// - `sections`, each with `bookCode` (or `book`), `cv` as `["1:1", "1:16"]` (or `start` and `end`) and an optional `due` date
+/// - `books`, used when there are no sections, defaulting to the books of the translation
+/// - `stages`, each with `name` and an optional `due` date. Commits are counted for a stage when their message contains its name.
+pub(crate) fn plan_progress(
+    app_resources_dir: &String,
+    plan: &Value,
+    repo_dir: &String,
+    translation_repo_path: &String,
+    today: &str,
+) -> Result<PlanProgress, PankosmiaError> {
+    let translation_path = format!("{}{}{}", repo_dir, os_slash_str(), translation_repo_path);
+    if !std::path::Path::new(&translation_path).is_dir() {
+        return Err(PankosmiaError(format!("Translation repo '{}' not found", translation_repo_path)));
+    }
+    let max_verses = repo_max_verses(&translation_path);
+    let verse_content = translation_verse_content(&translation_path);
+    let mut overdue = vec![];
+    // Sections
+    let mut sections = vec![];
+    for section in plan["sections"].as_array().unwrap_or(&vec![]) {
+        let book_code = match section["bookCode"].as_str().or(section["book"].as_str()) {
+            Some(b) => b.to_uppercase(),
+            None => continue,
+        };
+        let (start, end) = match (
+            section["cv"][0].as_str().or(section["start"].as_str()),
+            section["cv"][1].as_str().or(section["end"].as_str()),
+        ) {
+            (Some(s), Some(e)) => (s.to_string(), e.to_string()),
+            _ => continue,
+        };
+        let ((start_chapter, start_verse), (end_chapter, end_verse)) = match (parse_cv(&start), parse_cv(&end)) {
+            (Some(s), Some(e)) => (s, e),
+            _ => continue,
+        };
+        let chapter_counts = book_max_verses(&max_verses, &book_code);
+        let (mut expected, mut drafted) = (0, 0);
+        for chapter in start_chapter..=end_chapter {
+            let first = if chapter == start_chapter { start_verse.max(1) } else { 1 };
+            let last = if chapter == end_chapter {
+                end_verse
+            } else {
+                chapter_counts.get((chapter as usize).saturating_sub(1)).copied().unwrap_or(0)
+            };
+            for verse in first..=last {
+                expected += 1;
+                if verse_content
+                    .get(&book_code)
+                    .and_then(|c| c.drafted.get(&chapter))
+                    .is_some_and(|verses| verses.contains(&verse))
+                {
+                    drafted += 1;
+                }
+            }
+        }
+        let due = section["due"].as_str().map(|s| s.to_string());
+        let section_overdue = is_overdue(&due, today, expected > 0 && drafted >= expected);
+        if section_overdue {
+            overdue.push(format!("{} {}-{}", book_code, start, end));
+        }
+        sections.push(SectionProgress {
+            book_code,
+            start,
+            end,
+            expected,
+            drafted,
+            percent: percent(drafted, expected),
+            due,
+            overdue: section_overdue,
+        });
+    }
+    // Books
+    let mut book_codes: Vec<String> = if !sections.is_empty() {
+        sections.iter().map(|s| s.book_code.clone()).collect()
+    } else if let Some(plan_books) = plan["books"].as_array() {
+        plan_books.iter().filter_map(|b| b.as_str().map(|s| s.to_uppercase())).collect()
+    } else {
+        verse_content.keys().cloned().collect()
+    };
+    book_codes.sort_by_key(|b| book_order_index(b));
+    book_codes.dedup();
+    let mut books = vec![];
+    for book_code in book_codes {
+        let chapter_counts = book_max_verses(&max_verses, &book_code);
+        let content = verse_content.get(&book_code);
+        let chapters: Vec<ChapterProgress> = chapter_counts
+            .iter()
+            .enumerate()
+            .map(|(n, expected)| {
+                let chapter = (n + 1) as u16;
+                let drafted = content
+                    .and_then(|c| c.drafted.get(&chapter))
+                    .map_or(0, |verses| verses.iter().filter(|v| **v <= *expected).count());
+                ChapterProgress { chapter, expected: *expected as usize, drafted }
+            })
+            .collect();
+        let expected = chapters.iter().map(|c| c.expected).sum();
+        let drafted = chapters.iter().map(|c| c.drafted).sum();
+        books.push(BookProgress {
+            book_code,
+            expected,
+            drafted,
+            percent: percent(drafted, expected),
+            chapters,
+        });
+    }
+    // Stages
+    let stage_values = plan["stages"].as_array().cloned().unwrap_or(vec![]);
+    let stage_names: Vec<String> = stage_values
+        .iter()
+        .map(|s| plan_name(&s["name"]).or(plan_name(s)).unwrap_or("".to_string()))
+        .collect();
+    let commit_counts = stage_commits(&translation_path, &stage_names);
+    let mut stages = vec![];
+    for ((stage, name), (commits, last_commit)) in stage_values.iter().zip(stage_names).zip(commit_counts) {
+        let due = stage["due"].as_str().map(|s| s.to_string());
+        let stage_overdue = is_overdue(&due, today, commits > 0);
+        if stage_overdue {
+            overdue.push(format!("stage {}", name));
+        }
+        stages.push(StageProgress { name, commits, last_commit, due, overdue: stage_overdue });
+    }
+    // Audit
+    let failed_checks: Vec<String> = audit_report(app_resources_dir, translation_path.clone())
+        .into_iter()
+        .filter(|r| !r.success)
+        .map(|r| r.name)
+        .collect();
+    let expected = books.iter().map(|b| b.expected).sum();
+    let drafted = books.iter().map(|b| b.drafted).sum();
+    Ok(PlanProgress {
+        translation_repo_path: translation_repo_path.clone(),
+        expected,
+        drafted,
+        percent: percent(drafted, expected),
+        books,
+        sections,
+        stages,
+        audit: AuditStatus { success: failed_checks.is_empty(), failed_checks },
+        overdue,
+    })
+}
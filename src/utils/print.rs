@@ -0,0 +1,341 @@
+use crate::structs::{PankosmiaError, PrintJob, Typography};
+use crate::utils::bcv_ref::book_order_index;
+use crate::utils::burrito::{metadata_language_tag, rewrite_ingredients_metadata};
+use crate::utils::html_export::{html_paged_media_page, PagedMediaOptions};
+use crate::utils::paths::os_slash_str;
+use crate::utils::time::utc_now_timestamp_string;
+use crate::utils::usfm::usfm_book_headers;
+use crate::utils::usj::{attribute, marker_base, parse_usfm, UsfmNode};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, Mutex};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+/// Print jobs for this session, by id.
+pub(crate) static PRINT_JOBS: Mutex<BTreeMap<String, PrintJob>> = Mutex::new(BTreeMap::new());
+
+// Page options and language tags are written into CSS and ConTeXt source, so they are limited to these patterns
+static PAGE_SIZE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z0-9]{1,16}$").unwrap());
+static PAGE_MARGIN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{1,3}(\.\d{1,2})?(mm|cm|in|pt)$").unwrap());
+static LANGUAGE_TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z]{2,3}([-_][A-Za-z0-9]{1,8})*$").unwrap());
+
+/// Everything a print job needs, so that it can run without access to Rocket state.
+pub(crate) struct PrintRequest {
+    pub(crate) app_resources_dir: String,
+    pub(crate) repo_dir: String,
+    pub(crate) working_dir: String,
+    pub(crate) repo_path: String,
+    pub(crate) source_repo_path: String,
+    pub(crate) format: String,
+    pub(crate) destination: String,
+    pub(crate) spec: Value,
+    pub(crate) typography: Typography,
+}
+
+/// Reads the spec of an x-printspec repo from `ingredients/plan.json`, where new print spec repos are written,
+/// or else from `ingredients/spec.json`, which is the ingredient name in their metadata.
+pub(crate) fn read_print_spec(path_to_repo: &String) -> Result<Value, PankosmiaError> {
+    for spec_name in ["plan.json", "spec.json"] {
+        let path_to_spec = format!("{}{}ingredients{}{}", path_to_repo, os_slash_str(), os_slash_str(), spec_name);
+        if let Ok(spec_string) = std::fs::read_to_string(&path_to_spec) {
+            return match serde_json::from_str(&spec_string) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(PankosmiaError(format!("Could not parse print spec: {}", e))),
+            };
+        }
+    }
+    Err(PankosmiaError("No print spec found in repo".to_string()))
+}
+
+/// Returns the repo path of the scripture burrito named by a print spec, as `source` or `repo_path`.
+pub(crate) fn print_spec_source(spec: &Value) -> Option<String> {
+    spec["source"]
+        .as_str()
+        .or(spec["repo_path"].as_str())
+        .map(|s| s.to_string())
+}
+
+fn paged_media_options(spec: &Value) -> Result<PagedMediaOptions, PankosmiaError> {
+    let options = PagedMediaOptions {
+        books: spec["books"].as_array().map(|books| {
+            books
+                .iter()
+                .filter_map(|b| b.as_str().map(|s| s.to_uppercase()))
+                .collect()
+        }),
+        page_size: spec["page"]["size"]
+            .as_str()
+            .or(spec["pageSize"].as_str())
+            .unwrap_or("A5")
+            .to_string(),
+        page_margin: spec["page"]["margin"]
+            .as_str()
+            .or(spec["pageMargin"].as_str())
+            .unwrap_or("15mm")
+            .to_string(),
+        columns: spec["columns"].as_u64().unwrap_or(1).clamp(1, 4) as u8,
+    };
+    if !PAGE_SIZE_RE.is_match(&options.page_size) {
+        return Err(PankosmiaError(format!("Unsupported page size '{}'", options.page_size)));
+    }
+    if !PAGE_MARGIN_RE.is_match(&options.page_margin) {
+        return Err(PankosmiaError(format!("Unsupported page margin '{}'", options.page_margin)));
+    }
+    Ok(options)
+}
+
+fn context_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\backslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '^' => escaped.push_str("\\letterhat{}"),
+            '~' => escaped.push_str("\\lettertilde{}"),
+            '|' => escaped.push_str("\\letterbar{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_context_node(node: &UsfmNode, out: &mut String) {
+    let e = match node {
+        UsfmNode::Text(t) => {
+            out.push_str(&context_escape(t));
+            return;
+        }
+        UsfmNode::Element(e) => e,
+    };
+    if attribute(e, "eid").is_some() {
+        return;
+    }
+    let mut content = String::new();
+    for child in e.content.iter() {
+        write_context_node(child, &mut content);
+    }
+    match e.kind.as_str() {
+        "chapter" => out.push_str(&format!(
+            "\n\n\\ScriptureChapter{{{}}}",
+            context_escape(attribute(e, "number").unwrap_or(""))
+        )),
+        "verse" => out.push_str(&format!(
+            "\\ScriptureVerse{{{}}}",
+            context_escape(attribute(e, "number").unwrap_or(""))
+        )),
+        "para" => match marker_base(&e.marker) {
+            "h" | "toc" | "toca" | "rem" | "ide" => {}
+            "mt" => out.push_str(&format!("\n\n\\ScriptureTitle{{{}}}", content)),
+            "s" | "ms" | "mr" | "sr" | "r" | "d" | "sp" | "cl" => {
+                out.push_str(&format!("\n\n\\ScriptureHeading{{{}}}", content))
+            }
+            "q" | "qm" | "pi" => out.push_str(&format!(
+                "\n\n\\startScriptureIndent\n{}\n\\stopScriptureIndent",
+                content
+            )),
+            _ => out.push_str(&format!("\n\n{}", content)),
+        },
+        "table" => out.push_str(&format!("\n\n{}", content)),
+        "table:row" => out.push_str(&format!("{}\\par\n", content)),
+        "table:cell" => out.push_str(&format!("{}\\quad ", content)),
+        "note" => out.push_str(&format!("\\footnote{{{}}}", content.trim())),
+        "char" => match e.marker.as_str() {
+            "add" | "it" | "tl" | "em" | "fq" | "fqa" | "qac" => out.push_str(&format!("{{\\em {}}}", content)),
+            "bd" | "fr" | "xo" | "k" => out.push_str(&format!("{{\\bf {}}}", content)),
+            "bdit" => out.push_str(&format!("{{\\bi {}}}", content)),
+            "nd" | "sc" => out.push_str(&format!("{{\\sc {}}}", content)),
+            "sup" => out.push_str(&format!("\\high{{{}}}", content)),
+            _ => out.push_str(&content),
+        },
+        "book" | "ms" => {}
+        _ => out.push_str(&content),
+    }
+}
+
+/// Converts a USFM book to ConTeXt source, using `\ScriptureChapter`, `\ScriptureVerse`, `\ScriptureTitle`, `\ScriptureHeading`
+/// and `ScriptureIndent`, which must be defined by the enclosing document.
+pub(crate) fn usfm_to_context(usfm: &str) -> String {
+    let root = parse_usfm(usfm);
+    let mut out = String::new();
+    for node in root.content.iter() {
+        write_context_node(node, &mut out);
+    }
+    out.trim().to_string() + "\n"
+}
+
+// A ConTeXt document wrapping the books, with the macros used by usfm_to_context
+fn context_document(source_path: &String, metadata: &Value, options: &PagedMediaOptions) -> Result<String, PankosmiaError> {
+    let path_to_ingredients = format!("{}{}ingredients", source_path, os_slash_str());
+    let mut books = vec![];
+    for entry in WalkDir::new(&path_to_ingredients).into_iter().filter_map(|e| e.ok()) {
+        if !entry.path().is_file() || entry.path().extension().and_then(|e| e.to_str()) != Some("usfm") {
+            continue;
+        }
+        let usfm = match std::fs::read_to_string(entry.path()) {
+            Ok(s) => s,
+            Err(e) => return Err(PankosmiaError(format!("Could not read {}: {}", entry.path().display(), e))),
+        };
+        let book_code = match usfm_book_headers(&usfm).book_code {
+            Some(c) => c,
+            None => continue,
+        };
+        if let Some(codes) = &options.books {
+            if !codes.contains(&book_code) {
+                continue;
+            }
+        }
+        books.push((book_code, usfm_to_context(&usfm)));
+    }
+    if books.is_empty() {
+        return Err(PankosmiaError("No USFM ingredients found to print".to_string()));
+    }
+    books.sort_by_key(|(code, _)| book_order_index(code));
+    let language = metadata_language_tag(metadata);
+    if !LANGUAGE_TAG_RE.is_match(&language) {
+        return Err(PankosmiaError(format!("Unsupported language tag '{}'", language)));
+    }
+    let body = books
+        .iter()
+        .map(|(_, tex)| tex.clone())
+        .collect::<Vec<String>>()
+        .join("\n\\page\n");
+    let (start_columns, stop_columns) = if options.columns > 1 {
+        (format!("\\startcolumns[n={}]\n", options.columns), "\n\\stopcolumns".to_string())
+    } else {
+        ("".to_string(), "".to_string())
+    };
+    Ok(format!(
+        "\\mainlanguage[{}]
+\\setuppapersize[{}]
+\\setuplayout[backspace={}, topspace={}, width=middle, height=middle, header=0pt, footer=2em]
+\\setuppagenumbering[location=footer]
+\\define[1]\\ScriptureChapter{{\\blank[big]{{\\bfc #1}}\\blank}}
+\\define[1]\\ScriptureVerse{{\\high{{#1}}\\,}}
+\\define[1]\\ScriptureTitle{{\\startalignment[middle]{{\\bfd #1}}\\stopalignment\\blank[big]}}
+\\define[1]\\ScriptureHeading{{\\blank{{\\bf #1}}\\blank}}
+\\definestartstop[ScriptureIndent][before={{\\startnarrower[left]}}, after={{\\stopnarrower}}]
+\\starttext
+{}{}{}
+\\stoptext
+",
+        language, options.page_size, options.page_margin, options.page_margin, start_columns, body, stop_columns
+    ))
+}
+
+/// Renders the source burrito of a print request, then writes it to the temp dir or to the output directory of the print spec repo.
+/// Returns the temp file id or the ingredient path of the output.
+pub(crate) fn render_print(request: &PrintRequest) -> Result<String, PankosmiaError> {
+    let source_path = format!("{}{}{}", request.repo_dir, os_slash_str(), request.source_repo_path);
+    let source_metadata: Value = match std::fs::read_to_string(format!("{}{}metadata.json", source_path, os_slash_str()))
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(e) => return Err(PankosmiaError(format!("Could not read source metadata: {}", e))),
+    };
+    let options = paged_media_options(&request.spec)?;
+    let (output, extension) = match request.format.as_str() {
+        "html" => (
+            html_paged_media_page(&source_path, &source_metadata, &request.typography, "/api/webfonts", &options)?,
+            "html",
+        ),
+        "context" => (context_document(&source_path, &source_metadata, &options)?, "tex"),
+        other => return Err(PankosmiaError(format!("Unknown print format '{}'", other))),
+    };
+    match request.destination.as_str() {
+        "temp" => {
+            let temp_id = Uuid::new_v4().to_string();
+            let path_to_temp = format!("{}{}temp{}{}", request.working_dir, os_slash_str(), os_slash_str(), temp_id);
+            match std::fs::write(path_to_temp, output) {
+                Ok(_) => Ok(temp_id),
+                Err(e) => Err(PankosmiaError(format!("Could not write temp file: {}", e))),
+            }
+        }
+        "repo" => {
+            let path_to_repo = format!("{}{}{}", request.repo_dir, os_slash_str(), request.repo_path);
+            let path_to_output_dir = format!("{}{}ingredients{}output", path_to_repo, os_slash_str(), os_slash_str());
+            if let Err(e) = std::fs::create_dir_all(&path_to_output_dir) {
+                return Err(PankosmiaError(format!("Could not create output directory: {}", e)));
+            }
+            let output_name = format!("print.{}", extension);
+            if let Err(e) = std::fs::write(format!("{}{}{}", path_to_output_dir, os_slash_str(), output_name), output) {
+                return Err(PankosmiaError(format!("Could not write print output: {}", e)));
+            }
+            rewrite_ingredients_metadata(request.app_resources_dir.clone(), path_to_repo)?;
+            Ok(format!("output/{}", output_name))
+        }
+        other => Err(PankosmiaError(format!("Unknown print destination '{}'", other))),
+    }
+}
+
+// The message of a panic payload, which is usually a &str or a String
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(s), _) => s.to_string(),
+        (_, Some(s)) => s.clone(),
+        _ => "unknown error".to_string(),
+    }
+}
+
+// Finished jobs that are never fetched are dropped after this long
+const PRINT_JOB_TTL_HOURS: i64 = 24;
+
+// Removes finished jobs older than PRINT_JOB_TTL_HOURS
+fn prune_print_jobs(jobs: &mut BTreeMap<String, PrintJob>) {
+    let cutoff = Utc::now() - Duration::hours(PRINT_JOB_TTL_HOURS);
+    jobs.retain(|_, job| match &job.finished {
+        Some(finished) => DateTime::parse_from_rfc3339(finished).is_ok_and(|f| f >= cutoff),
+        None => true,
+    });
+}
+
+/// Records a new print job, then renders it on a separate thread. Returns the job id.
+/// A panic while rendering marks the job as failed, so that it is never left running.
+/// Finished jobs are removed when they are fetched, or after a day if they never are.
+pub(crate) fn start_print_job(request: PrintRequest) -> String {
+    let job_id = Uuid::new_v4().to_string();
+    let job = PrintJob {
+        id: job_id.clone(),
+        repo_path: request.repo_path.clone(),
+        source_repo_path: request.source_repo_path.clone(),
+        format: request.format.clone(),
+        destination: request.destination.clone(),
+        status: "running".to_string(),
+        output: None,
+        error: None,
+        started: utc_now_timestamp_string(),
+        finished: None,
+    };
+    {
+        let mut jobs = PRINT_JOBS.lock().unwrap();
+        prune_print_jobs(&mut jobs);
+        jobs.insert(job_id.clone(), job);
+    }
+    let thread_job_id = job_id.clone();
+    std::thread::spawn(move || {
+        let result = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| render_print(&request))) {
+            Ok(r) => r,
+            Err(payload) => Err(PankosmiaError(format!("Print job panicked: {}", panic_message(payload.as_ref())))),
+        };
+        if let Some(job) = PRINT_JOBS.lock().unwrap().get_mut(&thread_job_id) {
+            match result {
+                Ok(output) => {
+                    job.status = "done".to_string();
+                    job.output = Some(output);
+                }
+                Err(e) => {
+                    job.status = "failed".to_string();
+                    job.error = Some(e.0);
+                }
+            }
+            job.finished = Some(utc_now_timestamp_string());
+        }
+    });
+    job_id
+}
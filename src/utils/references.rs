@@ -0,0 +1,203 @@
+use crate::structs::Bcv;
+use crate::utils::burrito::negotiated_localized_string;
+use crate::utils::paths::os_slash_str;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Book names by book code, then by name style (short, abbr, long), then by language.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BookNames {
+    names: BTreeMap<String, BTreeMap<String, BTreeMap<String, String>>>,
+}
+
+// Lowercase without spaces or punctuation, so that "1 Cor." and "1cor" match
+fn normalized_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+// Abbreviations that match several book codes equally well, with the book they conventionally mean
+const CONVENTIONAL_ABBREVIATIONS: [(&str, &str); 1] = [("jn", "JHN")];
+
+// True if the letters of short appear in order in long, starting with the same character
+fn is_abbreviation_of(short: &str, long: &str) -> bool {
+    if short.is_empty() || short.chars().next() != long.chars().next() {
+        return false;
+    }
+    let mut long_chars = long.chars();
+    short.chars().all(|c| long_chars.any(|lc| lc == c))
+}
+
+impl BookNames {
+    /// Adds the names in a burrito `localizedNames` object, keyed like `book-jhn`.
+    pub(crate) fn add_localized_names(&mut self, localized_names: &Value) {
+        if let Some(books) = localized_names.as_object() {
+            for (book_key, styles) in books {
+                let book_code = match book_key.strip_prefix("book-") {
+                    Some(c) => c.to_uppercase(),
+                    None => continue,
+                };
+                let book_entry = self.names.entry(book_code).or_default();
+                for style in ["short", "abbr", "long"] {
+                    if let Some(languages) = styles[style].as_object() {
+                        let style_entry = book_entry.entry(style.to_string()).or_default();
+                        for (language, name) in languages {
+                            if let Some(n) = name.as_str() {
+                                style_entry.entry(language.clone()).or_insert(n.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the book code for a name, preferring book codes and exact names, then name prefixes, then abbreviations.
+    /// Within each of these, shorter names win, then localized abbreviations over other names and book codes.
+    pub(crate) fn book_code_for(&self, name: &str, book_codes: &[String]) -> Option<String> {
+        let wanted = normalized_name(name);
+        if wanted.is_empty() {
+            return None;
+        }
+        let upper_name = name.trim().to_uppercase();
+        if book_codes.contains(&upper_name) {
+            return Some(upper_name);
+        }
+        // Candidate names with a rank for their style: abbreviations, then short and long names, then book codes
+        let mut candidates: Vec<(String, String, usize)> = vec![];
+        for book_code in book_codes {
+            candidates.push((book_code.clone(), normalized_name(book_code), 3));
+            if let Some(styles) = self.names.get(book_code) {
+                for (style, languages) in styles {
+                    let rank = match style.as_str() {
+                        "abbr" => 0,
+                        "short" => 1,
+                        _ => 2,
+                    };
+                    for n in languages.values() {
+                        candidates.push((book_code.clone(), normalized_name(n), rank));
+                    }
+                }
+            }
+        }
+        let tests: [&dyn Fn(&String) -> bool; 3] = [
+            &|candidate: &String| *candidate == wanted,
+            &|candidate: &String| candidate.starts_with(&wanted),
+            &|candidate: &String| is_abbreviation_of(&wanted, candidate),
+        ];
+        for test in tests.iter() {
+            let matches: Vec<&(String, String, usize)> =
+                candidates.iter().filter(|(_, candidate, _)| test(candidate)).collect();
+            let best_key = match matches.iter().map(|(_, candidate, rank)| (candidate.len(), *rank)).min() {
+                Some(k) => k,
+                None => continue,
+            };
+            let best_book_codes: Vec<&String> = matches
+                .iter()
+                .filter(|(_, candidate, rank)| (candidate.len(), *rank) == best_key)
+                .map(|(book_code, _, _)| book_code)
+                .collect();
+            // Ties between books, such as "jn" for both JHN and JON, go to the conventional book, then to the first book
+            if let Some((_, conventional)) = CONVENTIONAL_ABBREVIATIONS.iter().find(|(abbreviation, book_code)| {
+                *abbreviation == wanted && best_book_codes.iter().any(|b| b == book_code)
+            }) {
+                return Some(conventional.to_string());
+            }
+            return Some(best_book_codes[0].clone());
+        }
+        None
+    }
+
+    /// Returns the book name in the first available user language, for a style, falling back to the book code.
+    pub(crate) fn book_name(&self, book_code: &str, style: &str, languages: &[String]) -> String {
+        let styles = match self.names.get(book_code) {
+            Some(s) => s,
+            None => return book_code.to_string(),
+        };
+        for try_style in [style, "short", "long", "abbr"] {
+            if let Some(localized) = styles.get(try_style) {
+                if let Some(n) = negotiated_localized_string(localized, languages) {
+                    return n;
+                }
+            }
+        }
+        book_code.to_string()
+    }
+}
+
+/// Collects localized book names from the metadata of the given repos.
+pub(crate) fn book_names_from_repos(repo_dir: &str, repo_paths: &[String]) -> BookNames {
+    let mut book_names = BookNames::default();
+    for repo_path in repo_paths {
+        let metadata_path = format!("{}{}{}{}metadata.json", repo_dir, os_slash_str(), repo_path, os_slash_str());
+        if let Ok(s) = fs::read_to_string(&metadata_path) {
+            if let Ok(metadata) = serde_json::from_str::<Value>(&s) {
+                book_names.add_localized_names(&metadata["localizedNames"]);
+            }
+        }
+    }
+    book_names
+}
+
+/// Parses a reference such as `Jn 3:16-18`, `1 Cor 13` or `Jean 3,16` into a book-code range.
+/// A chapter without verses covers the whole chapter, using the verse counts in max_verses when available.
+/// Chapter and verse numbers that do not fit in a u16 are an error.
+pub(crate) fn parse_reference(
+    reference: &str,
+    book_names: &BookNames,
+    book_codes: &[String],
+    max_verses: &Value,
+) -> Result<Bcv, String> {
+    let reference_re =
+        Regex::new(r"^\s*(.+?)(?:\s*(\d+)(?:\s*[:.,]\s*(\d+)(?:\s*[-–]\s*(\d+))?)?)?\s*$").unwrap();
+    let captures = match reference_re.captures(reference) {
+        Some(c) => c,
+        None => return Err(format!("Could not parse reference '{}'", reference)),
+    };
+    let book_code = match book_names.book_code_for(&captures[1], book_codes) {
+        Some(b) => b,
+        None => return Err(format!("Unknown book '{}'", &captures[1])),
+    };
+    let number = |n: usize| match captures.get(n) {
+        Some(m) => match m.as_str().parse::<u16>() {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => Err(format!("Number '{}' is too large in '{}'", m.as_str(), reference)),
+        },
+        None => Ok(None),
+    };
+    let chapter = number(2)?.unwrap_or(1);
+    let (verse, to_verse) = match number(3)? {
+        Some(v) => (v, number(4)?.unwrap_or(v)),
+        None => {
+            let last_verse = match &max_verses[book_code.as_str()][(chapter as usize).saturating_sub(1)] {
+                Value::String(s) => s.parse::<u16>().unwrap_or(1),
+                Value::Number(n) => n.as_u64().and_then(|n| u16::try_from(n).ok()).unwrap_or(1),
+                _ => 1,
+            };
+            (1, last_verse)
+        }
+    };
+    if to_verse < verse {
+        return Err(format!("Verse range ends before it starts in '{}'", reference));
+    }
+    Ok(Bcv {
+        book_code,
+        chapter,
+        verse,
+        to_verse,
+    })
+}
+
+/// Formats a reference using a book name in the user's languages, eg `Jean 3:16-18`.
+pub(crate) fn format_reference(bcv: &Bcv, book_names: &BookNames, style: &str, languages: &[String]) -> String {
+    let book_name = book_names.book_name(&bcv.book_code, style, languages);
+    if bcv.to_verse > bcv.verse {
+        format!("{} {}:{}-{}", book_name, bcv.chapter, bcv.verse, bcv.to_verse)
+    } else {
+        format!("{} {}:{}", book_name, bcv.chapter, bcv.verse)
+    }
+}
@@ -0,0 +1,394 @@
+use crate::structs::{Bcv, PankosmiaError};
+use crate::utils::files::write_file_atomically;
+use crate::utils::paths::{os_slash_str, search_index_path};
+use crate::utils::tsv::tsv_chapter_verses;
+use crate::utils::usfm::{usfm_book_headers, usfm_plain_text, usfm_verses};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use walkdir::WalkDir;
+
+// Serializes read-modify-write of search indexes
+static SEARCH_INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+static BOOK_CODE_SUFFIX_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"([1-6A-Z]{3})$").unwrap());
+
+static BOOK_CODE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[1-6A-Z]{3}$").unwrap());
+
+static MARKDOWN_LINK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+
+static MARKDOWN_SYNTAX_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^\s*(#+|>|[-*+]\s)|[*_`]").unwrap());
+
+static SPACE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+const INDEXED_EXTENSIONS: [&str; 4] = ["usfm", "sfm", "tsv", "md"];
+
+// TSV columns that hold identifiers or links rather than searchable text
+const NOT_INDEXED_TSV_COLUMNS: [&str; 11] = [
+    "Reference",
+    "Book",
+    "Chapter",
+    "Verse",
+    "ID",
+    "Tags",
+    "SupportReference",
+    "Occurrence",
+    "TWLink",
+    "OrigWords",
+    "OrigQuote",
+];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct SearchSegment {
+    pub(crate) bcv: Option<Bcv>,
+    pub(crate) text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexedIngredient {
+    modified: u64,
+    segments: Vec<SearchSegment>,
+}
+
+type RepoSearchIndex = BTreeMap<String, IndexedIngredient>;
+
+#[derive(Serialize)]
+pub(crate) struct SearchHit {
+    pub(crate) repo: String,
+    pub(crate) ipath: String,
+    pub(crate) bcv: Option<Bcv>,
+    pub(crate) snippet: String,
+}
+
+fn repo_index_path(working_dir: &String, repo_path: &str) -> String {
+    format!(
+        "{}{}{}.json",
+        search_index_path(working_dir),
+        os_slash_str(),
+        repo_path.replace("/", os_slash_str())
+    )
+}
+
+fn file_modified(file_path: &Path) -> u64 {
+    match fs::metadata(file_path).and_then(|m| m.modified()) {
+        Ok(t) => match t.duration_since(std::time::SystemTime::UNIX_EPOCH) {
+            Ok(d) => d.as_nanos() as u64,
+            Err(_) => 0,
+        },
+        Err(_) => 0,
+    }
+}
+
+fn is_indexed_ipath(ipath: &str) -> bool {
+    let file_name = ipath.split("/").last().unwrap_or("");
+    if file_name.starts_with(".") {
+        return false;
+    }
+    match file_name.rsplit_once(".") {
+        Some((_, extension)) => INDEXED_EXTENSIONS.contains(&extension.to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+fn book_code_from_ipath(ipath: &str) -> Option<String> {
+    let file_name = ipath.split("/").last().unwrap_or("");
+    let stem = match file_name.split_once(".") {
+        Some((s, _)) => s.to_uppercase(),
+        None => file_name.to_uppercase(),
+    };
+    BOOK_CODE_SUFFIX_RE.captures(&stem).map(|c| c[1].to_string())
+}
+
+fn bcv_for(book_code: &Option<String>, chapter: u16, verse: u16, to_verse: u16) -> Option<Bcv> {
+    book_code.as_ref().map(|b| Bcv {
+        book_code: b.clone(),
+        chapter,
+        verse,
+        to_verse,
+    })
+}
+
+fn usfm_segments(ipath: &str, usfm: &str) -> Vec<SearchSegment> {
+    let book_code = match usfm_book_headers(usfm).book_code {
+        Some(b) => Some(b),
+        None => book_code_from_ipath(ipath),
+    };
+    usfm_verses(usfm)
+        .iter()
+        .map(|v| SearchSegment {
+            bcv: bcv_for(&book_code, v.chapter, v.verse, v.to_verse),
+            text: usfm_plain_text(&v.usfm),
+        })
+        .filter(|s| !s.text.is_empty())
+        .collect()
+}
+
+fn tsv_segments(ipath: &str, tsv: &str) -> Vec<SearchSegment> {
+    let mut lines = tsv.lines();
+    let headers: Vec<String> = match lines.next() {
+        Some(h) => h.split("\t").map(|c| c.trim().to_string()).collect(),
+        None => return vec![],
+    };
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let reference_column = column("Reference");
+    let chapter_column = column("Chapter");
+    let verse_column = column("Verse");
+    let book_code = book_code_from_ipath(ipath);
+    let mut segments = vec![];
+    for line in lines {
+        let cells: Vec<&str> = line.split("\t").collect();
+        let (chapter, verse, to_verse) = match (reference_column, chapter_column, verse_column) {
+            (Some(r), _, _) => tsv_chapter_verses(cells.get(r).unwrap_or(&"")),
+            (None, Some(c), Some(v)) => tsv_chapter_verses(
+                format!("{}:{}", cells.get(c).unwrap_or(&""), cells.get(v).unwrap_or(&"")).as_str(),
+            ),
+            _ => (0, 0, 0),
+        };
+        let text = cells
+            .iter()
+            .enumerate()
+            .filter(|(n, _)| match headers.get(*n) {
+                Some(h) => !NOT_INDEXED_TSV_COLUMNS.contains(&h.as_str()),
+                None => true,
+            })
+            .map(|(_, c)| c.replace("<br>", " ").replace("\\n", " "))
+            .collect::<Vec<String>>()
+            .join(" ")
+            .trim()
+            .to_string();
+        if !text.is_empty() {
+            segments.push(SearchSegment {
+                bcv: bcv_for(&book_code, chapter, verse, to_verse),
+                text,
+            });
+        }
+    }
+    segments
+}
+
+// Markdown resources are often laid out as <book>/<chapter>/<verse>.md
+fn markdown_segments(ipath: &str, markdown: &str) -> Vec<SearchSegment> {
+    let parts: Vec<&str> = ipath.split("/").collect();
+    let bcv = if parts.len() >= 3 {
+        let book = parts[parts.len() - 3].to_uppercase();
+        let chapter = parts[parts.len() - 2].parse::<u16>();
+        let verse = parts[parts.len() - 1]
+            .split(".")
+            .next()
+            .unwrap_or("")
+            .parse::<u16>();
+        match (BOOK_CODE_RE.is_match(&book), chapter, verse) {
+            (true, Ok(c), Ok(v)) => bcv_for(&Some(book), c, v, v),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    markdown
+        .split("\n\n")
+        .map(|paragraph| {
+            let text = MARKDOWN_LINK_RE.replace_all(paragraph, "$1");
+            let text = MARKDOWN_SYNTAX_RE.replace_all(&text, "");
+            SPACE_RE.replace_all(&text, " ").trim().to_string()
+        })
+        .filter(|t| !t.is_empty())
+        .map(|t| SearchSegment {
+            bcv: bcv.clone(),
+            text: t,
+        })
+        .collect()
+}
+
+/// Returns the searchable segments of an ingredient, by file extension.
+pub(crate) fn ingredient_search_segments(ipath: &str, content: &str) -> Vec<SearchSegment> {
+    let extension = match ipath.rsplit_once(".") {
+        Some((_, e)) => e.to_lowercase(),
+        None => return vec![],
+    };
+    match extension.as_str() {
+        "usfm" | "sfm" => usfm_segments(ipath, content),
+        "tsv" => tsv_segments(ipath, content),
+        "md" => markdown_segments(ipath, content),
+        _ => vec![],
+    }
+}
+
+fn read_repo_index(working_dir: &String, repo_path: &str) -> RepoSearchIndex {
+    match fs::read_to_string(repo_index_path(working_dir, repo_path)) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn write_repo_index(working_dir: &String, repo_path: &str, index: &RepoSearchIndex) -> Result<(), PankosmiaError> {
+    let index_path = repo_index_path(working_dir, repo_path);
+    if let Some(parent) = Path::new(&index_path).parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            return Err(PankosmiaError(format!("Could not create search index directory: {}", e)));
+        }
+    }
+    let index_string = match serde_json::to_string(index) {
+        Ok(s) => s,
+        Err(e) => return Err(PankosmiaError(format!("Could not make search index as JSON: {}", e))),
+    };
+    match write_file_atomically(&index_path, &index_string) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(PankosmiaError(format!("Could not write search index for {}: {}", repo_path, e))),
+    }
+}
+
+fn index_ingredient(full_ingredient_path: &Path, ipath: &str) -> Option<IndexedIngredient> {
+    match fs::read_to_string(full_ingredient_path) {
+        Ok(content) => Some(IndexedIngredient {
+            modified: file_modified(full_ingredient_path),
+            segments: ingredient_search_segments(ipath, &content),
+        }),
+        Err(_) => None,
+    }
+}
+
+/// Loads the search index for a repo, reindexing ingredients that have changed since they were indexed.
+fn refreshed_repo_index(
+    working_dir: &String,
+    repo_dir: &str,
+    repo_path: &str,
+) -> Result<RepoSearchIndex, PankosmiaError> {
+    let _lock = SEARCH_INDEX_LOCK.lock().unwrap();
+    let mut index = read_repo_index(working_dir, repo_path);
+    let mut changed = false;
+    let ingredients_path = format!(
+        "{}{}{}{}ingredients",
+        repo_dir,
+        os_slash_str(),
+        repo_path,
+        os_slash_str()
+    );
+    let mut seen = HashSet::new();
+    for entry in WalkDir::new(&ingredients_path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.path().is_file() {
+            continue;
+        }
+        let ipath = match entry.path().strip_prefix(&ingredients_path) {
+            Ok(p) => p.to_string_lossy().replace("\\", "/"),
+            Err(_) => continue,
+        };
+        if !is_indexed_ipath(&ipath) || ipath.split("/").any(|p| p.starts_with(".")) {
+            continue;
+        }
+        seen.insert(ipath.clone());
+        let modified = file_modified(entry.path());
+        let is_current = match index.get(&ipath) {
+            Some(i) => i.modified == modified,
+            None => false,
+        };
+        if !is_current {
+            if let Some(indexed) = index_ingredient(entry.path(), &ipath) {
+                index.insert(ipath, indexed);
+                changed = true;
+            }
+        }
+    }
+    let before = index.len();
+    index.retain(|k, _| seen.contains(k));
+    if changed || index.len() != before {
+        write_repo_index(working_dir, repo_path, &index)?;
+    }
+    Ok(index)
+}
+
+/// Reindexes one ingredient after it has been written.
+pub(crate) fn update_search_index_for_ingredient(
+    working_dir: &String,
+    repo_dir: &str,
+    repo_path: &str,
+    ipath: &str,
+) -> Result<(), PankosmiaError> {
+    if !is_indexed_ipath(ipath) {
+        return Ok(());
+    }
+    let _lock = SEARCH_INDEX_LOCK.lock().unwrap();
+    let mut index = read_repo_index(working_dir, repo_path);
+    let full_ingredient_path = format!(
+        "{}{}{}{}ingredients{}{}",
+        repo_dir,
+        os_slash_str(),
+        repo_path,
+        os_slash_str(),
+        os_slash_str(),
+        ipath
+    );
+    match index_ingredient(Path::new(&full_ingredient_path), ipath) {
+        Some(indexed) => {
+            index.insert(ipath.to_string(), indexed);
+        }
+        None => {
+            index.remove(ipath);
+        }
+    }
+    write_repo_index(working_dir, repo_path, &index)
+}
+
+fn snippet(text: &str, term: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower_text = text.to_lowercase();
+    let match_char_pos = match lower_text.find(term) {
+        Some(byte_pos) => lower_text[..byte_pos].chars().count(),
+        None => 0,
+    };
+    let start = match_char_pos.saturating_sub(60);
+    let end = (match_char_pos + term.chars().count() + 60).min(chars.len());
+    let mut snippet_text: String = chars[start.min(end)..end].iter().collect();
+    if start > 0 {
+        snippet_text = format!("…{}", snippet_text);
+    }
+    if end < chars.len() {
+        snippet_text = format!("{}…", snippet_text);
+    }
+    snippet_text
+}
+
+/// Finds segments containing every term of the query, case-insensitively.
+pub(crate) fn search_repos(
+    working_dir: &String,
+    repo_dir: &str,
+    repo_paths: &[String],
+    query: &str,
+    book: &Option<String>,
+    limit: usize,
+) -> Result<Vec<SearchHit>, PankosmiaError> {
+    let terms: Vec<String> = query.to_lowercase().split_whitespace().map(|t| t.to_string()).collect();
+    let mut hits = vec![];
+    if terms.is_empty() {
+        return Ok(hits);
+    }
+    let book_filter = book.as_ref().map(|b| b.to_uppercase());
+    for repo_path in repo_paths {
+        let index = refreshed_repo_index(working_dir, repo_dir, repo_path)?;
+        for (ipath, indexed) in index.iter() {
+            for segment in indexed.segments.iter() {
+                if let Some(b) = &book_filter {
+                    match &segment.bcv {
+                        Some(bcv) if bcv.book_code == *b => {}
+                        _ => continue,
+                    }
+                }
+                let lower_text = segment.text.to_lowercase();
+                if terms.iter().all(|t| lower_text.contains(t.as_str())) {
+                    hits.push(SearchHit {
+                        repo: repo_path.clone(),
+                        ipath: ipath.clone(),
+                        bcv: segment.bcv.clone(),
+                        snippet: snippet(&segment.text, &terms[0]),
+                    });
+                    if hits.len() >= limit {
+                        return Ok(hits);
+                    }
+                }
+            }
+        }
+    }
+    Ok(hits)
+}
@@ -0,0 +1,243 @@
+use crate::structs::{BookChecks, CheckItem, PankosmiaError};
+use crate::utils::files::write_file_atomically;
+use crate::utils::paths::os_slash_str;
+use crate::utils::time::utc_now_timestamp_string;
+use crate::utils::tsv::{book_tsv_path, tsv_rows};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Default)]
+pub(crate) struct CategoryProgress {
+    total: usize,
+    selected: usize,
+    percent: f64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BookCheckProgress {
+    book_code: String,
+    categories: BTreeMap<String, CategoryProgress>,
+    total: usize,
+    selected: usize,
+    percent: f64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BookCheckReport {
+    book_code: String,
+    target: String,
+    total: usize,
+    selected: usize,
+    comments: usize,
+    verse_edits: usize,
+    groups: BTreeMap<String, usize>,
+    items: Vec<CheckItem>,
+}
+
+fn percent(selected: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    ((selected as f64 / total as f64) * 1000.0).round() / 10.0
+}
+
+fn checks_dir(tcore_path: &str) -> String {
+    format!("{}{}ingredients{}checks", tcore_path, os_slash_str(), os_slash_str())
+}
+
+fn book_checks_path(tcore_path: &str, book_code: &str) -> String {
+    format!("{}{}{}.json", checks_dir(tcore_path), os_slash_str(), book_code)
+}
+
+// The last part of a resource container link, eg figs-metaphor for rc://*/ta/man/translate/figs-metaphor
+fn rc_leaf(link: &str) -> String {
+    link.trim_end_matches('/').rsplit('/').next().unwrap_or("").to_string()
+}
+
+/// Reads the check items of a book from an x-tcore repo. Checks that have not been generated are None, but checks that cannot be
+/// read or parsed are an error, so that they are never overwritten.
+pub(crate) fn read_book_checks(tcore_path: &str, book_code: &str) -> Result<Option<BookChecks>, PankosmiaError> {
+    match std::fs::read_to_string(book_checks_path(tcore_path, book_code)) {
+        Ok(s) => match serde_json::from_str(&s) {
+            Ok(c) => Ok(Some(c)),
+            Err(e) => Err(PankosmiaError(format!("Could not parse checks for {}: {}", book_code, e))),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(PankosmiaError(format!("Could not read checks for {}: {}", book_code, e))),
+    }
+}
+
+/// Reads the check items of every book of an x-tcore repo.
+pub(crate) fn read_all_book_checks(tcore_path: &str) -> Result<Vec<BookChecks>, PankosmiaError> {
+    let mut all_checks = vec![];
+    if let Ok(entries) = std::fs::read_dir(checks_dir(tcore_path)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(book_code) = path.file_stem().and_then(|s| s.to_str()) {
+                if let Some(checks) = read_book_checks(tcore_path, book_code)? {
+                    all_checks.push(checks);
+                }
+            }
+        }
+    }
+    all_checks.sort_by(|a, b| a.book_code.cmp(&b.book_code));
+    Ok(all_checks)
+}
+
+pub(crate) fn write_book_checks(tcore_path: &str, checks: &BookChecks) -> Result<(), PankosmiaError> {
+    if let Err(e) = std::fs::create_dir_all(checks_dir(tcore_path)) {
+        return Err(PankosmiaError(format!("Could not create checks directory: {}", e)));
+    }
+    let checks_string = match serde_json::to_string_pretty(checks) {
+        Ok(s) => s,
+        Err(e) => return Err(PankosmiaError(format!("Could not make checks as JSON: {}", e))),
+    };
+    match write_file_atomically(&book_checks_path(tcore_path, &checks.book_code), &checks_string) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(PankosmiaError(format!("Could not write checks: {}", e))),
+    }
+}
+
+// Check items from the rows of a TSV resource for a book
+fn resource_check_items(resource_path: &str, book_code: &str, category: &str) -> Result<Vec<CheckItem>, PankosmiaError> {
+    let tsv_path = match book_tsv_path(resource_path, book_code) {
+        Some(p) => p,
+        None => return Err(PankosmiaError(format!("No {} TSV for {}", category, book_code))),
+    };
+    let tsv = match std::fs::read_to_string(&tsv_path) {
+        Ok(s) => s,
+        Err(e) => return Err(PankosmiaError(format!("Could not read {}: {}", tsv_path, e))),
+    };
+    let mut items = vec![];
+    for row in tsv_rows(&tsv) {
+        let quote = row.cell(&["Quote", "OrigQuote", "OrigWords"]).unwrap_or_default();
+        // Rows without a quote or a verse are introductions, not checks
+        if quote.is_empty() || row.chapter == 0 || row.verse == 0 {
+            continue;
+        }
+        let (group, note) = if category == "translationWords" {
+            let link = row.cell(&["TWLink"]).unwrap_or_default();
+            (rc_leaf(&link), link)
+        } else {
+            (
+                rc_leaf(&row.cell(&["SupportReference"]).unwrap_or_default()),
+                row.cell(&["Note", "OccurrenceNote"]).unwrap_or_default(),
+            )
+        };
+        let id = row
+            .cell(&["ID"])
+            .unwrap_or(format!("{}-{}-{}", row.chapter, row.verse, items.len()));
+        items.push(CheckItem {
+            id: format!("{}:{}", if category == "translationWords" { "tw" } else { "tn" }, id),
+            category: category.to_string(),
+            group,
+            chapter: row.chapter,
+            verse: row.verse,
+            quote,
+            occurrence: row.cell(&["Occurrence"]).and_then(|o| o.parse().ok()).unwrap_or(1),
+            note,
+            selections: vec![],
+            comment: None,
+            verse_edited: false,
+            updated: None,
+        });
+    }
+    Ok(items)
+}
+
+/// Generates the check items of a book from translation notes and translation words links repos, keeping the status of items
+/// in the *previous* checks of the book.
+pub(crate) fn generate_book_checks(
+    repo_dir: &str,
+    previous: Option<BookChecks>,
+    book_code: &str,
+    target: &str,
+    notes: &Option<String>,
+    words: &Option<String>,
+) -> Result<BookChecks, PankosmiaError> {
+    if notes.is_none() && words.is_none() {
+        return Err(PankosmiaError("At least one of notes and words must be provided".to_string()));
+    }
+    let mut items = vec![];
+    for (resource, category) in [(words, "translationWords"), (notes, "translationNotes")] {
+        if let Some(resource_repo_path) = resource {
+            let resource_path = format!("{}{}{}", repo_dir, os_slash_str(), resource_repo_path);
+            items.extend(resource_check_items(&resource_path, book_code, category)?);
+        }
+    }
+    let previous: BTreeMap<String, CheckItem> = match previous {
+        Some(c) => c.items.into_iter().map(|i| (i.id.clone(), i)).collect(),
+        None => BTreeMap::new(),
+    };
+    for item in items.iter_mut() {
+        if let Some(previous_item) = previous.get(&item.id) {
+            item.selections = previous_item.selections.clone();
+            item.comment = previous_item.comment.clone();
+            item.verse_edited = previous_item.verse_edited;
+            item.updated = previous_item.updated.clone();
+        }
+    }
+    items.sort_by_key(|i| (i.chapter, i.verse));
+    Ok(BookChecks {
+        book_code: book_code.to_string(),
+        target: target.to_string(),
+        notes: notes.clone(),
+        words: words.clone(),
+        generated: utc_now_timestamp_string(),
+        items,
+    })
+}
+
+fn item_selected(item: &CheckItem) -> bool {
+    !item.selections.is_empty()
+}
+
+pub(crate) fn book_check_progress(checks: &BookChecks) -> BookCheckProgress {
+    let mut categories: BTreeMap<String, CategoryProgress> = BTreeMap::new();
+    for item in checks.items.iter() {
+        let category = categories.entry(item.category.clone()).or_default();
+        category.total += 1;
+        if item_selected(item) {
+            category.selected += 1;
+        }
+    }
+    for category in categories.values_mut() {
+        category.percent = percent(category.selected, category.total);
+    }
+    let total = checks.items.len();
+    let selected = checks.items.iter().filter(|i| item_selected(i)).count();
+    BookCheckProgress {
+        book_code: checks.book_code.clone(),
+        categories,
+        total,
+        selected,
+        percent: percent(selected, total),
+    }
+}
+
+/// A report of the items of a book that have been checked, commented or that led to a verse edit, with the number of checked items by group.
+pub(crate) fn book_check_report(checks: &BookChecks) -> BookCheckReport {
+    let items: Vec<CheckItem> = checks
+        .items
+        .iter()
+        .filter(|i| item_selected(i) || i.comment.is_some() || i.verse_edited)
+        .cloned()
+        .collect();
+    let mut groups: BTreeMap<String, usize> = BTreeMap::new();
+    for item in items.iter().filter(|i| item_selected(i)) {
+        *groups.entry(item.group.clone()).or_insert(0) += 1;
+    }
+    BookCheckReport {
+        book_code: checks.book_code.clone(),
+        target: checks.target.clone(),
+        total: checks.items.len(),
+        selected: checks.items.iter().filter(|i| item_selected(i)).count(),
+        comments: items.iter().filter(|i| i.comment.is_some()).count(),
+        verse_edits: items.iter().filter(|i| i.verse_edited).count(),
+        groups,
+        items,
+    }
+}
@@ -0,0 +1,135 @@
+use crate::structs::{AlignmentSource, AlignmentTarget};
+use crate::utils::alignment::{align_words, aligned_verse_usfm, strip_alignment, unalign_words, verse_alignment};
+
+const VERSE_USFM: &str = " Paul, a servant of God\\f + \\ft Or slave.\\f* and an apostle of Jesus Christ.\n\\p\n";
+
+fn source(content: &str, strong: &str) -> AlignmentSource {
+    AlignmentSource {
+        content: content.to_string(),
+        strong: Some(strong.to_string()),
+        lemma: None,
+        morph: None,
+        occurrence: 1,
+        occurrences: 1,
+    }
+}
+
+fn target(word: &str, occurrence: u16) -> AlignmentTarget {
+    AlignmentTarget {
+        word: word.to_string(),
+        occurrence,
+        occurrences: 1,
+    }
+}
+
+fn target_words(verse_usfm: &str, n: usize) -> Vec<String> {
+    verse_alignment(verse_usfm).alignments[n]
+        .targets
+        .iter()
+        .map(|t| t.word.clone())
+        .collect()
+}
+
+#[test]
+fn test_read_unaligned_verse() {
+    let alignment = verse_alignment(VERSE_USFM);
+    let words: Vec<&str> = alignment.words.iter().map(|w| w.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec!["Paul", "a", "servant", "of", "God", "and", "an", "apostle", "of", "Jesus", "Christ"]
+    );
+    assert_eq!(alignment.words[3].occurrence, 1);
+    assert_eq!(alignment.words[8].occurrence, 2);
+    assert_eq!(alignment.words[8].occurrences, 2);
+    assert!(alignment.alignments.is_empty());
+}
+
+#[test]
+fn test_align_and_reread() {
+    let current = verse_alignment(VERSE_USFM);
+    let alignments = align_words(&current, &[source("Παῦλος", "G39720")], &[target("Paul", 1)]).unwrap();
+    let aligned = aligned_verse_usfm(VERSE_USFM, &alignments);
+    let alignments = align_words(
+        &verse_alignment(&aligned),
+        &[source("δοῦλος", "G14010")],
+        &[target("a", 1), target("servant", 1)],
+    )
+    .unwrap();
+    let aligned = aligned_verse_usfm(&aligned, &alignments);
+    assert!(aligned.contains(
+        "\\zaln-s |x-strong=\"G14010\" x-occurrence=\"1\" x-occurrences=\"1\" x-content=\"δοῦλος\"\\*\\w a|x-occurrence=\"1\" x-occurrences=\"1\"\\w* \\w servant|x-occurrence=\"1\" x-occurrences=\"1\"\\w*\\zaln-e\\*"
+    ));
+    assert!(aligned.contains("\\f + \\ft Or slave.\\f*"));
+    assert!(aligned.ends_with("\n\\p\n"));
+    let reread = verse_alignment(&aligned);
+    assert_eq!(reread.words, verse_alignment(VERSE_USFM).words);
+    assert_eq!(reread.alignments.len(), 2);
+    assert_eq!(reread.alignments[0].sources[0].content, "Παῦλος");
+    assert_eq!(target_words(&aligned, 0), vec!["Paul"]);
+    assert_eq!(target_words(&aligned, 1), vec!["a", "servant"]);
+    assert_eq!(aligned_verse_usfm(&aligned, &reread.alignments), aligned);
+}
+
+#[test]
+fn test_align_moves_words_between_alignments() {
+    let current = verse_alignment(VERSE_USFM);
+    let alignments = align_words(&current, &[source("δοῦλος", "G14010")], &[target("a", 1), target("servant", 1)]).unwrap();
+    let aligned = aligned_verse_usfm(VERSE_USFM, &alignments);
+    let alignments = align_words(&verse_alignment(&aligned), &[source("θεοῦ", "G23160")], &[target("a", 1)]).unwrap();
+    let aligned = aligned_verse_usfm(&aligned, &alignments);
+    // Alignments are read in the order of their first target word
+    assert_eq!(verse_alignment(&aligned).alignments[0].sources[0].content, "θεοῦ");
+    assert_eq!(target_words(&aligned, 0), vec!["a"]);
+    assert_eq!(target_words(&aligned, 1), vec!["servant"]);
+}
+
+#[test]
+fn test_align_repeated_word() {
+    let current = verse_alignment(VERSE_USFM);
+    let alignments = align_words(&current, &[source("Ἰησοῦ", "G24240")], &[target("of", 2), target("Jesus", 1)]).unwrap();
+    let aligned = aligned_verse_usfm(VERSE_USFM, &alignments);
+    assert!(aligned.contains("\\w of|x-occurrence=\"2\" x-occurrences=\"2\"\\w* \\w Jesus|x-occurrence=\"1\" x-occurrences=\"1\"\\w*\\zaln-e\\*"));
+    let reread = verse_alignment(&aligned);
+    let reread_targets: Vec<(&str, u16, u16)> = reread.alignments[0]
+        .targets
+        .iter()
+        .map(|t| (t.word.as_str(), t.occurrence, t.occurrences))
+        .collect();
+    assert_eq!(reread_targets, vec![("of", 2, 2), ("Jesus", 1, 1)]);
+}
+
+#[test]
+fn test_align_unknown_word() {
+    let current = verse_alignment(VERSE_USFM);
+    assert!(align_words(&current, &[source("Παῦλος", "G39720")], &[target("Peter", 1)]).is_err());
+    assert!(align_words(&current, &[], &[target("Paul", 1)]).is_err());
+}
+
+#[test]
+fn test_unalign_and_reread() {
+    let current = verse_alignment(VERSE_USFM);
+    let alignments = align_words(&current, &[source("Παῦλος", "G39720")], &[target("Paul", 1)]).unwrap();
+    let aligned = aligned_verse_usfm(VERSE_USFM, &alignments);
+    let alignments = align_words(
+        &verse_alignment(&aligned),
+        &[source("δοῦλος", "G14010")],
+        &[target("a", 1), target("servant", 1)],
+    )
+    .unwrap();
+    let aligned = aligned_verse_usfm(&aligned, &alignments);
+    let alignments = unalign_words(&verse_alignment(&aligned), &None, &Some(vec![target("a", 1)]));
+    let partly_unaligned = aligned_verse_usfm(&aligned, &alignments);
+    assert_eq!(target_words(&partly_unaligned, 1), vec!["servant"]);
+    let alignments = unalign_words(
+        &verse_alignment(&partly_unaligned),
+        &Some(vec![source("Παῦλος", "G39720")]),
+        &None,
+    );
+    let partly_unaligned = aligned_verse_usfm(&partly_unaligned, &alignments);
+    let reread = verse_alignment(&partly_unaligned);
+    assert_eq!(reread.alignments.len(), 1);
+    assert_eq!(reread.alignments[0].sources[0].content, "δοῦλος");
+    let unaligned = aligned_verse_usfm(&partly_unaligned, &unalign_words(&reread, &None, &Some(vec![target("servant", 1)])));
+    assert_eq!(unaligned, VERSE_USFM);
+    assert_eq!(strip_alignment(&aligned), VERSE_USFM);
+}
@@ -0,0 +1,40 @@
+use crate::utils::burrito::usfm_ingredient_for_book;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// App resources with eng versification and a repo with a TIT ingredient, in a new temporary directory
+fn resources_and_repo(name: &str) -> (PathBuf, String, String) {
+    let root = std::env::temp_dir().join(format!("pankosmia_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let vrs_dir = root.join("resources").join("templates").join("content_templates").join("vrs");
+    fs::create_dir_all(&vrs_dir).unwrap();
+    fs::write(vrs_dir.join("eng.json"), r#"{"maxVerses": {"TIT": ["16", "15", "15"]}}"#).unwrap();
+    let repo = root.join("repos").join("_local_").join("_local_").join("my_tit");
+    fs::create_dir_all(repo.join("ingredients")).unwrap();
+    fs::write(repo.join("ingredients").join("TIT.usfm"), "\\id TIT\n").unwrap();
+    (
+        root.clone(),
+        root.join("resources").display().to_string(),
+        repo.display().to_string(),
+    )
+}
+
+#[test]
+fn test_usfm_ingredient_for_book() {
+    let (root, resources, repo) = resources_and_repo("usfm_ingredient");
+    assert_eq!(usfm_ingredient_for_book(&resources, &repo, "TIT"), Some("TIT.usfm".to_string()));
+    assert_eq!(usfm_ingredient_for_book(&resources, &repo, "PHM"), None);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_usfm_ingredient_for_book_traversal() {
+    let (root, resources, repo) = resources_and_repo("usfm_traversal");
+    // A book parameter of ../../x, upper-cased, would resolve to this file next to the repo
+    let outside_path = root.join("repos").join("_local_").join("_local_").join("X.usfm");
+    fs::write(&outside_path, "\\id XXX\n").unwrap();
+    assert!(Path::new(&repo).join("ingredients").join("../../X.usfm").is_file());
+    assert_eq!(usfm_ingredient_for_book(&resources, &repo, "../../X"), None);
+    assert_eq!(usfm_ingredient_for_book(&resources, &repo, "../my_tit/ingredients/TIT"), None);
+    fs::remove_dir_all(root).unwrap();
+}
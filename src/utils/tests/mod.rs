@@ -0,0 +1,6 @@
+mod usfm;
+mod versification;
+mod references;
+mod usj;
+mod alignment;
+mod burrito;
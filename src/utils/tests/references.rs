@@ -0,0 +1,90 @@
+use crate::structs::Bcv;
+use crate::utils::references::{parse_reference, BookNames};
+use serde_json::{json, Value};
+
+fn book_codes() -> Vec<String> {
+    ["JDG", "JON", "JHN", "1CO", "2CO", "JUD"]
+        .iter()
+        .map(|b| b.to_string())
+        .collect()
+}
+
+fn book_names() -> BookNames {
+    let mut book_names = BookNames::default();
+    book_names.add_localized_names(&json!({
+        "book-jon": {
+            "short": {"en": "Jonah", "fr": "Jonas"},
+            "abbr": {"en": "Jon", "fr": "Jon"},
+            "long": {"en": "The Book of Jonah"}
+        },
+        "book-jhn": {
+            "short": {"en": "John", "fr": "Jean"},
+            "abbr": {"en": "Jn", "fr": "Jn"},
+            "long": {"en": "The Gospel according to John", "fr": "Évangile selon Jean"}
+        },
+        "book-1co": {
+            "short": {"en": "1 Corinthians", "fr": "1 Corinthiens"},
+            "abbr": {"en": "1 Cor", "fr": "1 Co"},
+            "long": {"en": "The First Letter to the Corinthians"}
+        }
+    }));
+    book_names
+}
+
+fn max_verses() -> Value {
+    json!({
+        "JON": ["17", "10", "10", "11"],
+        "1CO": ["31", "16", "23", "21", "13", "20", "40", "13", "27", "33", "34", "31", "13", "40", "58", "24"]
+    })
+}
+
+#[track_caller]
+fn assert_bcv(found: Result<Bcv, String>, expected: (&str, u16, u16, u16)) {
+    let found = found.unwrap();
+    assert_eq!(
+        (found.book_code.as_str(), found.chapter, found.verse, found.to_verse),
+        expected
+    );
+}
+
+#[test]
+fn test_parse_john_abbreviation() {
+    assert_bcv(parse_reference("Jn 3:16-18", &book_names(), &book_codes(), &max_verses()), ("JHN", 3, 16, 18));
+    assert_bcv(parse_reference("Jn 3:16", &book_names(), &book_codes(), &max_verses()), ("JHN", 3, 16, 16));
+}
+
+#[test]
+fn test_parse_john_abbreviation_without_localized_names() {
+    let book_names = BookNames::default();
+    assert_bcv(parse_reference("Jn 3:16-18", &book_names, &book_codes(), &max_verses()), ("JHN", 3, 16, 18));
+    assert_bcv(parse_reference("JON 1:17", &book_names, &book_codes(), &max_verses()), ("JON", 1, 17, 17));
+}
+
+#[test]
+fn test_parse_jonah() {
+    assert_bcv(parse_reference("Jon 1:17", &book_names(), &book_codes(), &max_verses()), ("JON", 1, 17, 17));
+    assert_bcv(parse_reference("Jonah 2", &book_names(), &book_codes(), &max_verses()), ("JON", 2, 1, 10));
+}
+
+#[test]
+fn test_parse_whole_chapter() {
+    assert_bcv(parse_reference("1 Cor 13", &book_names(), &book_codes(), &max_verses()), ("1CO", 13, 1, 13));
+    assert_bcv(parse_reference("1cor 13", &book_names(), &book_codes(), &max_verses()), ("1CO", 13, 1, 13));
+    assert_bcv(parse_reference("1 Corinthians 15:3", &book_names(), &book_codes(), &max_verses()), ("1CO", 15, 3, 3));
+}
+
+#[test]
+fn test_parse_localized_name() {
+    assert_bcv(parse_reference("Jean 3,16", &book_names(), &book_codes(), &max_verses()), ("JHN", 3, 16, 16));
+    assert_bcv(parse_reference("Jonas 1.2-3", &book_names(), &book_codes(), &max_verses()), ("JON", 1, 2, 3));
+    assert_bcv(parse_reference("1 Corinthiens 13", &book_names(), &book_codes(), &max_verses()), ("1CO", 13, 1, 13));
+}
+
+#[test]
+fn test_parse_bad_references() {
+    assert!(parse_reference("Hezekiah 3:16", &book_names(), &book_codes(), &max_verses()).is_err());
+    assert!(parse_reference("Jn 3:18-16", &book_names(), &book_codes(), &max_verses()).is_err());
+    assert!(parse_reference("Jn 65537:1", &book_names(), &book_codes(), &max_verses()).is_err());
+    assert!(parse_reference("Jn 3:65537", &book_names(), &book_codes(), &max_verses()).is_err());
+    assert!(parse_reference("Jn 3:16-65537", &book_names(), &book_codes(), &max_verses()).is_err());
+}
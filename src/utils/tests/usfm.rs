@@ -0,0 +1,95 @@
+use crate::utils::alignment::verse_alignment;
+use crate::utils::usfm::{replace_usfm_verses, usfm_verses};
+
+const CHAPTER_USFM: &str = "\\id TIT\n\\c 1\n\\p\n\\v 1 Paul, a servant of God.\n\\v 2 In hope of eternal life.\n\\s Qualifications\n\\p\n\\v 3-5 Bridged verses.\n\\v 6 If anyone is blameless.\n";
+
+const ALIGNED_USFM: &str = "\\id TIT\n\\c 1\n\\p\n\\v 1 \\zaln-s |x-strong=\"G39720\" x-occurrence=\"1\" x-occurrences=\"1\" x-content=\"Παῦλος\"\\*\\w Paul|x-occurrence=\"1\" x-occurrences=\"1\"\\w*\\zaln-e\\*, \\zaln-s |x-strong=\"G14010\" x-occurrence=\"1\" x-occurrences=\"1\" x-content=\"δοῦλος\"\\*\\w a|x-occurrence=\"1\" x-occurrences=\"1\"\\w* \\w servant|x-occurrence=\"1\" x-occurrences=\"1\"\\w*\\zaln-e\\*.\n\\v 2 In hope.\n";
+
+fn verse_text(usfm: &str, verse: u16) -> String {
+    usfm_verses(usfm)
+        .into_iter()
+        .find(|v| v.verse == verse)
+        .map(|v| v.usfm)
+        .unwrap()
+}
+
+#[test]
+fn test_replace_single_verse() {
+    let replaced = replace_usfm_verses(CHAPTER_USFM, 1, 2, 2, "In the hope of life.").unwrap();
+    assert_eq!(
+        replaced,
+        "\\id TIT\n\\c 1\n\\p\n\\v 1 Paul, a servant of God.\n\\v 2 In the hope of life.\n\\s Qualifications\n\\p\n\\v 3-5 Bridged verses.\n\\v 6 If anyone is blameless.\n"
+    );
+}
+
+#[test]
+fn test_replace_single_verse_rejects_verse_markers() {
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 1, 1, "Paul. \\v 2 Hope.").is_err());
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 1, 1, "\\c 2 Paul.").is_err());
+}
+
+#[test]
+fn test_replace_bridged_verse() {
+    for verse in [3, 4, 5] {
+        let replaced = replace_usfm_verses(CHAPTER_USFM, 1, verse, verse, "New bridge.").unwrap();
+        assert!(replaced.contains("\\v 3-5 New bridge.\n\\v 6"));
+        assert!(replaced.contains("\\s Qualifications\n\\p\n\\v 3-5"));
+    }
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 3, 5, "New bridge. \\v 4 Split.").is_err());
+}
+
+#[test]
+fn test_replace_verse_range() {
+    let replaced = replace_usfm_verses(CHAPTER_USFM, 1, 2, 6, "Hope. \\v 3-5 Bridge. \\v 6 Blameless.").unwrap();
+    assert_eq!(
+        replaced,
+        "\\id TIT\n\\c 1\n\\p\n\\v 1 Paul, a servant of God.\n\\v 2 Hope. \\v 3-5 Bridge. \\v 6 Blameless.\n"
+    );
+    assert_eq!(verse_text(&replaced, 6).trim(), "Blameless.");
+}
+
+#[test]
+fn test_replace_verse_range_checks_verse_markers() {
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 1, 2, "Paul.").is_err());
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 1, 2, "Paul. \\v 3 Hope.").is_err());
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 1, 2, "Paul. \\v 2 Hope. \\v 2 Again.").is_err());
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 2, 6, "Hope. \\v 3 Bridge. \\v 6 Blameless.").is_err());
+    assert!(replace_usfm_verses(CHAPTER_USFM, 1, 1, 2, "Paul. \\v 2 Hope.").is_ok());
+}
+
+#[test]
+fn test_replace_missing_verse() {
+    assert!(replace_usfm_verses(CHAPTER_USFM, 2, 1, 1, "Nothing.").is_err());
+}
+
+#[test]
+fn test_replace_aligned_verse_keeps_alignments() {
+    let replaced = replace_usfm_verses(ALIGNED_USFM, 1, 1, 1, "Paul, a faithful servant.").unwrap();
+    let alignment = verse_alignment(&verse_text(&replaced, 1));
+    let words: Vec<String> = alignment.words.iter().map(|w| w.word.clone()).collect();
+    assert_eq!(words, vec!["Paul", "a", "faithful", "servant"]);
+    assert_eq!(alignment.alignments.len(), 2);
+    assert_eq!(alignment.alignments[0].sources[0].content, "Παῦλος");
+    assert_eq!(alignment.alignments[0].targets[0].word, "Paul");
+    let servant_targets: Vec<String> = alignment.alignments[1].targets.iter().map(|t| t.word.clone()).collect();
+    assert_eq!(servant_targets, vec!["a", "servant"]);
+    assert!(replaced.contains("\\w faithful|x-occurrence=\"1\" x-occurrences=\"1\"\\w*"));
+    assert!(replaced.contains("\n\\v 2 In hope.\n"));
+}
+
+#[test]
+fn test_replace_aligned_verse_drops_removed_words() {
+    let replaced = replace_usfm_verses(ALIGNED_USFM, 1, 1, 1, "Simon, a servant.").unwrap();
+    let alignment = verse_alignment(&verse_text(&replaced, 1));
+    assert_eq!(alignment.alignments.len(), 1);
+    assert_eq!(alignment.alignments[0].sources[0].content, "δοῦλος");
+    assert!(!replaced.contains("Παῦλος"));
+}
+
+#[test]
+fn test_replace_aligned_verse_range() {
+    let replaced = replace_usfm_verses(ALIGNED_USFM, 1, 1, 2, "Paul the servant. \\v 2 In faith.").unwrap();
+    assert_eq!(verse_alignment(&verse_text(&replaced, 1)).alignments.len(), 2);
+    assert!(verse_alignment(&verse_text(&replaced, 2)).alignments.is_empty());
+    assert!(replaced.contains("\\v 2 In faith.\n"));
+}
@@ -0,0 +1,80 @@
+use crate::utils::alignment::verse_alignment;
+use crate::utils::usfm::usfm_verses;
+use crate::utils::usj::{usfm_to_usj, usfm_to_usx, usx_to_usfm};
+use serde_json::Value;
+
+const USFM: &str = "\\id TIT Test\n\\c 1\n\\p\n\\v 1 \\zaln-s |x-strong=\"G39720\" x-occurrence=\"1\" x-occurrences=\"1\" x-content=\"Παῦλος\"\\*\\w Paul|x-occurrence=\"1\" x-occurrences=\"1\"\\w*\\zaln-e\\*, a servant\\f + \\fr 1:1 \\ft Or slave.\\f* of God.\n\\s1 Titus in Crete\n\\p\n\\v 2 In hope.\n\\s1 Elders\n\\q1\n\\v 3 For this reason.\n";
+
+// USFM whitespace is not significant between markers, so round trips are compared without it
+fn collapsed_whitespace(usfm: &str) -> String {
+    usfm.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn paras(usj: &Value) -> Vec<&Value> {
+    usj["content"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|n| n["type"] == "para")
+        .collect()
+}
+
+#[test]
+fn test_usj_aligned_verse_with_footnote() {
+    let usj = usfm_to_usj(USFM);
+    assert_eq!(usj["type"], "USJ");
+    assert_eq!(usj["content"][0]["code"], "TIT");
+    assert_eq!(usj["content"][1]["number"], "1");
+    let verse_1 = paras(&usj)[0]["content"].as_array().unwrap();
+    assert_eq!(verse_1[0]["sid"], "TIT 1:1");
+    assert_eq!(verse_1[1]["marker"], "zaln-s");
+    assert_eq!(verse_1[1]["x-content"], "Παῦλος");
+    assert_eq!(verse_1[2]["marker"], "w");
+    assert_eq!(verse_1[2]["content"][0], "Paul");
+    assert_eq!(verse_1[3]["marker"], "zaln-e");
+    assert_eq!(verse_1[4], ", a servant");
+    assert_eq!(verse_1[5]["type"], "note");
+    assert_eq!(verse_1[5]["caller"], "+");
+    assert_eq!(verse_1[5]["content"][1]["content"][0], "Or slave.");
+    assert_eq!(verse_1[6], " of God.");
+}
+
+#[test]
+fn test_usj_headings_between_verses() {
+    let usj = usfm_to_usj(USFM);
+    let markers: Vec<&str> = paras(&usj).iter().map(|p| p["marker"].as_str().unwrap()).collect();
+    assert_eq!(markers, vec!["p", "s1", "p", "s1", "q1"]);
+    assert_eq!(paras(&usj)[1]["content"][0], "Titus in Crete");
+    assert_eq!(paras(&usj)[2]["content"][0]["sid"], "TIT 1:2");
+    assert_eq!(paras(&usj)[4]["content"][0]["sid"], "TIT 1:3");
+}
+
+#[test]
+fn test_usx_verses_end_before_headings() {
+    let usx = usfm_to_usx(USFM);
+    assert!(usx.contains("<note style=\"f\" caller=\"+\"><char style=\"fr\">1:1 </char><char style=\"ft\">Or slave.</char></note>"));
+    assert!(usx.contains(" of God.<verse eid=\"TIT 1:1\" /></para>\n  <para style=\"s1\">Titus in Crete</para>"));
+    assert!(usx.contains("In hope.<verse eid=\"TIT 1:2\" /></para>\n  <para style=\"s1\">Elders</para>"));
+    assert!(usx.contains("<chapter eid=\"TIT 1\" />"));
+}
+
+#[test]
+fn test_usfm_usx_usfm_round_trip() {
+    let round_tripped = usx_to_usfm(&usfm_to_usx(USFM));
+    assert_eq!(collapsed_whitespace(&round_tripped), collapsed_whitespace(USFM));
+    assert_eq!(usx_to_usfm(&usfm_to_usx(&round_tripped)), round_tripped);
+}
+
+#[test]
+fn test_round_trip_keeps_alignment() {
+    let round_tripped = usx_to_usfm(&usfm_to_usx(USFM));
+    let original_verses = usfm_verses(USFM);
+    let round_tripped_verses = usfm_verses(&round_tripped);
+    assert_eq!(original_verses.len(), round_tripped_verses.len());
+    let alignment = verse_alignment(&round_tripped_verses[0].usfm);
+    assert_eq!(alignment.alignments.len(), 1);
+    assert_eq!(alignment.alignments[0].sources[0].strong, Some("G39720".to_string()));
+    assert_eq!(alignment.alignments[0].targets[0].word, "Paul");
+    assert!(round_tripped_verses[0].usfm.contains("\\f + \\fr 1:1 \\ft Or slave.\\f*"));
+    assert!(round_tripped_verses[0].usfm.contains("\\s1 Titus in Crete"));
+}
@@ -0,0 +1,82 @@
+use crate::structs::Bcv;
+use crate::utils::versification::{map_bcv_between, Versification};
+use serde_json::json;
+
+fn eng() -> Versification {
+    Versification::from_json(&json!({
+        "maxVerses": {"PSA": ["6", "8", "8"], "MAL": ["14", "17", "18", "6"]},
+        "mappedVerses": {
+            "PSA 3:0": "PSA 3:1",
+            "PSA 3:1-8": "PSA 3:2-9",
+            "MAL 4:1-6": "MAL 3:19-24"
+        }
+    }))
+}
+
+fn org() -> Versification {
+    Versification::from_json(&json!({
+        "maxVerses": {"PSA": ["6", "8", "9"], "MAL": ["14", "17", "24"]},
+        "mappedVerses": {}
+    }))
+}
+
+fn bcv(book_code: &str, chapter: u16, verse: u16, to_verse: u16) -> Bcv {
+    Bcv {
+        book_code: book_code.to_string(),
+        chapter,
+        verse,
+        to_verse,
+    }
+}
+
+#[track_caller]
+fn assert_bcv(found: Bcv, expected: (&str, u16, u16, u16)) {
+    assert_eq!(
+        (found.book_code.as_str(), found.chapter, found.verse, found.to_verse),
+        expected
+    );
+}
+
+#[test]
+fn test_map_eng_to_org() {
+    assert_bcv(map_bcv_between(&bcv("MAL", 4, 1, 1), &eng(), &org()), ("MAL", 3, 19, 19));
+    assert_bcv(map_bcv_between(&bcv("MAL", 4, 2, 6), &eng(), &org()), ("MAL", 3, 20, 24));
+    assert_bcv(map_bcv_between(&bcv("PSA", 3, 0, 0), &eng(), &org()), ("PSA", 3, 1, 1));
+    assert_bcv(map_bcv_between(&bcv("PSA", 3, 8, 8), &eng(), &org()), ("PSA", 3, 9, 9));
+}
+
+#[test]
+fn test_map_org_to_eng() {
+    assert_bcv(map_bcv_between(&bcv("MAL", 3, 19, 19), &org(), &eng()), ("MAL", 4, 1, 1));
+    assert_bcv(map_bcv_between(&bcv("MAL", 3, 20, 24), &org(), &eng()), ("MAL", 4, 2, 6));
+    assert_bcv(map_bcv_between(&bcv("PSA", 3, 2, 9), &org(), &eng()), ("PSA", 3, 1, 8));
+}
+
+#[test]
+fn test_map_round_trip() {
+    for verse in 1..=6 {
+        let original = map_bcv_between(&bcv("MAL", 4, verse, verse), &eng(), &org());
+        assert_bcv(map_bcv_between(&original, &org(), &eng()), ("MAL", 4, verse, verse));
+    }
+}
+
+#[test]
+fn test_map_unmapped_verses() {
+    assert_bcv(map_bcv_between(&bcv("MAL", 3, 18, 18), &eng(), &org()), ("MAL", 3, 18, 18));
+    assert_bcv(map_bcv_between(&bcv("JHN", 3, 16, 18), &eng(), &org()), ("JHN", 3, 16, 18));
+    assert_bcv(map_bcv_between(&bcv("MAL", 4, 1, 1), &eng(), &eng()), ("MAL", 4, 1, 1));
+}
+
+#[test]
+fn test_map_range_across_chapters() {
+    // MAL 3:18 is unmapped and MAL 3:19 maps to another chapter, so only the first verse is kept
+    assert_bcv(map_bcv_between(&bcv("MAL", 3, 18, 19), &org(), &eng()), ("MAL", 3, 18, 18));
+}
+
+#[test]
+fn test_paratext_verse_mappings() {
+    let scheme = Versification::from_json(&json!({
+        "verseMappings": {"MAL 4:1-6": ["MAL 3:19-24"]}
+    }));
+    assert_bcv(map_bcv_between(&bcv("MAL", 4, 3, 3), &scheme, &org()), ("MAL", 3, 21, 21));
+}
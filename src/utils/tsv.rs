@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+/// A row of a book TSV resource such as translation notes or translation words links, with its parsed reference.
+#[derive(Debug, Clone)]
+pub(crate) struct TsvRow {
+    pub(crate) chapter: u16,
+    pub(crate) verse: u16,
+    pub(crate) cells: BTreeMap<String, String>,
+}
+
+impl TsvRow {
+    /// Returns the first non-empty cell among columns, which lets callers accept older and newer column names.
+    pub(crate) fn cell(&self, columns: &[&str]) -> Option<String> {
+        columns
+            .iter()
+            .filter_map(|c| self.cells.get(*c))
+            .find(|v| !v.trim().is_empty())
+            .map(|v| v.trim().to_string())
+    }
+}
+
+/// Reads references like 1:3, 1:3-5 and front:intro, with 0 for non-numeric parts
+pub(crate) fn tsv_chapter_verses(reference: &str) -> (u16, u16, u16) {
+    let (chapter_string, verse_string) = match reference.split_once(":") {
+        Some((c, v)) => (c, v),
+        None => (reference, ""),
+    };
+    let chapter = chapter_string.trim().parse::<u16>().unwrap_or(0);
+    let (from_string, to_string) = match verse_string.split_once("-") {
+        Some((f, t)) => (f, t),
+        None => (verse_string, verse_string),
+    };
+    let verse = from_string.trim().parse::<u16>().unwrap_or(0);
+    let to_verse = to_string.trim().parse::<u16>().unwrap_or(verse);
+    (chapter, verse, to_verse)
+}
+
+/// Reads the rows of a TSV resource by header, with references from a Reference column or from Chapter and Verse columns.
+pub(crate) fn tsv_rows(tsv: &str) -> Vec<TsvRow> {
+    let mut lines = tsv.lines();
+    let headers: Vec<String> = match lines.next() {
+        Some(h) => h.split("\t").map(|c| c.trim().to_string()).collect(),
+        None => return vec![],
+    };
+    let mut rows = vec![];
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: BTreeMap<String, String> = headers
+            .iter()
+            .cloned()
+            .zip(line.split("\t").map(|c| c.to_string()))
+            .collect();
+        let reference = match (cells.get("Reference"), cells.get("Chapter"), cells.get("Verse")) {
+            (Some(r), _, _) => r.clone(),
+            (None, Some(c), Some(v)) => format!("{}:{}", c, v),
+            _ => "".to_string(),
+        };
+        let (chapter, verse, _) = tsv_chapter_verses(&reference);
+        rows.push(TsvRow {
+            chapter,
+            verse,
+            cells,
+        });
+    }
+    rows
+}
+
+/// Finds the TSV ingredient for a book in a resource repo, such as `TIT.tsv`, `tn_TIT.tsv` or `twl_TIT.tsv`, as a full path.
+pub(crate) fn book_tsv_path(repo_path: &str, book_code: &str) -> Option<String> {
+    let path_to_ingredients = std::path::Path::new(repo_path).join("ingredients");
+    walkdir::WalkDir::new(&path_to_ingredients)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file() && e.path().extension().and_then(|x| x.to_str()) == Some("tsv"))
+        .find(|e| {
+            let stem = e.path().file_stem().and_then(|s| s.to_str()).unwrap_or("").to_uppercase();
+            stem == book_code || stem.ends_with(&format!("_{}", book_code))
+        })
+        .map(|e| e.path().display().to_string())
+}
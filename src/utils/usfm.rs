@@ -0,0 +1,245 @@
+use crate::structs::PankosmiaError;
+use crate::utils::alignment::{has_alignment_markup, realigned_verse_usfm};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A word of USFM text: letters, marks and digits, possibly joined by apostrophes or hyphens.
+pub(crate) const USFM_WORD_PATTERN: &str = r"[\p{L}\p{M}\p{N}]+(?:['’\-][\p{L}\p{M}\p{N}]+)*";
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UsfmHeaders {
+    pub(crate) book_code: Option<String>,
+    pub(crate) h: Option<String>,
+    pub(crate) toc1: Option<String>,
+    pub(crate) toc2: Option<String>,
+    pub(crate) toc3: Option<String>,
+}
+
+/// Reads book code and title markers from the part of a USFM book before the first chapter.
+pub(crate) fn usfm_book_headers(usfm: &str) -> UsfmHeaders {
+    let chapter_re = Regex::new(r"(?m)^\s*\\c\s").unwrap();
+    let header_text = match chapter_re.find(usfm) {
+        Some(m) => &usfm[..m.start()],
+        None => usfm,
+    };
+    let marker_re = Regex::new(r"(?m)^\s*\\(id|h|toc1|toc2|toc3)\s+(.*?)\s*$").unwrap();
+    let mut headers = UsfmHeaders::default();
+    for captures in marker_re.captures_iter(header_text) {
+        let value = captures[2].to_string();
+        if value.is_empty() {
+            continue;
+        }
+        match &captures[1] {
+            "id" => {
+                headers.book_code = value
+                    .split_whitespace()
+                    .next()
+                    .map(|c| c.to_uppercase())
+            }
+            "h" => headers.h = Some(value),
+            "toc1" => headers.toc1 = Some(value),
+            "toc2" => headers.toc2 = Some(value),
+            "toc3" => headers.toc3 = Some(value),
+            _ => {}
+        }
+    }
+    headers
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UsfmVerse {
+    pub(crate) chapter: u16,
+    pub(crate) verse: u16,
+    pub(crate) to_verse: u16,
+    pub(crate) usfm: String,
+    // Byte offsets of the verse content, after the verse marker
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// Splits a USFM book into verses, keeping the USFM of each verse. Bridged verses such as `\v 3-5` are one entry.
+/// Content before the first verse of a chapter is not returned.
+pub(crate) fn usfm_verses(usfm: &str) -> Vec<UsfmVerse> {
+    let cv_re = Regex::new(r"\\(c|v)\s+(\d+)(?:-(\d+))?").unwrap();
+    let mut verses: Vec<UsfmVerse> = vec![];
+    let mut chapter: u16 = 0;
+    let mut current: Option<(u16, u16, usize)> = None;
+    for captures in cv_re.captures_iter(usfm) {
+        let whole = captures.get(0).unwrap();
+        if let Some((verse, to_verse, start)) = current.take() {
+            verses.push(UsfmVerse {
+                chapter,
+                verse,
+                to_verse,
+                usfm: usfm[start..whole.start()].to_string(),
+                start,
+                end: whole.start(),
+            });
+        }
+        let number = captures[2].parse::<u16>().unwrap_or(0);
+        if &captures[1] == "c" {
+            chapter = number;
+        } else {
+            let to_number = match captures.get(3) {
+                Some(n) => n.as_str().parse::<u16>().unwrap_or(number),
+                None => number,
+            };
+            current = Some((number, to_number, whole.end()));
+        }
+    }
+    if let Some((verse, to_verse, start)) = current {
+        verses.push(UsfmVerse {
+            chapter,
+            verse,
+            to_verse,
+            usfm: usfm[start..].to_string(),
+            start,
+            end: usfm.len(),
+        });
+    }
+    verses
+}
+
+/// Returns the readable text of a USFM fragment, without markers, attributes, notes, headings or milestones.
+pub(crate) fn usfm_plain_text(usfm: &str) -> String {
+    let heading_re = Regex::new(r"(?m)^\s*\\(s\d?|ms\d?|mr|r|sr|sp|cl|rem|qa|sts)\s.*$").unwrap();
+    let note_re = Regex::new(r"(?s)\\(f|fe|x|ef|ex)\s.*?\\(f|fe|x|ef|ex)\*").unwrap();
+    let milestone_re = Regex::new(r"\\[a-z0-9]+-[se]\b[^\\]*\\\*").unwrap();
+    let attribute_re = Regex::new(r"\|[^\\]*(\\\+?[a-z0-9-]+\*)").unwrap();
+    let marker_re = Regex::new(r"\\\+?[a-z0-9-]+\*?").unwrap();
+    let space_re = Regex::new(r"\s+").unwrap();
+    let text = heading_re.replace_all(usfm, "");
+    let text = note_re.replace_all(&text, " ");
+    let text = milestone_re.replace_all(&text, "");
+    let text = attribute_re.replace_all(&text, "$1");
+    let text = marker_re.replace_all(&text, " ");
+    let text = text.replace("//", "").replace("~", " ");
+    space_re
+        .replace_all(&text, " ")
+        .trim()
+        .replace(" ,", ",")
+        .replace(" .", ".")
+        .replace(" ;", ";")
+        .replace(" :", ":")
+        .replace(" !", "!")
+        .replace(" ?", "?")
+}
+
+/// Verse numbers of a USFM book by chapter, split into verses with text and verses that are empty or only hold a `___` placeholder.
+/// Every verse of a bridge is counted.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UsfmVerseContent {
+    pub(crate) drafted: BTreeMap<u16, BTreeSet<u16>>,
+    pub(crate) empty: BTreeMap<u16, BTreeSet<u16>>,
+}
+
+pub(crate) fn usfm_verse_content(usfm: &str) -> UsfmVerseContent {
+    let mut content = UsfmVerseContent::default();
+    for verse in usfm_verses(usfm) {
+        let text = usfm_plain_text(&verse.usfm);
+        let is_empty = text.chars().all(|c| c == '_' || c.is_whitespace());
+        let target = if is_empty { &mut content.empty } else { &mut content.drafted };
+        let chapter_verses = target.entry(verse.chapter).or_default();
+        for v in verse.verse..=verse.to_verse.max(verse.verse) {
+            chapter_verses.insert(v);
+        }
+    }
+    content
+}
+
+/// Returns the verses of a chapter that overlap a verse range, so that bridged verses are included.
+pub(crate) fn usfm_verses_in_range(usfm: &str, chapter: u16, verse: u16, to_verse: u16) -> Vec<UsfmVerse> {
+    let last_verse = to_verse.max(verse);
+    usfm_verses(usfm)
+        .into_iter()
+        .filter(|v| v.chapter == chapter && v.to_verse >= verse && v.verse <= last_verse)
+        .collect()
+}
+
+fn verse_label(verse: u16, to_verse: u16) -> String {
+    if to_verse > verse {
+        format!("{}-{}", verse, to_verse)
+    } else {
+        verse.to_string()
+    }
+}
+
+/// Replaces the content of a verse or verse range, keeping the first verse marker and any paragraph or heading markup that follows the last verse.
+/// New content for a range must include exactly the verse markers of the range after the first one.
+/// When the new content has no alignment markup, the alignments of each replaced verse are kept for the words that are still in that verse.
+pub(crate) fn replace_usfm_verses(
+    usfm: &str,
+    chapter: u16,
+    verse: u16,
+    to_verse: u16,
+    new_content: &str,
+) -> Result<String, PankosmiaError> {
+    let selected = usfm_verses_in_range(usfm, chapter, verse, to_verse);
+    let (first, last) = match (selected.first(), selected.last()) {
+        (Some(f), Some(l)) => (f, l),
+        _ => {
+            return Err(PankosmiaError(format!(
+                "Verse {}:{} not found",
+                chapter, verse
+            )))
+        }
+    };
+    if Regex::new(r"\\c\s").unwrap().is_match(new_content) {
+        return Err(PankosmiaError("New verse content may not contain chapters".to_string()));
+    }
+    let verse_marker_re = Regex::new(r"\\v\s+(\d+)(?:-(\d+))?").unwrap();
+    let new_content = new_content.trim();
+    let new_markers: Vec<String> = verse_marker_re
+        .captures_iter(new_content)
+        .map(|c| c[0][2..].trim().to_string())
+        .collect();
+    let expected_markers: Vec<String> = selected[1..].iter().map(|v| verse_label(v.verse, v.to_verse)).collect();
+    if selected.len() == 1 && !new_markers.is_empty() {
+        return Err(PankosmiaError("New content for one verse may not contain verse markers".to_string()));
+    }
+    if new_markers != expected_markers {
+        return Err(PankosmiaError(format!(
+            "New content must contain the verse markers {}, found {}",
+            expected_markers.join(", "),
+            new_markers.join(", ")
+        )));
+    }
+    // Each piece of the new content, between verse markers, replaces one of the selected verses
+    let new_content = if !has_alignment_markup(new_content) && selected.iter().any(|v| has_alignment_markup(&v.usfm)) {
+        let mut realigned = String::new();
+        let mut position = 0;
+        let mut old_verses = selected.iter();
+        for marker in verse_marker_re.find_iter(new_content) {
+            realigned.push_str(&realigned_verse_usfm(&old_verses.next().unwrap().usfm, &new_content[position..marker.start()]));
+            realigned.push_str(marker.as_str());
+            position = marker.end();
+        }
+        realigned.push_str(&realigned_verse_usfm(&old_verses.next().unwrap().usfm, &new_content[position..]));
+        realigned
+    } else {
+        new_content.to_string()
+    };
+    let region = &usfm[first.start..last.end];
+    let region_re = Regex::new(
+        r"(?s)^(\s*)(.*?)((?:\s*(?:\\(?:p|m|mi|nb|pi\d?|pc|pm|pmo|pmc|pmr|ph\d?|q\d?|qc|qr|qm\d?|b|li\d?|lh|lf|cls)[ \t]*(?:\n|$)|\\(?:s\d?|ms\d?|mr|r|d|sp|cl|rem)[ \t][^\\\n]*|\\ts(?:-[se])?\b[^\\]*\\\*))*\s*)$",
+    )
+    .unwrap();
+    let (leading, trailing) = match region_re.captures(region) {
+        Some(c) => (c[1].to_string(), c[3].to_string()),
+        None => (" ".to_string(), "".to_string()),
+    };
+    let leading = if leading.is_empty() { " ".to_string() } else { leading };
+    let trailing = if trailing.is_empty() && last.end < usfm.len() {
+        "\n".to_string()
+    } else {
+        trailing
+    };
+    Ok(format!(
+        "{}{}{}{}{}",
+        &usfm[..first.start],
+        leading,
+        new_content.trim(),
+        trailing,
+        &usfm[last.end..]
+    ))
+}
@@ -0,0 +1,762 @@
+use crate::structs::PankosmiaError;
+use crate::utils::paths::os_slash_str;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+static USFM_MARKER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\(\+?)([A-Za-z0-9_-]+)(\*?)|\\\*").unwrap());
+
+static SPACE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+static USFM_ATTRIBUTE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"([A-Za-z0-9_-]+)\s*=\s*"([^"]*)""#).unwrap());
+
+static XML_TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<!--.*?-->|<\?.*?\?>|<!DOCTYPE[^>]*>|<(/?)([A-Za-z_][A-Za-z0-9_:.-]*)([^>]*?)(/?)>").unwrap()
+});
+
+static XML_ATTRIBUTE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([A-Za-z_][A-Za-z0-9_:.-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+const USJ_VERSION: &str = "3.1";
+
+const NOTE_MARKERS: [&str; 5] = ["f", "fe", "ef", "x", "ex"];
+
+const CHAR_MARKERS: [&str; 59] = [
+    "w", "wj", "wg", "wh", "wa", "nd", "add", "addpn", "bk", "pn", "png", "qt", "sig", "sls", "tl",
+    "em", "bd", "it", "bdit", "no", "sc", "sup", "k", "rq", "ior", "iqt", "dc", "ord", "lit", "qs",
+    "qac", "va", "vp", "ca", "cp", "rb", "jmp", "fig", "pro", "fr", "fq", "fqa", "fk", "ft", "fl",
+    "fw", "fp", "fv", "fdc", "xo", "xk", "xq", "xt", "xta", "xop", "xot", "xnt", "xdc", "ref",
+];
+
+// Character markers inside notes, which end at the next one of these or at the end of the note
+const NOTE_CHAR_MARKERS: [&str; 18] = [
+    "fr", "fq", "fqa", "fk", "ft", "fl", "fw", "fp", "fv", "fdc", "xo", "xk", "xq", "xt", "xta",
+    "xop", "xot", "xnt",
+];
+
+// Paragraphs that end the current verse, since they come between verses in USX
+const HEADING_MARKERS: [&str; 9] = ["s", "ms", "mr", "r", "d", "sp", "cl", "sr", "mt"];
+
+#[derive(Debug, Clone)]
+enum UsfmToken {
+    Marker { name: String, nested: bool, closing: bool },
+    MilestoneEnd,
+    Text(String),
+}
+
+/// A USFM element with USX-style kind (book, chapter, verse, para, char, note, ms, table...), marker and attributes.
+#[derive(Debug, Clone)]
+pub(crate) struct UsfmElement {
+    pub(crate) kind: String,
+    pub(crate) marker: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) content: Vec<UsfmNode>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum UsfmNode {
+    Text(String),
+    Element(UsfmElement),
+}
+
+fn element(kind: &str, marker: &str) -> UsfmElement {
+    UsfmElement {
+        kind: kind.to_string(),
+        marker: marker.to_string(),
+        attributes: vec![],
+        content: vec![],
+    }
+}
+
+fn tokenize(usfm: &str) -> Vec<UsfmToken> {
+    let mut tokens = vec![];
+    let mut last_end = 0;
+    for captures in USFM_MARKER_RE.captures_iter(usfm) {
+        let whole = captures.get(0).unwrap();
+        if whole.start() > last_end {
+            tokens.push(UsfmToken::Text(
+                SPACE_RE.replace_all(&usfm[last_end..whole.start()], " ").to_string(),
+            ));
+        }
+        last_end = whole.end();
+        match captures.get(2) {
+            Some(name) => tokens.push(UsfmToken::Marker {
+                name: name.as_str().to_string(),
+                nested: &captures[1] == "+",
+                closing: &captures[3] == "*",
+            }),
+            None => tokens.push(UsfmToken::MilestoneEnd),
+        }
+    }
+    if last_end < usfm.len() {
+        tokens.push(UsfmToken::Text(SPACE_RE.replace_all(&usfm[last_end..], " ").to_string()));
+    }
+    tokens
+}
+
+// Strips trailing digits so that s1 and q2 are treated like s and q
+pub(crate) fn marker_base(marker: &str) -> &str {
+    marker.trim_end_matches(|c: char| c.is_ascii_digit())
+}
+
+fn is_milestone(marker: &str) -> bool {
+    marker.ends_with("-s") || marker.ends_with("-e") || marker == "ts"
+}
+
+fn is_table_cell(marker: &str) -> bool {
+    ["th", "thr", "thc", "tc", "tcr", "tcc"].contains(&marker_base(marker))
+}
+
+fn parse_attributes(marker: &str, attribute_string: &str) -> Vec<(String, String)> {
+    let mut attributes: Vec<(String, String)> = USFM_ATTRIBUTE_RE
+        .captures_iter(attribute_string)
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect();
+    let trimmed = attribute_string.trim();
+    if attributes.is_empty() && !trimmed.is_empty() {
+        let default_attribute = match marker {
+            "w" => "lemma",
+            "rb" => "gloss",
+            "xt" => "link-href",
+            "fig" => "src",
+            _ => "",
+        };
+        if !default_attribute.is_empty() {
+            attributes.push((default_attribute.to_string(), trimmed.to_string()));
+        }
+    }
+    attributes
+}
+
+struct UsfmParser {
+    stack: Vec<UsfmElement>,
+    book_code: String,
+    chapter: Option<String>,
+    verse: Option<String>,
+}
+
+impl UsfmParser {
+    fn top(&mut self) -> &mut UsfmElement {
+        self.stack.last_mut().unwrap()
+    }
+
+    fn pop(&mut self) {
+        if self.stack.len() < 2 {
+            return;
+        }
+        let mut popped = self.stack.pop().unwrap();
+        if popped.kind == "para" || popped.kind == "book" || popped.kind == "table:cell" {
+            if let Some(UsfmNode::Text(t)) = popped.content.last_mut() {
+                *t = t.trim_end().to_string();
+                if t.is_empty() {
+                    popped.content.pop();
+                }
+            }
+        }
+        if popped.kind == "char" {
+            if let Some(UsfmNode::Text(t)) = popped.content.last_mut() {
+                if let Some((text, attribute_string)) = t.clone().rsplit_once("|") {
+                    *t = text.to_string();
+                    popped.attributes = parse_attributes(&popped.marker, attribute_string);
+                }
+            }
+        }
+        self.top().content.push(UsfmNode::Element(popped));
+    }
+
+    fn pop_to_top_level(&mut self) {
+        while self.stack.len() > 1 {
+            self.pop();
+        }
+    }
+
+    fn pop_while(&mut self, kinds: &[&str]) {
+        while self.stack.len() > 1 && kinds.contains(&self.top().kind.as_str()) {
+            self.pop();
+        }
+    }
+
+    fn push_leaf(&mut self, leaf: UsfmElement) {
+        self.top().content.push(UsfmNode::Element(leaf));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if self.stack.len() == 1 {
+            if text.trim().is_empty() {
+                return;
+            }
+            self.stack.push(element("para", "p"));
+        }
+        let top = self.top();
+        if top.content.is_empty() && (top.kind == "para" || top.kind == "book") {
+            let trimmed = text.trim_start();
+            if !trimmed.is_empty() {
+                top.content.push(UsfmNode::Text(trimmed.to_string()));
+            }
+            return;
+        }
+        match top.content.last_mut() {
+            Some(UsfmNode::Text(t)) => t.push_str(text),
+            _ => top.content.push(UsfmNode::Text(text.to_string())),
+        }
+    }
+
+    // Verse ends go in the innermost open paragraph, or in the previous paragraph if that one is still empty
+    fn end_verse(&mut self) {
+        if let Some(sid) = self.verse.take() {
+            let mut verse_end = element("verse", "v");
+            verse_end.attributes.push(("eid".to_string(), sid));
+            if let Some(i) = self.stack.iter().rposition(|e| e.kind == "para") {
+                while self.stack.len() > i + 1 {
+                    self.pop();
+                }
+            }
+            let top = self.top();
+            let target = if top.kind == "para" && !top.content.is_empty() {
+                Some(top)
+            } else {
+                let parent_index = if top.kind == "para" { self.stack.len() - 2 } else { self.stack.len() - 1 };
+                match self.stack[parent_index].content.last_mut() {
+                    Some(UsfmNode::Element(e)) if e.kind == "para" => Some(e),
+                    _ => None,
+                }
+            };
+            match target {
+                Some(para) => {
+                    if let Some(UsfmNode::Text(t)) = para.content.last_mut() {
+                        *t = t.trim_end().to_string();
+                    }
+                    para.content.push(UsfmNode::Element(verse_end));
+                }
+                None => self.push_leaf(verse_end),
+            }
+        }
+    }
+
+    fn end_chapter(&mut self) {
+        self.end_verse();
+        self.pop_to_top_level();
+        if let Some(sid) = self.chapter.take() {
+            let mut chapter_end = element("chapter", "c");
+            chapter_end.attributes.push(("eid".to_string(), sid));
+            self.push_leaf(chapter_end);
+        }
+    }
+}
+
+// Takes the first word from text that follows a marker such as \c, \v or \f
+fn split_first_word(text: &str) -> (String, String) {
+    let trimmed = text.trim_start();
+    match trimmed.split_once(' ') {
+        Some((first, rest)) => (first.to_string(), rest.to_string()),
+        None => (trimmed.to_string(), "".to_string()),
+    }
+}
+
+/// Parses USFM into a tree of elements, with verses ended before headings, as shared by the USJ, USX, HTML and ConTeXt converters.
+pub(crate) fn parse_usfm(usfm: &str) -> UsfmElement {
+    let tokens = tokenize(usfm);
+    let mut parser = UsfmParser {
+        stack: vec![element("USJ", "")],
+        book_code: "".to_string(),
+        chapter: None,
+        verse: None,
+    };
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index].clone();
+        index += 1;
+        // Text that directly follows a marker, with the separating space removed
+        let mut following_text = || -> String {
+            match tokens.get(index) {
+                Some(UsfmToken::Text(t)) => {
+                    index += 1;
+                    t.strip_prefix(' ').unwrap_or(t).to_string()
+                }
+                _ => "".to_string(),
+            }
+        };
+        match token {
+            UsfmToken::Text(t) => parser.push_text(&t),
+            UsfmToken::MilestoneEnd => {}
+            UsfmToken::Marker { name, nested: _, closing: true } => {
+                if let Some(position) = parser
+                    .stack
+                    .iter()
+                    .rposition(|e| e.marker == name && (e.kind == "char" || e.kind == "note"))
+                {
+                    while parser.stack.len() > position {
+                        parser.pop();
+                    }
+                }
+            }
+            UsfmToken::Marker { name, nested, closing: false } => {
+                let marker = name.as_str();
+                if marker == "id" {
+                    parser.pop_to_top_level();
+                    let (code, rest) = split_first_word(&following_text());
+                    parser.book_code = code.to_uppercase();
+                    let mut book = element("book", "id");
+                    book.attributes.push(("code".to_string(), parser.book_code.clone()));
+                    parser.stack.push(book);
+                    parser.push_text(&rest);
+                } else if marker == "c" {
+                    parser.end_chapter();
+                    let (number, rest) = split_first_word(&following_text());
+                    let sid = format!("{} {}", parser.book_code, number);
+                    let mut chapter = element("chapter", "c");
+                    chapter.attributes.push(("number".to_string(), number));
+                    chapter.attributes.push(("sid".to_string(), sid.clone()));
+                    parser.push_leaf(chapter);
+                    parser.chapter = Some(sid);
+                    parser.push_text(rest.trim_start());
+                } else if marker == "v" {
+                    parser.end_verse();
+                    parser.pop_while(&["char", "note"]);
+                    let (number, rest) = split_first_word(&following_text());
+                    let sid = format!(
+                        "{} {}:{}",
+                        parser.book_code,
+                        parser.chapter.clone().unwrap_or("".to_string()).rsplit(' ').next().unwrap_or(""),
+                        number
+                    );
+                    let mut verse = element("verse", "v");
+                    verse.attributes.push(("number".to_string(), number));
+                    verse.attributes.push(("sid".to_string(), sid.clone()));
+                    if parser.stack.len() == 1 {
+                        parser.stack.push(element("para", "p"));
+                    }
+                    parser.push_leaf(verse);
+                    parser.verse = Some(sid);
+                    parser.push_text(&rest);
+                } else if is_milestone(marker) {
+                    let mut milestone = element("ms", marker);
+                    if let Some(UsfmToken::Text(t)) = tokens.get(index) {
+                        if let Some((_, attribute_string)) = t.split_once("|") {
+                            milestone.attributes = parse_attributes(marker, attribute_string);
+                        }
+                        index += 1;
+                    }
+                    if let Some(UsfmToken::MilestoneEnd) = tokens.get(index) {
+                        index += 1;
+                    }
+                    parser.push_leaf(milestone);
+                } else if NOTE_MARKERS.contains(&marker) {
+                    let (caller, rest) = split_first_word(&following_text());
+                    let mut note = element("note", marker);
+                    note.attributes.push(("caller".to_string(), caller));
+                    parser.stack.push(note);
+                    parser.push_text(&rest);
+                } else if nested || CHAR_MARKERS.contains(&marker) {
+                    if NOTE_CHAR_MARKERS.contains(&marker) && !nested {
+                        while parser.top().kind == "char"
+                            && NOTE_CHAR_MARKERS.contains(&parser.top().marker.as_str())
+                        {
+                            parser.pop();
+                        }
+                    }
+                    parser.stack.push(element("char", marker));
+                    let text = following_text();
+                    parser.push_text(&text);
+                } else if marker == "tr" {
+                    parser.end_verse_if_heading(marker);
+                    while parser.stack.len() > 1 && parser.top().kind != "table" {
+                        parser.pop();
+                    }
+                    if parser.stack.len() == 1 {
+                        parser.stack.push(element("table", ""));
+                    }
+                    parser.stack.push(element("table:row", "tr"));
+                    let text = following_text();
+                    parser.push_text(&text);
+                } else if is_table_cell(marker) {
+                    while parser.stack.len() > 1 && parser.top().kind != "table:row" {
+                        parser.pop();
+                    }
+                    let mut cell = element("table:cell", marker);
+                    let align = match marker_base(marker) {
+                        "thr" | "tcr" => "end",
+                        "thc" | "tcc" => "center",
+                        _ => "start",
+                    };
+                    cell.attributes.push(("align".to_string(), align.to_string()));
+                    parser.stack.push(cell);
+                    let text = following_text();
+                    parser.push_text(&text);
+                } else {
+                    parser.end_verse_if_heading(marker);
+                    parser.pop_to_top_level();
+                    parser.stack.push(element("para", marker));
+                    let text = following_text();
+                    parser.push_text(&text);
+                }
+            }
+        }
+    }
+    parser.end_chapter();
+    parser.pop_to_top_level();
+    parser.stack.remove(0)
+}
+
+impl UsfmParser {
+    fn end_verse_if_heading(&mut self, marker: &str) {
+        if HEADING_MARKERS.contains(&marker_base(marker)) {
+            self.end_verse();
+        }
+    }
+}
+
+fn usj_node(node: &UsfmNode) -> Option<Value> {
+    match node {
+        UsfmNode::Text(t) => Some(Value::String(t.clone())),
+        UsfmNode::Element(e) => {
+            // USJ has no end milestones for chapters and verses
+            if e.attributes.iter().any(|(k, _)| k == "eid") {
+                return None;
+            }
+            let mut ob = Map::new();
+            ob.insert("type".to_string(), Value::String(e.kind.clone()));
+            if !e.marker.is_empty() {
+                ob.insert("marker".to_string(), Value::String(e.marker.clone()));
+            }
+            for (key, value) in e.attributes.iter() {
+                ob.insert(key.clone(), Value::String(value.clone()));
+            }
+            if !["chapter", "verse", "ms"].contains(&e.kind.as_str()) {
+                ob.insert(
+                    "content".to_string(),
+                    Value::Array(e.content.iter().filter_map(usj_node).collect()),
+                );
+            }
+            Some(Value::Object(ob))
+        }
+    }
+}
+
+/// Converts a USFM book to USJ.
+pub(crate) fn usfm_to_usj(usfm: &str) -> Value {
+    let root = parse_usfm(usfm);
+    json!({
+        "type": "USJ",
+        "version": USJ_VERSION,
+        "content": root.content.iter().filter_map(usj_node).collect::<Vec<Value>>()
+    })
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+}
+
+fn write_usx_node(node: &UsfmNode, out: &mut String) {
+    match node {
+        UsfmNode::Text(t) => out.push_str(&xml_escape(t)),
+        UsfmNode::Element(e) => {
+            let tag = match e.kind.as_str() {
+                "table:row" => "row",
+                "table:cell" => "cell",
+                k => k,
+            };
+            let is_end = e.attributes.iter().any(|(k, _)| k == "eid");
+            let mut attributes = String::new();
+            if !e.marker.is_empty() && !is_end {
+                attributes.push_str(&format!(" style=\"{}\"", xml_escape(&e.marker)));
+            }
+            for (key, value) in e.attributes.iter() {
+                attributes.push_str(&format!(" {}=\"{}\"", key, xml_escape(value)));
+            }
+            let block = ["book", "chapter", "para", "table", "table:row"].contains(&e.kind.as_str());
+            if block {
+                out.push_str("\n  ");
+            }
+            if e.content.is_empty() && e.kind != "para" && e.kind != "book" {
+                out.push_str(&format!("<{}{} />", tag, attributes));
+            } else {
+                out.push_str(&format!("<{}{}>", tag, attributes));
+                for child in e.content.iter() {
+                    write_usx_node(child, out);
+                }
+                out.push_str(&format!("</{}>", tag));
+            }
+        }
+    }
+}
+
+/// Converts a USFM book to USX.
+pub(crate) fn usfm_to_usx(usfm: &str) -> String {
+    let root = parse_usfm(usfm);
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<usx version=\"{}\">",
+        USJ_VERSION
+    );
+    for node in root.content.iter() {
+        write_usx_node(node, &mut out);
+    }
+    out.push_str("\n</usx>\n");
+    out
+}
+
+/// Converts the USFM ingredients of the repo at the given path to USX or USJ, optionally for one book, writing them under the destination directory with the same relative paths.
+/// Returns the relative paths of the files written.
+pub(crate) fn export_usfm_ingredients(
+    repo_path: &String,
+    format: &str,
+    book: &Option<String>,
+    destination: &String,
+) -> Result<Vec<String>, PankosmiaError> {
+    if format != "usx" && format != "usj" {
+        return Err(PankosmiaError(format!("Unknown export format '{}'", format)));
+    }
+    let path_to_ingredients = format!("{}{}ingredients", repo_path, os_slash_str());
+    let mut written = vec![];
+    for entry in WalkDir::new(&path_to_ingredients).sort_by_file_name() {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => return Err(PankosmiaError(format!("Could not read ingredients: {}", e))),
+        };
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("usfm") {
+            continue;
+        }
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_string();
+        if let Some(b) = book {
+            if stem.to_uppercase() != b.to_uppercase() {
+                continue;
+            }
+        }
+        let usfm = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => return Err(PankosmiaError(format!("Could not read {}: {}", path.display(), e))),
+        };
+        let converted = match format {
+            "usx" => usfm_to_usx(&usfm),
+            _ => serde_json::to_string_pretty(&usfm_to_usj(&usfm)).unwrap(),
+        };
+        let relative_path = path
+            .strip_prefix(&path_to_ingredients)
+            .unwrap()
+            .with_extension(format)
+            .display()
+            .to_string()
+            .replace("\\", "/");
+        let target_path = std::path::Path::new(destination).join(&relative_path);
+        if let Some(parent) = target_path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(PankosmiaError(format!("Could not create export directory: {}", e)));
+            }
+        }
+        if let Err(e) = std::fs::write(&target_path, converted) {
+            return Err(PankosmiaError(format!("Could not write {}: {}", relative_path, e)));
+        }
+        written.push(relative_path);
+    }
+    if written.is_empty() {
+        return Err(PankosmiaError("No matching USFM ingredients found".to_string()));
+    }
+    Ok(written)
+}
+
+#[derive(Debug, Clone)]
+enum XmlToken {
+    Open { name: String, attributes: Vec<(String, String)>, empty: bool },
+    Close,
+    Text(String),
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn tokenize_xml(xml: &str) -> Vec<XmlToken> {
+    let mut tokens = vec![];
+    let mut last_end = 0;
+    for captures in XML_TAG_RE.captures_iter(xml) {
+        let whole = captures.get(0).unwrap();
+        if whole.start() > last_end {
+            tokens.push(XmlToken::Text(xml_unescape(&xml[last_end..whole.start()])));
+        }
+        last_end = whole.end();
+        let name = match captures.get(2) {
+            Some(n) => n.as_str().to_string(),
+            None => continue,
+        };
+        if &captures[1] == "/" {
+            tokens.push(XmlToken::Close);
+        } else {
+            let attributes = XML_ATTRIBUTE_RE
+                .captures_iter(&captures[3])
+                .map(|c| {
+                    let value = c.get(2).or(c.get(3)).map(|v| v.as_str()).unwrap_or("");
+                    (c[1].to_string(), xml_unescape(value))
+                })
+                .collect();
+            tokens.push(XmlToken::Open { name, attributes, empty: &captures[4] == "/" });
+        }
+    }
+    if last_end < xml.len() {
+        tokens.push(XmlToken::Text(xml_unescape(&xml[last_end..])));
+    }
+    tokens
+}
+
+fn parse_xml(xml: &str) -> UsfmElement {
+    let mut stack = vec![element("document", "")];
+    for token in tokenize_xml(xml) {
+        match token {
+            XmlToken::Text(t) => stack.last_mut().unwrap().content.push(UsfmNode::Text(t)),
+            XmlToken::Open { name, attributes, empty } => {
+                let mut e = element(&name, "");
+                for (key, value) in attributes {
+                    if key == "style" {
+                        e.marker = value;
+                    } else {
+                        e.attributes.push((key, value));
+                    }
+                }
+                if empty {
+                    stack.last_mut().unwrap().content.push(UsfmNode::Element(e));
+                } else {
+                    stack.push(e);
+                }
+            }
+            XmlToken::Close => {
+                if stack.len() > 1 {
+                    let closed = stack.pop().unwrap();
+                    stack.last_mut().unwrap().content.push(UsfmNode::Element(closed));
+                }
+            }
+        }
+    }
+    while stack.len() > 1 {
+        let closed = stack.pop().unwrap();
+        stack.last_mut().unwrap().content.push(UsfmNode::Element(closed));
+    }
+    stack.remove(0)
+}
+
+/// Returns the value of an attribute of an element.
+pub(crate) fn attribute<'a>(e: &'a UsfmElement, key: &str) -> Option<&'a str> {
+    e.attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn usfm_attribute_string(e: &UsfmElement) -> String {
+    let attributes: Vec<String> = e
+        .attributes
+        .iter()
+        .filter(|(k, _)| !["closed", "sid", "eid", "vid", "status"].contains(&k.as_str()))
+        .map(|(k, v)| format!("{}=\"{}\"", k, v))
+        .collect();
+    if attributes.is_empty() {
+        "".to_string()
+    } else {
+        format!("|{}", attributes.join(" "))
+    }
+}
+
+fn write_usfm_node(node: &UsfmNode, in_char: bool, in_note: bool, out: &mut String) {
+    let e = match node {
+        UsfmNode::Text(t) => {
+            out.push_str(&SPACE_RE.replace_all(t, " "));
+            return;
+        }
+        UsfmNode::Element(e) => e,
+    };
+    let write_content = |out: &mut String, in_char: bool, in_note: bool| {
+        for child in e.content.iter() {
+            write_usfm_node(child, in_char, in_note, out);
+        }
+    };
+    match e.kind.as_str() {
+        "book" => {
+            out.push_str(&format!("\\id {} ", attribute(e, "code").unwrap_or("")));
+            write_content(out, false, false);
+        }
+        "chapter" => {
+            if let Some(number) = attribute(e, "number") {
+                out.push_str(&format!("\n\\c {}", number));
+            }
+        }
+        "verse" => {
+            if let Some(number) = attribute(e, "number") {
+                if !out.ends_with(|c: char| c.is_whitespace()) {
+                    out.push('\n');
+                }
+                out.push_str(&format!("\\v {} ", number));
+            }
+        }
+        "para" => {
+            out.push_str(&format!("\n\\{} ", e.marker));
+            write_content(out, false, false);
+            while out.ends_with(' ') {
+                out.pop();
+            }
+        }
+        "table" => write_content(out, false, false),
+        "row" => {
+            out.push_str("\n\\tr ");
+            write_content(out, false, false);
+        }
+        "cell" => {
+            out.push_str(&format!("\\{} ", e.marker));
+            write_content(out, false, false);
+        }
+        "note" => {
+            out.push_str(&format!("\\{} {} ", e.marker, attribute(e, "caller").unwrap_or("+")));
+            write_content(out, false, true);
+            out.push_str(&format!("\\{}*", e.marker));
+        }
+        "char" | "figure" | "ref" => {
+            let marker = match e.kind.as_str() {
+                "char" => e.marker.clone(),
+                "figure" => "fig".to_string(),
+                _ => "ref".to_string(),
+            };
+            // Footnote and cross-reference content markers are conventionally left open
+            if in_note && !in_char && NOTE_CHAR_MARKERS.contains(&marker.as_str()) {
+                out.push_str(&format!("\\{} ", marker));
+                write_content(out, false, in_note);
+            } else {
+                let prefix = if in_char { "+" } else { "" };
+                out.push_str(&format!("\\{}{} ", prefix, marker));
+                write_content(out, true, in_note);
+                out.push_str(&format!("{}\\{}{}*", usfm_attribute_string(e), prefix, marker));
+            }
+        }
+        "ms" => out.push_str(&format!("\\{}{}\\*", e.marker, usfm_attribute_string(e).replacen("|", " |", 1))),
+        "optbreak" => out.push_str("//"),
+        "sidebar" => {
+            out.push_str("\n\\esb");
+            write_content(out, false, false);
+            out.push_str("\n\\esbe");
+        }
+        _ => write_content(out, in_char, in_note),
+    }
+}
+
+/// Converts a USX book to USFM.
+pub(crate) fn usx_to_usfm(usx: &str) -> String {
+    let root = parse_xml(usx);
+    let mut out = String::new();
+    for node in root.content.iter() {
+        if let UsfmNode::Element(e) = node {
+            if e.kind == "usx" {
+                for child in e.content.iter() {
+                    if let UsfmNode::Element(_) = child {
+                        write_usfm_node(child, false, false, &mut out);
+                    }
+                }
+            }
+        }
+    }
+    out.trim_start().to_string() + "\n"
+}
@@ -0,0 +1,204 @@
+use crate::structs::{Bcv, PankosmiaError};
+use crate::utils::files::load_json;
+use crate::utils::paths::os_slash_str;
+use regex::Regex;
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+struct VerseRange {
+    book_code: String,
+    chapter: u16,
+    verse: u16,
+    to_verse: u16,
+}
+
+#[derive(Debug, Clone)]
+struct VerseMapping {
+    scheme: VerseRange,
+    original: VerseRange,
+}
+
+/// Verse mappings between one versification and the original (org) versification.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Versification {
+    mappings: Vec<VerseMapping>,
+}
+
+// Reads references like GEN 31:55 and PSA 3:0-8
+fn parse_verse_range(reference: &str) -> Option<VerseRange> {
+    let reference_re = Regex::new(r"^\s*([1-6A-Z]{3})\s+(\d+):(\d+)(?:-(\d+))?\s*$").unwrap();
+    let captures = reference_re.captures(reference)?;
+    let verse = captures[3].parse::<u16>().ok()?;
+    Some(VerseRange {
+        book_code: captures[1].to_string(),
+        chapter: captures[2].parse::<u16>().ok()?,
+        verse,
+        to_verse: match captures.get(4) {
+            Some(v) => v.as_str().parse::<u16>().ok()?,
+            None => verse,
+        },
+    })
+}
+
+impl Versification {
+    /// Reads `mappedVerses` (or Paratext-style `verseMappings`) from versification JSON. Values may be a reference or an array of references.
+    pub(crate) fn from_json(vrs_json: &Value) -> Versification {
+        let mut mappings = vec![];
+        let mapped_verses = match vrs_json["mappedVerses"].as_object() {
+            Some(m) => Some(m),
+            None => vrs_json["verseMappings"].as_object(),
+        };
+        if let Some(mapped) = mapped_verses {
+            for (scheme_reference, original_value) in mapped {
+                let original_reference = match original_value {
+                    Value::String(s) => s.clone(),
+                    Value::Array(a) => match a.first().and_then(|v| v.as_str()) {
+                        Some(s) => s.to_string(),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                if let (Some(scheme), Some(original)) = (
+                    parse_verse_range(scheme_reference),
+                    parse_verse_range(&original_reference),
+                ) {
+                    mappings.push(VerseMapping { scheme, original });
+                }
+            }
+        }
+        Versification { mappings }
+    }
+
+    fn map_verse(
+        &self,
+        book_code: &String,
+        chapter: u16,
+        verse: u16,
+        to_original: bool,
+    ) -> (String, u16, u16) {
+        for mapping in self.mappings.iter() {
+            let (from, to) = match to_original {
+                true => (&mapping.scheme, &mapping.original),
+                false => (&mapping.original, &mapping.scheme),
+            };
+            if from.book_code == *book_code
+                && from.chapter == chapter
+                && verse >= from.verse
+                && verse <= from.to_verse
+            {
+                let offset = (verse - from.verse).min(to.to_verse.saturating_sub(to.verse));
+                return (to.book_code.clone(), to.chapter, to.verse + offset);
+            }
+        }
+        (book_code.clone(), chapter, verse)
+    }
+
+    fn map_bcv(&self, bcv: &Bcv, to_original: bool) -> Bcv {
+        let (book_code, chapter, verse) = self.map_verse(&bcv.book_code, bcv.chapter, bcv.verse, to_original);
+        let (to_book_code, to_chapter, to_verse) =
+            self.map_verse(&bcv.book_code, bcv.chapter, bcv.to_verse.max(bcv.verse), to_original);
+        Bcv {
+            book_code: book_code.clone(),
+            chapter,
+            verse,
+            to_verse: match to_book_code == book_code && to_chapter == chapter {
+                true => to_verse.max(verse),
+                false => verse,
+            },
+        }
+    }
+
+    /// Converts a reference in this versification to the original versification.
+    pub(crate) fn map_to_original(&self, bcv: &Bcv) -> Bcv {
+        self.map_bcv(bcv, true)
+    }
+
+    /// Converts a reference in the original versification to this versification.
+    pub(crate) fn map_from_original(&self, bcv: &Bcv) -> Bcv {
+        self.map_bcv(bcv, false)
+    }
+}
+
+/// Converts a reference between versifications, via the original versification.
+pub(crate) fn map_bcv_between(bcv: &Bcv, from: &Versification, to: &Versification) -> Bcv {
+    to.map_from_original(&from.map_to_original(bcv))
+}
+
+/// Returns the path of a versification scheme in the vrs content templates, eg `eng` or `org`, checking that the name is safe to use in a path.
+pub(crate) fn versification_template_path(
+    app_resources_dir: &String,
+    versification_name: &String,
+) -> Result<String, PankosmiaError> {
+    if !Regex::new(r"^[A-Za-z0-9_-]+$").unwrap().is_match(versification_name) {
+        return Err(PankosmiaError(format!(
+            "Bad versification name '{}'",
+            versification_name
+        )));
+    }
+    Ok(format!(
+        "{}{}templates{}content_templates{}vrs{}{}.json",
+        app_resources_dir,
+        os_slash_str(),
+        os_slash_str(),
+        os_slash_str(),
+        os_slash_str(),
+        versification_name.to_lowercase(),
+    ))
+}
+
+/// Loads a versification scheme from the vrs content templates, eg `eng` or `org`.
+pub(crate) fn named_versification(
+    app_resources_dir: &String,
+    versification_name: &String,
+) -> Result<Versification, PankosmiaError> {
+    let path_to_versification = versification_template_path(app_resources_dir, versification_name)?;
+    match load_json(&path_to_versification) {
+        Ok(j) => Ok(Versification::from_json(&j)),
+        Err(e) => Err(PankosmiaError(format!(
+            "Could not load versification '{}': {}",
+            versification_name, e
+        ))),
+    }
+}
+
+/// Loads the versification of a repo from `ingredients/vrs.json`, if there is one.
+pub(crate) fn repo_versification(repo_path: &String) -> Option<Versification> {
+    let path_to_versification = format!(
+        "{}{}ingredients{}vrs.json",
+        repo_path,
+        os_slash_str(),
+        os_slash_str()
+    );
+    match load_json(&path_to_versification) {
+        Ok(j) => Some(Versification::from_json(&j)),
+        Err(_) => None,
+    }
+}
+
+/// Returns the maxVerses object of the versification of a repo, from `ingredients/vrs.json`, or Null.
+pub(crate) fn repo_max_verses(repo_path: &str) -> Value {
+    let path_to_versification = format!(
+        "{}{}ingredients{}vrs.json",
+        repo_path,
+        os_slash_str(),
+        os_slash_str()
+    );
+    match load_json(&path_to_versification) {
+        Ok(j) => j["maxVerses"].clone(),
+        Err(_) => Value::Null,
+    }
+}
+
+/// Returns the number of verses in each chapter of a book, from a maxVerses object whose values are arrays of numbers as strings.
+pub(crate) fn book_max_verses(max_verses: &Value, book_code: &str) -> Vec<u16> {
+    match max_verses[book_code].as_array() {
+        Some(chapters) => chapters
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.parse::<u16>().unwrap_or(0),
+                _ => v.as_u64().unwrap_or(0) as u16,
+            })
+            .collect(),
+        None => vec![],
+    }
+}
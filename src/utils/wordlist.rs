@@ -0,0 +1,163 @@
+use crate::structs::{PankosmiaError, Wordlist};
+use crate::utils::burrito::metadata_language_tag;
+use crate::utils::files::write_file_atomically;
+use crate::utils::metadata_index::local_repo_paths;
+use crate::utils::paths::os_slash_str;
+use crate::utils::time::utc_now_timestamp_string;
+use crate::utils::usfm::{usfm_plain_text, usfm_verses, USFM_WORD_PATTERN};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+static USFM_WORD_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(USFM_WORD_PATTERN).unwrap());
+
+#[derive(Serialize)]
+pub(crate) struct SuspiciousWord {
+    word: String,
+    reason: String,
+    count: usize,
+    references: Vec<(u16, u16)>,
+}
+
+/// Path of the wordlist of a burrito, which is an ingredient so that it is shared with the rest of the project.
+pub(crate) fn wordlist_path(repo_path: &String) -> String {
+    format!("{}{}ingredients{}wordlist.json", repo_path, os_slash_str(), os_slash_str())
+}
+
+/// Reads the wordlist of a burrito, or None if it has not been built. A wordlist that cannot be read or parsed is an error,
+/// so that it is never overwritten.
+pub(crate) fn read_wordlist(repo_path: &String) -> Result<Option<Wordlist>, PankosmiaError> {
+    match std::fs::read_to_string(wordlist_path(repo_path)) {
+        Ok(s) => match serde_json::from_str(&s) {
+            Ok(w) => Ok(Some(w)),
+            Err(e) => Err(PankosmiaError(format!("Could not parse wordlist: {}", e))),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(PankosmiaError(format!("Could not read wordlist: {}", e))),
+    }
+}
+
+pub(crate) fn write_wordlist(repo_path: &String, wordlist: &Wordlist) -> Result<(), PankosmiaError> {
+    let wordlist_string = match serde_json::to_string_pretty(wordlist) {
+        Ok(s) => s,
+        Err(e) => return Err(PankosmiaError(format!("Could not make wordlist as JSON: {}", e))),
+    };
+    match write_file_atomically(&wordlist_path(repo_path), &wordlist_string) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(PankosmiaError(format!("Could not write wordlist: {}", e))),
+    }
+}
+
+/// Returns the language tag of a burrito from its metadata.
+pub(crate) fn repo_language(repo_path: &String) -> Result<String, PankosmiaError> {
+    let metadata: Value = match std::fs::read_to_string(format!("{}{}metadata.json", repo_path, os_slash_str()))
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(e) => return Err(PankosmiaError(format!("Could not read metadata: {}", e))),
+    };
+    Ok(metadata_language_tag(&metadata))
+}
+
+// Lower-cased words of USFM text, without numbers
+fn usfm_words(usfm: &str) -> Vec<String> {
+    USFM_WORD_RE
+        .find_iter(&usfm_plain_text(usfm))
+        .map(|w| w.as_str().to_lowercase())
+        .filter(|w| !w.chars().all(|c| c.is_numeric()))
+        .collect()
+}
+
+/// Counts the words of the USFM ingredients of every local burrito in a language, keeping the correct and incorrect words of an existing wordlist.
+pub(crate) fn build_wordlist(repo_dir: &String, language: &String, existing: Option<Wordlist>) -> Wordlist {
+    let mut words: BTreeMap<String, usize> = BTreeMap::new();
+    for repo_path in local_repo_paths(repo_dir, &None) {
+        let full_repo_path = format!("{}{}{}", repo_dir, os_slash_str(), repo_path);
+        if repo_language(&full_repo_path).ok().as_ref() != Some(language) {
+            continue;
+        }
+        let path_to_ingredients = format!("{}{}ingredients", full_repo_path, os_slash_str());
+        for entry in WalkDir::new(&path_to_ingredients).into_iter().filter_map(|e| e.ok()) {
+            if !entry.path().is_file() || entry.path().extension().and_then(|e| e.to_str()) != Some("usfm") {
+                continue;
+            }
+            if let Ok(usfm) = std::fs::read_to_string(entry.path()) {
+                for word in usfm_words(&usfm) {
+                    *words.entry(word).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    let existing = existing.unwrap_or_default();
+    Wordlist {
+        language: language.clone(),
+        built: Some(utc_now_timestamp_string()),
+        words,
+        correct: existing.correct,
+        incorrect: existing.incorrect,
+    }
+}
+
+/// Marks a word as `correct`, `incorrect` or, to forget an earlier decision, `unknown`.
+pub(crate) fn mark_word(wordlist: &mut Wordlist, word: &str, status: &str) -> Result<(), PankosmiaError> {
+    let word = word.trim().to_lowercase();
+    if word.is_empty() {
+        return Err(PankosmiaError("Word may not be empty".to_string()));
+    }
+    wordlist.correct.retain(|w| *w != word);
+    wordlist.incorrect.retain(|w| *w != word);
+    match status {
+        "correct" => wordlist.correct.push(word),
+        "incorrect" => wordlist.incorrect.push(word),
+        "unknown" => (),
+        other => return Err(PankosmiaError(format!("Unknown word status '{}'", other))),
+    }
+    wordlist.correct.sort();
+    wordlist.incorrect.sort();
+    Ok(())
+}
+
+/// Returns words of a USFM book, or of one chapter or verse, that are marked incorrect, or that are not marked correct and
+/// occur fewer than *min_count* times in the wordlist. References are chapter and verse.
+pub(crate) fn suspicious_words(
+    usfm: &str,
+    wordlist: &Wordlist,
+    chapter: Option<u16>,
+    verse: Option<u16>,
+    min_count: usize,
+) -> Vec<SuspiciousWord> {
+    let mut found: BTreeMap<String, Vec<(u16, u16)>> = BTreeMap::new();
+    for usfm_verse in usfm_verses(usfm) {
+        if chapter.is_some_and(|c| c != usfm_verse.chapter)
+            || verse.is_some_and(|v| v < usfm_verse.verse || v > usfm_verse.to_verse.max(usfm_verse.verse))
+        {
+            continue;
+        }
+        for word in usfm_words(&usfm_verse.usfm) {
+            found.entry(word).or_default().push((usfm_verse.chapter, usfm_verse.verse));
+        }
+    }
+    let mut suspicious = vec![];
+    for (word, mut references) in found {
+        let count = wordlist.words.get(&word).copied().unwrap_or(0);
+        let reason = if wordlist.incorrect.contains(&word) {
+            "incorrect"
+        } else if !wordlist.correct.contains(&word) && count < min_count {
+            "rare"
+        } else {
+            continue;
+        };
+        references.dedup();
+        suspicious.push(SuspiciousWord {
+            word,
+            reason: reason.to_string(),
+            count,
+            references,
+        });
+    }
+    suspicious
+}